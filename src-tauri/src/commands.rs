@@ -1,7 +1,12 @@
 use crate::db::Database;
+use crate::indexing::{
+    collect_plate_tasks, ensure_tag, index_note_anchors, normalize_tag_path, plate_plain_text,
+    split_block_id, write_note_task_rows, NoteAnchor,
+};
 use rusqlite::params;
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
 use uuid::Uuid;
 
 // ─── Data Types ──────────────────────────────────────────
@@ -60,10 +65,13 @@ pub struct NoteTreeItem {
 #[derive(Debug, Serialize)]
 pub struct SearchResultItem {
     pub id: String,
+    pub kind: String, // "note" or "snippet"
     pub title: String,
     pub snippet: String,
     #[serde(rename = "noteId")]
-    pub note_id: String,
+    pub note_id: Option<String>,
+    #[serde(skip)]
+    pub rank: f64,
 }
 
 #[derive(Debug, Serialize)]
@@ -78,10 +86,19 @@ pub struct RecentNote {
 #[derive(Debug, Serialize)]
 pub struct TagInfo {
     pub id: String,
+    /// Full path, e.g. `project/alpha`.
     pub name: String,
+    /// Last path segment, e.g. `alpha`.
+    pub label: String,
+    #[serde(rename = "parentId")]
+    pub parent_id: Option<String>,
     pub color: Option<String>,
     #[serde(rename = "noteCount")]
     pub note_count: i64,
+    /// Distinct notes tagged with this tag or any of its descendants.
+    #[serde(rename = "totalCount")]
+    pub total_count: i64,
+    pub children: Vec<TagInfo>,
 }
 
 #[derive(Debug, Serialize)]
@@ -116,21 +133,38 @@ pub struct BacklinkItem {
     pub id: String,
     pub title: String,
     pub emoji: Option<String>,
+    #[serde(rename = "linkType")]
+    pub link_type: String, // "link" or "embed"
+    pub anchors: Vec<BacklinkAnchor>,
+    /// Text of the blocks in the source note where the link appears
+    pub contexts: Vec<String>,
 }
 
+/// A `#Heading` or `#^blockid` target of a backlink, resolved against the
+/// linked note's current anchors.
 #[derive(Debug, Serialize)]
+pub struct BacklinkAnchor {
+    pub anchor: String,
+    pub found: bool,
+    pub kind: Option<String>,
+    pub text: Option<String>,
+    pub position: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct GraphNode {
     pub id: String,
     pub title: String,
     pub emoji: Option<String>,
+    pub kind: String, // "note" or "tag"; tag node ids are "tag:<tag id>"
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 pub struct GraphEdge {
     pub source: String,
     pub target: String,
     #[serde(rename = "edgeType")]
-    pub edge_type: String, // "wikilink" or "tag"
+    pub edge_type: String, // "wikilink", "embed" or "tag"
 }
 
 #[derive(Debug, Serialize)]
@@ -143,6 +177,7 @@ pub struct GraphData {
 pub struct NoteTitleItem {
     pub id: String,
     pub title: String,
+    pub aliases: Vec<String>,
 }
 
 // ─── SQL Proxy ───────────────────────────────────────────
@@ -257,15 +292,22 @@ const NOTE_COLUMNS: &str = "id, title, content, plain_text, emoji, parent_id, is
 // ─── Note Commands ───────────────────────────────────────
 
 #[tauri::command]
-pub fn create_note(db: State<Database>, parent_id: Option<String>) -> Result<String, String> {
+pub fn create_note(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    parent_id: Option<String>,
+) -> Result<String, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let id = Uuid::new_v4().to_string();
 
-    conn.execute(
-        "INSERT INTO notes (id, title, content, parent_id) VALUES (?1, ?2, ?3, ?4)",
-        params![id, "Untitled", "[]", parent_id],
-    )
-    .map_err(|e| e.to_string())?;
+    with_graph_delta(&app, &conn, std::slice::from_ref(&id), || {
+        conn.execute(
+            "INSERT INTO notes (id, title, content, parent_id) VALUES (?1, ?2, ?3, ?4)",
+            params![id, "Untitled", "[]", parent_id],
+        )
+        .map_err(|e| e.to_string())?;
+        resolve_pending_links(&conn, &id).map_err(|e| e.to_string())
+    })?;
 
     Ok(id)
 }
@@ -281,6 +323,7 @@ pub fn get_note(db: State<Database>, note_id: String) -> Result<NoteData, String
 
 #[tauri::command]
 pub fn save_note_content(
+    app: tauri::AppHandle,
     db: State<Database>,
     note_id: String,
     content: String,
@@ -291,13 +334,16 @@ pub fn save_note_content(
 
     let word_count = plain_text.split_whitespace().count() as i64;
 
-    conn.execute(
-        "UPDATE notes SET content = ?1, title = ?2, plain_text = ?3, word_count = ?4, updated_at = unixepoch() WHERE id = ?5",
-        params![content, title, plain_text, word_count, note_id],
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(())
+    with_graph_delta(&app, &conn, std::slice::from_ref(&note_id), || {
+        conn.execute(
+            "UPDATE notes SET content = ?1, title = ?2, plain_text = ?3, word_count = ?4, updated_at = unixepoch() WHERE id = ?5",
+            params![content, title, plain_text, word_count, note_id],
+        )
+        .map_err(|e| e.to_string())?;
+        index_note_anchors(&conn, &note_id, &content).map_err(|e| e.to_string())?;
+        index_note_tasks(&conn, &note_id, &content).map_err(|e| e.to_string())?;
+        resolve_pending_links(&conn, &note_id).map_err(|e| e.to_string())
+    })
 }
 
 #[tauri::command]
@@ -346,21 +392,35 @@ pub fn get_most_recent_note(db: State<Database>) -> Result<Option<NoteData>, Str
     }
 }
 
+/// Turn free text into an FTS5 prefix phrase query, or `None` if nothing
+/// searchable is left after sanitizing.
+fn fts_phrase_prefix(query: &str) -> Option<String> {
+    let sanitized = query.replace('"', "").trim().to_string();
+    if sanitized.is_empty() {
+        None
+    } else {
+        Some(format!("\"{}\"*", sanitized))
+    }
+}
+
 #[tauri::command]
-pub fn search_notes(db: State<Database>, query: String) -> Result<Vec<SearchResultItem>, String> {
+pub fn search_notes(
+    db: State<Database>,
+    query: String,
+    include_snippets: Option<bool>,
+) -> Result<Vec<SearchResultItem>, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
     // Sanitize query for FTS5
-    let sanitized = query.replace('"', "").trim().to_string();
-    if sanitized.is_empty() {
+    let Some(fts_query) = fts_phrase_prefix(&query) else {
         return Ok(vec![]);
-    }
-    let fts_query = format!("\"{}\"*", sanitized);
+    };
 
     let mut stmt = conn
         .prepare(
             "SELECT n.id, highlight(notes_fts, 0, '<mark>', '</mark>') as title,
-                    snippet(notes_fts, 1, '<mark>', '</mark>', '...', 32) as snippet
+                    snippet(notes_fts, 1, '<mark>', '</mark>', '...', 32) as snippet,
+                    rank
              FROM notes_fts
              JOIN notes n ON n.rowid = notes_fts.rowid
              WHERE notes_fts MATCH ?1 AND n.is_trashed = 0
@@ -369,38 +429,107 @@ pub fn search_notes(db: State<Database>, query: String) -> Result<Vec<SearchResu
         )
         .map_err(|e| e.to_string())?;
 
-    let results = stmt
+    let mut results: Vec<SearchResultItem> = stmt
         .query_map(params![fts_query], |row| {
             let id: String = row.get(0)?;
             Ok(SearchResultItem {
-                note_id: id.clone(),
+                note_id: Some(id.clone()),
                 id,
+                kind: "note".to_string(),
                 title: row.get(1)?,
                 snippet: row.get(2)?,
+                rank: row.get(3)?,
             })
         })
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
+    let fts_count = results.len();
+
+    // Notes reachable by alias rank right behind their FTS equivalents
+    let alias_hits = query_alias_matches(&conn, &query, 5).map_err(|e| e.to_string())?;
+    for hit in alias_hits {
+        if results.iter().any(|r| r.id == hit.note_id) {
+            continue;
+        }
+        results.push(SearchResultItem {
+            note_id: Some(hit.note_id.clone()),
+            id: hit.note_id,
+            kind: "note".to_string(),
+            title: hit.title,
+            snippet: format!("Alias: <mark>{}</mark>", hit.alias),
+            rank: 0.0,
+        });
+    }
+
+    if include_snippets.unwrap_or(false) {
+        let hits = query_snippets_fts(&conn, &query, None, &[], 10).map_err(|e| e.to_string())?;
+
+        // bm25 scores from different tables aren't comparable, so each list
+        // is rescaled against its own best hit before merging. Alias hits
+        // keep their place behind the FTS notes.
+        let fts_ranks: Vec<f64> = results[..fts_count].iter().map(|r| r.rank).collect();
+        let mut note_scores = normalize_fts_ranks(&fts_ranks);
+        let alias_score = note_scores.last().copied().unwrap_or(1.0);
+        note_scores.resize(results.len(), alias_score);
+        let snippet_ranks: Vec<f64> = hits.iter().map(|hit| hit.rank).collect();
+        let snippet_scores = normalize_fts_ranks(&snippet_ranks);
+
+        let mut scored: Vec<(f64, SearchResultItem)> =
+            note_scores.into_iter().zip(results).collect();
+        scored.extend(snippet_scores.into_iter().zip(hits).map(|(score, hit)| {
+            (
+                score,
+                SearchResultItem {
+                    id: hit.snippet.id,
+                    kind: "snippet".to_string(),
+                    title: hit.title_highlight,
+                    snippet: hit.excerpt,
+                    note_id: None,
+                    rank: hit.rank,
+                },
+            )
+        }));
+        // Stable, so notes stay ahead of snippets that score the same
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        results = scored.into_iter().map(|(_, item)| item).take(20).collect();
+    }
 
     Ok(results)
 }
 
 #[tauri::command]
-pub fn delete_note(db: State<Database>, note_id: String) -> Result<(), String> {
+pub fn delete_note(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    note_id: String,
+) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    conn.execute(
-        "UPDATE notes SET is_trashed = 1, trashed_at = unixepoch() WHERE id = ?1",
-        params![note_id],
-    )
-    .map_err(|e| e.to_string())?;
-    // Also trash children
-    conn.execute(
-        "UPDATE notes SET is_trashed = 1, trashed_at = unixepoch() WHERE parent_id = ?1",
-        params![note_id],
-    )
-    .map_err(|e| e.to_string())?;
-    Ok(())
+    let mut affected = vec![note_id.clone()];
+    {
+        let mut stmt = conn
+            .prepare("SELECT id FROM notes WHERE parent_id = ?1 AND is_trashed = 0")
+            .map_err(|e| e.to_string())?;
+        let children = stmt
+            .query_map(params![note_id], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+        affected.extend(children.flatten());
+    }
+
+    with_graph_delta(&app, &conn, &affected, || {
+        conn.execute(
+            "UPDATE notes SET is_trashed = 1, trashed_at = unixepoch() WHERE id = ?1",
+            params![note_id],
+        )
+        .map_err(|e| e.to_string())?;
+        // Also trash children
+        conn.execute(
+            "UPDATE notes SET is_trashed = 1, trashed_at = unixepoch() WHERE parent_id = ?1",
+            params![note_id],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    })
 }
 
 #[tauri::command]
@@ -431,1255 +560,9018 @@ pub fn get_recent_notes(db: State<Database>, limit: Option<i64>) -> Result<Vec<R
     Ok(notes)
 }
 
-// ─── Template Command ────────────────────────────────────
+// ─── Global Search ───────────────────────────────────────
 
-#[tauri::command]
-pub fn create_note_from_template(
-    db: State<Database>,
-    title: String,
-    emoji: String,
-    content: String,
-) -> Result<String, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let id = Uuid::new_v4().to_string();
+#[derive(Debug, Serialize)]
+pub struct GlobalSearchResult {
+    pub kind: String, // "note", "snippet", "flashcard", "tag" or "canvas"
+    pub id: String,
+    pub title: String,
+    pub snippet: String,
+    #[serde(rename = "noteId")]
+    pub note_id: Option<String>,
+    pub emoji: Option<String>,
+    pub score: f64,
+}
 
-    // Extract plain text for FTS
-    let plain_text = extract_plain_text_from_json(&content);
-    let word_count = plain_text.split_whitespace().count() as i64;
+const GLOBAL_SEARCH_KINDS: [&str; 5] = ["note", "snippet", "flashcard", "tag", "canvas"];
 
-    conn.execute(
-        "INSERT INTO notes (id, title, content, emoji, plain_text, word_count) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![id, title, content, emoji, plain_text, word_count],
-    )
-    .map_err(|e| e.to_string())?;
+/// Relative weight of each kind when results are merged into one list.
+fn global_search_weight(kind: &str) -> f64 {
+    match kind {
+        "note" => 1.0,
+        "tag" => 0.9,
+        "snippet" => 0.8,
+        "flashcard" => 0.7,
+        _ => 0.6,
+    }
+}
 
-    Ok(id)
+/// Escape a user string for use inside `LIKE ... ESCAPE '\'`.
+fn like_escape(query: &str) -> String {
+    query
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
 }
 
-/// Simple JSON-based plain text extraction for templates
-fn extract_plain_text_from_json(content: &str) -> String {
-    // Parse the JSON content and extract text nodes
-    if let Ok(nodes) = serde_json::from_str::<Vec<serde_json::Value>>(content) {
-        let mut parts = Vec::new();
-        fn walk(node: &serde_json::Value, parts: &mut Vec<String>) {
-            if let Some(text) = node.get("text").and_then(|t| t.as_str()) {
-                parts.push(text.to_string());
-            }
-            if let Some(children) = node.get("children").and_then(|c| c.as_array()) {
-                for child in children {
-                    walk(child, parts);
-                }
-            }
-        }
-        for node in &nodes {
-            walk(node, &mut parts);
-        }
-        parts.join(" ").trim().to_string()
+fn like_contains_pattern(query: &str) -> String {
+    format!("%{}%", like_escape(query))
+}
+
+/// Score a plain substring hit: exact > prefix > word prefix > anywhere.
+fn text_match_score(text: &str, query: &str) -> f64 {
+    let text = text.to_lowercase();
+    let query = query.to_lowercase();
+    if text == query {
+        1.0
+    } else if text.starts_with(&query) {
+        0.8
+    } else if text
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| word.starts_with(&query))
+    {
+        0.6
     } else {
-        String::new()
+        0.4
     }
 }
 
-// ─── Folder Commands ─────────────────────────────────────
+/// Rescale bm25 ranks (negative, lower is better) so the best hit is 1.0.
+fn normalize_fts_ranks(ranks: &[f64]) -> Vec<f64> {
+    let best = ranks.iter().cloned().fold(0.0_f64, f64::min);
+    ranks
+        .iter()
+        .map(|r| {
+            if best < 0.0 {
+                (r / best).clamp(0.0, 1.0)
+            } else {
+                1.0
+            }
+        })
+        .collect()
+}
 
 #[tauri::command]
-pub fn create_folder(
+pub fn global_search(
     db: State<Database>,
-    name: String,
-    parent_id: Option<String>,
-) -> Result<String, String> {
+    query: String,
+    kinds: Option<Vec<String>>,
+    limit_per_kind: Option<i64>,
+) -> Result<Vec<GlobalSearchResult>, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let id = Uuid::new_v4().to_string();
 
-    conn.execute(
-        "INSERT INTO notes (id, title, is_folder, emoji, parent_id) VALUES (?1, ?2, 1, '📁', ?3)",
-        params![id, name, parent_id],
-    )
-    .map_err(|e| e.to_string())?;
+    let trimmed = query.trim();
+    if trimmed.is_empty() {
+        return Ok(vec![]);
+    }
+    let limit = limit_per_kind.unwrap_or(8);
+    let wanted = |kind: &str| {
+        kinds
+            .as_ref()
+            .map(|k| k.iter().any(|w| w == kind))
+            .unwrap_or(true)
+    };
+    let like = like_contains_pattern(trimmed);
+    let mut results: Vec<GlobalSearchResult> = Vec::new();
+
+    if wanted("note") {
+        if let Some(fts_query) = fts_phrase_prefix(trimmed) {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT n.id, highlight(notes_fts, 0, '<mark>', '</mark>'),
+                            snippet(notes_fts, 1, '<mark>', '</mark>', '...', 24),
+                            n.emoji, rank
+                     FROM notes_fts
+                     JOIN notes n ON n.rowid = notes_fts.rowid
+                     WHERE notes_fts MATCH ?1 AND n.is_trashed = 0 AND n.is_folder = 0
+                     ORDER BY rank
+                     LIMIT ?2",
+                )
+                .map_err(|e| e.to_string())?;
+            let hits: Vec<(String, String, String, Option<String>, f64)> = stmt
+                .query_map(params![fts_query, limit], |row| {
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
+                })
+                .map_err(|e| e.to_string())?
+                .filter_map(|r| r.ok())
+                .collect();
+            let ranks: Vec<f64> = hits.iter().map(|h| h.4).collect();
+            for ((id, title, snippet, emoji, _), score) in
+                hits.into_iter().zip(normalize_fts_ranks(&ranks))
+            {
+                results.push(GlobalSearchResult {
+                    kind: "note".to_string(),
+                    note_id: Some(id.clone()),
+                    id,
+                    title,
+                    snippet,
+                    emoji,
+                    score,
+                });
+            }
+        }
 
-    Ok(id)
+        let alias_hits = query_alias_matches(&conn, trimmed, limit).map_err(|e| e.to_string())?;
+        for hit in alias_hits {
+            if results
+                .iter()
+                .any(|r| r.kind == "note" && r.id == hit.note_id)
+            {
+                continue;
+            }
+            results.push(GlobalSearchResult {
+                kind: "note".to_string(),
+                note_id: Some(hit.note_id.clone()),
+                id: hit.note_id,
+                title: hit.title,
+                snippet: format!("Alias: <mark>{}</mark>", hit.alias),
+                emoji: hit.emoji,
+                score: text_match_score(&hit.alias, trimmed),
+            });
+        }
+    }
+
+    if wanted("snippet") {
+        let hits =
+            query_snippets_fts(&conn, trimmed, None, &[], limit).map_err(|e| e.to_string())?;
+        let ranks: Vec<f64> = hits.iter().map(|h| h.rank).collect();
+        for (hit, score) in hits.into_iter().zip(normalize_fts_ranks(&ranks)) {
+            results.push(GlobalSearchResult {
+                kind: "snippet".to_string(),
+                id: hit.snippet.id,
+                title: hit.title_highlight,
+                snippet: hit.excerpt,
+                note_id: None,
+                emoji: None,
+                score,
+            });
+        }
+    }
+
+    if wanted("flashcard") {
+        let mut stmt = conn
+            .prepare(
+                "SELECT f.id, f.note_id, f.question, f.answer
+                 FROM flashcards f
+                 JOIN notes n ON n.id = f.note_id AND n.is_trashed = 0
+                 WHERE f.question LIKE ?1 ESCAPE '\\' OR f.answer LIKE ?1 ESCAPE '\\'
+                 LIMIT ?2",
+            )
+            .map_err(|e| e.to_string())?;
+        let hits: Vec<(String, String, String, String)> = stmt
+            .query_map(params![like, limit], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        for (id, note_id, question, answer) in hits {
+            let score =
+                text_match_score(&question, trimmed).max(text_match_score(&answer, trimmed) * 0.8);
+            results.push(GlobalSearchResult {
+                kind: "flashcard".to_string(),
+                id,
+                title: question,
+                snippet: clip_chars(&answer, 120),
+                note_id: Some(note_id),
+                emoji: Some("🃏".to_string()),
+                score,
+            });
+        }
+    }
+
+    if wanted("tag") {
+        // Tag names are stored without the `#` users tend to type
+        let tag_like = like_contains_pattern(trimmed.strip_prefix('#').unwrap_or(trimmed));
+        let mut stmt = conn
+            .prepare(
+                "SELECT t.id, t.name,
+                        (SELECT COUNT(DISTINCT n.id)
+                         FROM tags d
+                         JOIN note_tags nt ON nt.tag_id = d.id
+                         JOIN notes n ON n.id = nt.note_id AND n.is_trashed = 0
+                         WHERE d.id = t.id
+                            OR SUBSTR(d.name, 1, LENGTH(t.name) + 1) = t.name || '/')
+                 FROM tags t
+                 WHERE t.name LIKE ?1 ESCAPE '\\'
+                 LIMIT ?2",
+            )
+            .map_err(|e| e.to_string())?;
+        let hits: Vec<(String, String, i64)> = stmt
+            .query_map(params![tag_like, limit], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        for (id, name, count) in hits {
+            results.push(GlobalSearchResult {
+                kind: "tag".to_string(),
+                id,
+                score: text_match_score(&name, trimmed.trim_start_matches('#')),
+                title: format!("#{}", name),
+                snippet: format!("{} note{}", count, if count == 1 { "" } else { "s" }),
+                note_id: None,
+                emoji: None,
+            });
+        }
+    }
+
+    if wanted("canvas") {
+        let mut stmt = conn
+            .prepare(
+                "SELECT c.id, n.id, n.title, n.emoji
+                 FROM canvas_items c
+                 JOIN notes n ON n.id = c.note_id AND n.is_trashed = 0
+                 WHERE n.title LIKE ?1 ESCAPE '\\'
+                 LIMIT ?2",
+            )
+            .map_err(|e| e.to_string())?;
+        let hits: Vec<(String, String, String, Option<String>)> = stmt
+            .query_map(params![like, limit], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        for (id, note_id, title, emoji) in hits {
+            results.push(GlobalSearchResult {
+                kind: "canvas".to_string(),
+                id,
+                score: text_match_score(&title, trimmed),
+                title,
+                snippet: "On canvas".to_string(),
+                note_id: Some(note_id),
+                emoji,
+            });
+        }
+    }
+
+    for result in &mut results {
+        result.score *= global_search_weight(&result.kind);
+    }
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| {
+                let pos = |k: &str| GLOBAL_SEARCH_KINDS.iter().position(|g| *g == k);
+                pos(&a.kind).cmp(&pos(&b.kind))
+            })
+    });
+
+    Ok(results)
 }
 
-#[tauri::command]
-pub fn move_note(
-    db: State<Database>,
-    note_id: String,
-    new_parent_id: Option<String>,
-) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    conn.execute(
-        "UPDATE notes SET parent_id = ?1 WHERE id = ?2",
-        params![new_parent_id, note_id],
-    )
-    .map_err(|e| e.to_string())?;
-    Ok(())
+// ─── Search History & Suggestions ────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct SearchHistoryItem {
+    pub id: String,
+    pub query: String,
+    #[serde(rename = "resultCount")]
+    pub result_count: i64,
+    #[serde(rename = "searchedAt")]
+    pub searched_at: i64,
+    #[serde(rename = "clickedResultId")]
+    pub clicked_result_id: Option<String>,
+    #[serde(rename = "clickedResultKind")]
+    pub clicked_result_kind: Option<String>,
+    #[serde(rename = "clickedAt")]
+    pub clicked_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchSuggestion {
+    pub text: String,
+    pub source: String, // "history", "tag", "folder" or "term"
+    pub score: f64,
 }
 
+/// Older searches are dropped beyond this many.
+const SEARCH_HISTORY_MAX: i64 = 500;
+
 #[tauri::command]
-pub fn rename_note(db: State<Database>, note_id: String, new_title: String) -> Result<(), String> {
+pub fn record_search(
+    db: State<Database>,
+    query: String,
+    result_count: i64,
+) -> Result<String, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let query = query.trim();
+    if query.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    let id = Uuid::new_v4().to_string();
     conn.execute(
-        "UPDATE notes SET title = ?1, updated_at = unixepoch() WHERE id = ?2",
-        params![new_title, note_id],
+        "INSERT INTO search_history (id, query, result_count) VALUES (?1, ?2, ?3)",
+        params![id, query, result_count],
     )
     .map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-#[tauri::command]
-pub fn toggle_favorite(db: State<Database>, note_id: String) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
     conn.execute(
-        "UPDATE notes SET is_favorite = CASE WHEN is_favorite = 1 THEN 0 ELSE 1 END WHERE id = ?1",
-        params![note_id],
+        "DELETE FROM search_history WHERE rowid NOT IN (
+             SELECT rowid FROM search_history ORDER BY searched_at DESC, rowid DESC LIMIT ?1
+         )",
+        params![SEARCH_HISTORY_MAX],
     )
     .map_err(|e| e.to_string())?;
-    Ok(())
+    Ok(id)
 }
 
 #[tauri::command]
-pub fn toggle_pin(db: State<Database>, note_id: String) -> Result<(), String> {
+pub fn record_search_click(
+    db: State<Database>,
+    search_id: String,
+    result_id: String,
+    result_kind: String,
+) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     conn.execute(
-        "UPDATE notes SET is_pinned = CASE WHEN is_pinned = 1 THEN 0 ELSE 1 END WHERE id = ?1",
-        params![note_id],
+        "UPDATE search_history SET clicked_result_id = ?1, clicked_result_kind = ?2, clicked_at = unixepoch()
+         WHERE id = ?3",
+        params![result_id, result_kind, search_id],
     )
     .map_err(|e| e.to_string())?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn get_favorite_notes(db: State<Database>) -> Result<Vec<FavoriteNote>, String> {
+pub fn get_search_history(
+    db: State<Database>,
+    limit: Option<i64>,
+) -> Result<Vec<SearchHistoryItem>, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let mut stmt = conn
-        .prepare("SELECT id, title, emoji FROM notes WHERE is_favorite = 1 AND is_trashed = 0")
+        .prepare(
+            "SELECT id, query, result_count, searched_at, clicked_result_id, clicked_result_kind, clicked_at
+             FROM search_history
+             ORDER BY searched_at DESC, rowid DESC
+             LIMIT ?1",
+        )
         .map_err(|e| e.to_string())?;
 
-    let notes = stmt
-        .query_map([], |row| {
-            Ok(FavoriteNote {
+    let history = stmt
+        .query_map(params![limit.unwrap_or(50)], |row| {
+            Ok(SearchHistoryItem {
                 id: row.get(0)?,
-                title: row.get(1)?,
-                emoji: row.get(2)?,
+                query: row.get(1)?,
+                result_count: row.get(2)?,
+                searched_at: row.get(3)?,
+                clicked_result_id: row.get(4)?,
+                clicked_result_kind: row.get(5)?,
+                clicked_at: row.get(6)?,
             })
         })
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
 
-    Ok(notes)
+    Ok(history)
 }
 
 #[tauri::command]
-pub fn get_trashed_notes(db: State<Database>) -> Result<Vec<TrashedNote>, String> {
+pub fn clear_search_history(db: State<Database>) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM search_history", [])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Completions for the search box. History and names match the whole input;
+/// vocabulary terms complete the last word being typed.
+#[tauri::command]
+pub fn get_search_suggestions(
+    db: State<Database>,
+    prefix: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchSuggestion>, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let prefix = prefix.trim_start();
+    if prefix.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    let limit = limit.unwrap_or(8);
+    let like_prefix = format!("{}%", like_escape(prefix));
+    let mut suggestions: Vec<SearchSuggestion> = Vec::new();
+
+    // Past searches: frequency, click-through and recency all count
     let mut stmt = conn
         .prepare(
-            "SELECT id, title, emoji, trashed_at FROM notes
-             WHERE is_trashed = 1 ORDER BY trashed_at DESC",
+            "SELECT query, COUNT(*), COUNT(clicked_result_id), MAX(searched_at)
+             FROM search_history
+             WHERE query LIKE ?1 ESCAPE '\\'
+             GROUP BY LOWER(query)
+             ORDER BY MAX(searched_at) DESC
+             LIMIT ?2",
         )
         .map_err(|e| e.to_string())?;
-
-    let notes = stmt
-        .query_map([], |row| {
-            Ok(TrashedNote {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                emoji: row.get(2)?,
-                trashed_at: row.get(3)?,
-            })
+    let history: Vec<(String, i64, i64, i64)> = stmt
+        .query_map(params![like_prefix, limit as i64], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
         })
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
+    let newest = history.iter().map(|h| h.3).max().unwrap_or(0);
+    for (query, count, clicks, searched_at) in history {
+        let age_days = (newest - searched_at).max(0) as f64 / 86400.0;
+        suggestions.push(SearchSuggestion {
+            text: query,
+            source: "history".to_string(),
+            score: 3.0 + (count as f64).ln_1p() + clicks as f64 * 0.5 - age_days.min(30.0) * 0.05,
+        });
+    }
 
-    Ok(notes)
-}
-
-#[tauri::command]
-pub fn permanently_delete_note(db: State<Database>, note_id: String) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    conn.execute(
-        "DELETE FROM wikilinks WHERE source_note_id = ?1 OR target_note_id = ?1",
-        params![note_id],
-    )
-    .map_err(|e| e.to_string())?;
-    conn.execute(
-        "DELETE FROM note_tags WHERE note_id = ?1",
-        params![note_id],
-    )
-    .map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM notes WHERE id = ?1", params![note_id])
-        .map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-#[tauri::command]
-pub fn restore_note(db: State<Database>, note_id: String) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    conn.execute(
-        "UPDATE notes SET is_trashed = 0, trashed_at = NULL WHERE id = ?1",
-        params![note_id],
-    )
-    .map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-// ─── Tag Commands ────────────────────────────────────────
-
-#[tauri::command]
-pub fn get_all_tags(db: State<Database>) -> Result<Vec<TagInfo>, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tag_prefix = format!("{}%", like_escape(prefix.trim_start_matches('#')));
     let mut stmt = conn
         .prepare(
-            "SELECT t.id, t.name, t.color, COUNT(nt.note_id) as note_count
-             FROM tags t
+            "SELECT t.name, COUNT(n.id) FROM tags t
              LEFT JOIN note_tags nt ON nt.tag_id = t.id
              LEFT JOIN notes n ON n.id = nt.note_id AND n.is_trashed = 0
+             WHERE t.name LIKE ?1 ESCAPE '\\'
              GROUP BY t.id
-             ORDER BY t.name",
+             ORDER BY COUNT(n.id) DESC
+             LIMIT ?2",
         )
         .map_err(|e| e.to_string())?;
-
-    let tags = stmt
-        .query_map([], |row| {
-            Ok(TagInfo {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                color: row.get(2)?,
-                note_count: row.get(3)?,
-            })
+    let tags: Vec<(String, i64)> = stmt
+        .query_map(params![tag_prefix, limit as i64], |row| {
+            Ok((row.get(0)?, row.get(1)?))
         })
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
+    for (name, count) in tags {
+        suggestions.push(SearchSuggestion {
+            text: format!("#{}", name),
+            source: "tag".to_string(),
+            score: 2.0 + (count as f64).ln_1p() * 0.5,
+        });
+    }
 
-    Ok(tags)
-}
-
-#[tauri::command]
-pub fn get_note_tags(db: State<Database>, note_id: String) -> Result<Vec<NoteTagInfo>, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let mut stmt = conn
         .prepare(
-            "SELECT t.id, t.name, t.color, nt.source
-             FROM note_tags nt JOIN tags t ON t.id = nt.tag_id
-             WHERE nt.note_id = ?1",
+            "SELECT title FROM notes
+             WHERE is_folder = 1 AND is_trashed = 0 AND title LIKE ?1 ESCAPE '\\'
+             ORDER BY title
+             LIMIT ?2",
         )
         .map_err(|e| e.to_string())?;
-
-    let tags = stmt
-        .query_map(params![note_id], |row| {
-            Ok(NoteTagInfo {
-                tag_id: row.get(0)?,
-                tag_name: row.get(1)?,
-                tag_color: row.get(2)?,
-                source: row.get(3)?,
-            })
-        })
+    let folders: Vec<String> = stmt
+        .query_map(params![like_prefix, limit as i64], |row| row.get(0))
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
+    for title in folders {
+        suggestions.push(SearchSuggestion {
+            text: title,
+            source: "folder".to_string(),
+            score: 2.0,
+        });
+    }
 
-    Ok(tags)
-}
+    // Vocabulary terms are lowercased by the FTS tokenizer; range scans on
+    // `term` are answered from the index without touching every row
+    let head_len = prefix
+        .rfind(char::is_whitespace)
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let (head, last_word) = prefix.split_at(head_len);
+    let last_word = last_word.to_lowercase();
+    if !last_word.is_empty() {
+        let upper = format!("{}\u{10FFFF}", last_word);
+        let mut stmt = conn
+            .prepare(
+                "SELECT term, doc FROM notes_fts_vocab
+                 WHERE term >= ?1 AND term < ?2
+                 ORDER BY doc DESC
+                 LIMIT ?3",
+            )
+            .map_err(|e| e.to_string())?;
+        let terms: Vec<(String, i64)> = stmt
+            .query_map(params![last_word, upper, limit as i64], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        for (term, docs) in terms {
+            if term == last_word {
+                continue;
+            }
+            suggestions.push(SearchSuggestion {
+                text: format!("{}{}", head, term),
+                source: "term".to_string(),
+                score: 1.0 + (docs as f64).ln_1p() * 0.25,
+            });
+        }
+    }
 
-#[tauri::command]
-pub fn sync_inline_tags(
-    db: State<Database>,
-    note_id: String,
-    tag_names: Vec<String>,
-) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    suggestions.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let mut seen = std::collections::HashSet::new();
+    suggestions.retain(|s| seen.insert(s.text.to_lowercase()));
+    suggestions.truncate(limit);
 
-    // Remove old inline tags for this note
-    conn.execute(
-        "DELETE FROM note_tags WHERE note_id = ?1 AND source = 'inline'",
-        params![note_id],
-    )
-    .map_err(|e| e.to_string())?;
+    Ok(suggestions)
+}
 
-    // Insert new inline tags
-    for name in &tag_names {
-        let tag_id = Uuid::new_v4().to_string();
-        conn.execute(
-            "INSERT OR IGNORE INTO tags (id, name) VALUES (?1, ?2)",
-            params![tag_id, name],
-        )
-        .map_err(|e| e.to_string())?;
+// ─── Regex Search ────────────────────────────────────────
 
-        let actual_tag_id: String = conn
-            .query_row("SELECT id FROM tags WHERE name = ?1", params![name], |row| {
-                row.get(0)
-            })
-            .map_err(|e| e.to_string())?;
+#[derive(Debug, Serialize)]
+pub struct TextMatch {
+    pub field: String, // "plainText" or "content"
+    #[serde(rename = "lineNumber")]
+    pub line_number: usize,
+    pub line: String,
+    #[serde(rename = "matchStart")]
+    pub match_start: usize,
+    #[serde(rename = "matchEnd")]
+    pub match_end: usize,
+    pub start: usize,
+    pub end: usize,
+}
 
-        conn.execute(
-            "INSERT OR IGNORE INTO note_tags (note_id, tag_id, source) VALUES (?1, ?2, 'inline')",
-            params![note_id, actual_tag_id],
-        )
-        .map_err(|e| e.to_string())?;
-    }
+#[derive(Debug, Serialize)]
+pub struct RegexSearchNote {
+    #[serde(rename = "noteId")]
+    pub note_id: String,
+    pub title: String,
+    pub emoji: Option<String>,
+    pub matches: Vec<TextMatch>,
+}
 
-    Ok(())
+#[derive(Debug, Serialize)]
+pub struct RegexSearchResponse {
+    pub results: Vec<RegexSearchNote>,
+    #[serde(rename = "totalMatches")]
+    pub total_matches: usize,
+    pub truncated: bool,
+    #[serde(rename = "timedOut")]
+    pub timed_out: bool,
 }
 
-#[tauri::command]
-pub fn add_manual_tag(
-    db: State<Database>,
-    note_id: String,
-    tag_name: String,
-) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let tag_id = Uuid::new_v4().to_string();
+const REGEX_SEARCH_MAX_MATCHES: usize = 500;
+const REGEX_SEARCH_TIMEOUT_MS: u64 = 2000;
+const MATCH_CONTEXT_CHARS: usize = 80;
+
+/// Build the matcher shared by regex search and find & replace.
+/// Literal patterns are escaped so both modes go through the same engine.
+fn build_text_matcher(
+    pattern: &str,
+    is_regex: bool,
+    case_sensitive: bool,
+    whole_word: bool,
+) -> Result<regex::Regex, String> {
+    if pattern.is_empty() {
+        return Err("Search pattern is empty".to_string());
+    }
+    let mut source = if is_regex {
+        pattern.to_string()
+    } else {
+        regex::escape(pattern)
+    };
+    if whole_word {
+        source = format!(r"\b(?:{})\b", source);
+    }
+    regex::RegexBuilder::new(&source)
+        .case_insensitive(!case_sensitive)
+        .size_limit(1 << 20)
+        .build()
+        .map_err(|e| e.to_string())
+}
 
-    conn.execute(
-        "INSERT OR IGNORE INTO tags (id, name) VALUES (?1, ?2)",
-        params![tag_id, tag_name],
-    )
-    .map_err(|e| e.to_string())?;
+/// Collect non-empty matches of `re` in `text` with line context and
+/// character (not byte) offsets. Stops at `limit` matches or the deadline.
+fn collect_text_matches(
+    field: &str,
+    text: &str,
+    re: &regex::Regex,
+    limit: usize,
+    deadline: std::time::Instant,
+) -> (Vec<TextMatch>, bool) {
+    let mut matches = Vec::new();
+    let mut timed_out = false;
+
+    // Running cursor so char offsets are computed in a single pass
+    let mut byte_cursor = 0;
+    let mut char_cursor = 0;
+    let mut line_number = 1;
+    let mut line_start_byte = 0;
+
+    for m in re.find_iter(text) {
+        if matches.len() >= limit {
+            break;
+        }
+        if std::time::Instant::now() >= deadline {
+            timed_out = true;
+            break;
+        }
+        if m.start() == m.end() {
+            continue;
+        }
 
-    let actual_tag_id: String = conn
-        .query_row(
-            "SELECT id FROM tags WHERE name = ?1",
-            params![tag_name],
-            |row| row.get(0),
-        )
-        .map_err(|e| e.to_string())?;
+        let skipped = &text[byte_cursor..m.start()];
+        char_cursor += skipped.chars().count();
+        for (i, b) in skipped.bytes().enumerate() {
+            if b == b'\n' {
+                line_number += 1;
+                line_start_byte = byte_cursor + i + 1;
+            }
+        }
+        byte_cursor = m.start();
+
+        let line_end_byte = text[m.start()..]
+            .find('\n')
+            .map(|i| m.start() + i)
+            .unwrap_or(text.len());
+        let before: Vec<char> = text[line_start_byte..m.start()].chars().collect();
+        let matched = &text[m.start()..m.end().min(line_end_byte).max(m.start())];
+        let after: Vec<char> = text[m.end().min(line_end_byte)..line_end_byte]
+            .chars()
+            .collect();
 
-    conn.execute(
-        "INSERT OR IGNORE INTO note_tags (note_id, tag_id, source) VALUES (?1, ?2, 'manual')",
-        params![note_id, actual_tag_id],
-    )
-    .map_err(|e| e.to_string())?;
+        let before_from = before.len().saturating_sub(MATCH_CONTEXT_CHARS);
+        let after_to = after.len().min(MATCH_CONTEXT_CHARS);
+        let prefix: String = before[before_from..].iter().collect();
+        let suffix: String = after[..after_to].iter().collect();
+        let match_start = prefix.chars().count();
+        let match_end = match_start + matched.chars().count();
+
+        let start = char_cursor;
+        let end = start + m.as_str().chars().count();
+
+        matches.push(TextMatch {
+            field: field.to_string(),
+            line_number,
+            line: format!("{}{}{}", prefix, matched, suffix),
+            match_start,
+            match_end,
+            start,
+            end,
+        });
+    }
 
-    Ok(())
+    (matches, timed_out)
 }
 
 #[tauri::command]
-pub fn remove_tag(
+#[allow(clippy::too_many_arguments)]
+pub fn search_notes_regex(
     db: State<Database>,
-    note_id: String,
-    tag_id: String,
-) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    conn.execute(
-        "DELETE FROM note_tags WHERE note_id = ?1 AND tag_id = ?2",
-        params![note_id, tag_id],
-    )
-    .map_err(|e| e.to_string())?;
-    Ok(())
-}
-
-// ─── Wikilink Commands ───────────────────────────────────
+    pattern: String,
+    is_regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    whole_word: Option<bool>,
+    include_content: Option<bool>,
+    max_matches: Option<usize>,
+    timeout_ms: Option<u64>,
+) -> Result<RegexSearchResponse, String> {
+    let re = build_text_matcher(
+        &pattern,
+        is_regex.unwrap_or(true),
+        case_sensitive.unwrap_or(false),
+        whole_word.unwrap_or(false),
+    )?;
+    let include_content = include_content.unwrap_or(false);
+    let limit = max_matches.unwrap_or(REGEX_SEARCH_MAX_MATCHES);
+    let deadline = std::time::Instant::now()
+        + std::time::Duration::from_millis(timeout_ms.unwrap_or(REGEX_SEARCH_TIMEOUT_MS));
 
-#[tauri::command]
-pub fn sync_wikilinks(
-    db: State<Database>,
-    note_id: String,
-    target_titles: Vec<String>,
-) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, emoji, COALESCE(plain_text, ''), COALESCE(content, '')
+             FROM notes WHERE is_trashed = 0 AND is_folder = 0
+             ORDER BY updated_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
 
-    // Remove old wikilinks from this note
-    conn.execute(
-        "DELETE FROM wikilinks WHERE source_note_id = ?1",
-        params![note_id],
-    )
-    .map_err(|e| e.to_string())?;
+    let mut response = RegexSearchResponse {
+        results: vec![],
+        total_matches: 0,
+        truncated: false,
+        timed_out: false,
+    };
 
-    // Insert new wikilinks
-    for title in &target_titles {
-        // Find note by title (case-insensitive)
-        let target_id: Option<String> = conn
-            .query_row(
-                "SELECT id FROM notes WHERE LOWER(title) = LOWER(?1) AND is_trashed = 0 AND id != ?2 LIMIT 1",
-                params![title, note_id],
-                |row| row.get(0),
-            )
-            .ok();
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        if response.total_matches >= limit {
+            response.truncated = true;
+            break;
+        }
+        if std::time::Instant::now() >= deadline {
+            response.timed_out = true;
+            break;
+        }
 
-        if let Some(tid) = target_id {
-            conn.execute(
-                "INSERT OR IGNORE INTO wikilinks (source_note_id, target_note_id) VALUES (?1, ?2)",
-                params![note_id, tid],
-            )
-            .map_err(|e| e.to_string())?;
+        let plain_text: String = row.get(3).map_err(|e| e.to_string())?;
+        let (mut matches, timed_out) = collect_text_matches(
+            "plainText",
+            &plain_text,
+            &re,
+            limit - response.total_matches,
+            deadline,
+        );
+        response.timed_out |= timed_out;
+
+        if include_content && !response.timed_out {
+            let content: String = row.get(4).map_err(|e| e.to_string())?;
+            let (content_matches, timed_out) = collect_text_matches(
+                "content",
+                &content,
+                &re,
+                limit - response.total_matches - matches.len(),
+                deadline,
+            );
+            matches.extend(content_matches);
+            response.timed_out |= timed_out;
+        }
+
+        if !matches.is_empty() {
+            response.total_matches += matches.len();
+            response.results.push(RegexSearchNote {
+                note_id: row.get(0).map_err(|e| e.to_string())?,
+                title: row.get(1).map_err(|e| e.to_string())?,
+                emoji: row.get(2).map_err(|e| e.to_string())?,
+                matches,
+            });
+        }
+        if response.timed_out {
+            break;
         }
     }
 
-    Ok(())
-}
+    if response.total_matches >= limit {
+        response.truncated = true;
+    }
 
-#[tauri::command]
-pub fn get_backlinks(db: State<Database>, note_id: String) -> Result<Vec<BacklinkItem>, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn
-        .prepare(
-            "SELECT n.id, n.title, n.emoji FROM wikilinks w
-             JOIN notes n ON n.id = w.source_note_id
-             WHERE w.target_note_id = ?1 AND n.is_trashed = 0",
-        )
-        .map_err(|e| e.to_string())?;
+    Ok(response)
+}
 
-    let backlinks = stmt
-        .query_map(params![note_id], |row| {
-            Ok(BacklinkItem {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                emoji: row.get(2)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .filter_map(|r| r.ok())
-        .collect();
+// ─── Find & Replace ──────────────────────────────────────
 
-    Ok(backlinks)
+#[derive(Debug, Serialize)]
+pub struct ReplacePreview {
+    pub before: String,
+    pub after: String,
 }
 
-#[tauri::command]
-pub fn get_all_note_titles(db: State<Database>) -> Result<Vec<NoteTitleItem>, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn
-        .prepare("SELECT id, title FROM notes WHERE is_trashed = 0 AND is_folder = 0 ORDER BY title")
-        .map_err(|e| e.to_string())?;
+#[derive(Debug, Serialize)]
+pub struct FindReplaceNote {
+    #[serde(rename = "noteId")]
+    pub note_id: String,
+    pub title: String,
+    #[serde(rename = "matchCount")]
+    pub match_count: usize,
+    pub previews: Vec<ReplacePreview>,
+}
 
-    let titles = stmt
-        .query_map([], |row| {
-            Ok(NoteTitleItem {
-                id: row.get(0)?,
-                title: row.get(1)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .filter_map(|r| r.ok())
-        .collect();
+#[derive(Debug, Serialize)]
+pub struct FindReplaceResult {
+    #[serde(rename = "batchId")]
+    pub batch_id: Option<String>,
+    pub notes: Vec<FindReplaceNote>,
+    #[serde(rename = "totalMatches")]
+    pub total_matches: usize,
+    pub applied: bool,
+}
 
-    Ok(titles)
+#[derive(Debug, Serialize)]
+pub struct NoteRevision {
+    pub id: String,
+    #[serde(rename = "noteId")]
+    pub note_id: String,
+    #[serde(rename = "batchId")]
+    pub batch_id: Option<String>,
+    pub reason: String,
+    pub title: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
 }
 
-#[tauri::command]
-pub fn find_note_by_title(db: State<Database>, title: String) -> Result<Option<String>, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    match conn.query_row(
-        "SELECT id FROM notes WHERE LOWER(title) = LOWER(?1) AND is_trashed = 0 LIMIT 1",
-        params![title],
-        |row| row.get::<_, String>(0),
-    ) {
-        Ok(id) => Ok(Some(id)),
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-        Err(e) => Err(e.to_string()),
+const REPLACE_PREVIEWS_PER_NOTE: usize = 3;
+const REPLACE_PREVIEW_CHARS: usize = 160;
+
+fn clip_chars(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let clipped: String = text.chars().take(max).collect();
+        format!("{}…", clipped)
     }
 }
 
-// ─── Graph Command ───────────────────────────────────────
-
+/// Find & replace across note text leaves. Matching happens per leaf, so the
+/// JSON structure is never touched and a match cannot span formatting marks.
+/// With `dry_run` (the default) nothing is written and the result is a preview.
 #[tauri::command]
-pub fn get_graph_data(db: State<Database>) -> Result<GraphData, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+#[allow(clippy::too_many_arguments)]
+pub fn find_and_replace(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    pattern: String,
+    replacement: String,
+    is_regex: Option<bool>,
+    case_sensitive: Option<bool>,
+    whole_word: Option<bool>,
+    note_ids: Option<Vec<String>>,
+    dry_run: Option<bool>,
+) -> Result<FindReplaceResult, String> {
+    let is_regex = is_regex.unwrap_or(false);
+    let re = build_text_matcher(
+        &pattern,
+        is_regex,
+        case_sensitive.unwrap_or(false),
+        whole_word.unwrap_or(false),
+    )?;
+    if re.is_match("") {
+        return Err("Pattern must not match empty text".to_string());
+    }
+    let dry_run = dry_run.unwrap_or(true);
 
-    // Get all non-trashed, non-folder notes
-    let mut node_stmt = conn
-        .prepare("SELECT id, title, emoji FROM notes WHERE is_trashed = 0 AND is_folder = 0")
-        .map_err(|e| e.to_string())?;
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    let nodes: Vec<GraphNode> = node_stmt
-        .query_map([], |row| {
-            Ok(GraphNode {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                emoji: row.get(2)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .filter_map(|r| r.ok())
+    let mut candidates: Vec<(String, String, String)> = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT id, title, COALESCE(content, '[]') FROM notes
+                 WHERE is_trashed = 0 AND is_folder = 0
+                 ORDER BY title",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        rows
+    };
+    if let Some(ids) = &note_ids {
+        candidates.retain(|(id, _, _)| ids.contains(id));
+    }
+
+    let batch_id = Uuid::new_v4().to_string();
+    let mut result = FindReplaceResult {
+        batch_id: None,
+        notes: vec![],
+        total_matches: 0,
+        applied: false,
+    };
+    let mut changed: Vec<(String, Vec<serde_json::Value>)> = Vec::new();
+
+    for (note_id, title, content) in candidates {
+        let Ok(mut nodes) = serde_json::from_str::<Vec<serde_json::Value>>(&content) else {
+            continue;
+        };
+
+        let mut match_count = 0;
+        let mut previews = Vec::new();
+        for_each_text_leaf_mut(&mut nodes, &mut |text| {
+            let count = re.find_iter(text).count();
+            if count == 0 {
+                return;
+            }
+            let replaced = if is_regex {
+                re.replace_all(text, replacement.as_str()).into_owned()
+            } else {
+                re.replace_all(text, regex::NoExpand(&replacement))
+                    .into_owned()
+            };
+            if previews.len() < REPLACE_PREVIEWS_PER_NOTE {
+                previews.push(ReplacePreview {
+                    before: clip_chars(text, REPLACE_PREVIEW_CHARS),
+                    after: clip_chars(&replaced, REPLACE_PREVIEW_CHARS),
+                });
+            }
+            match_count += count;
+            *text = replaced;
+        });
+
+        if match_count == 0 {
+            continue;
+        }
+
+        if !dry_run {
+            changed.push((note_id.clone(), nodes));
+        }
+
+        result.total_matches += match_count;
+        result.notes.push(FindReplaceNote {
+            note_id,
+            title,
+            match_count,
+            previews,
+        });
+    }
+
+    if !changed.is_empty() {
+        let graph_ids: Vec<String> = changed.iter().map(|(id, _)| id.clone()).collect();
+        let graph_before = graph_snapshot(&tx, &graph_ids).map_err(|e| e.to_string())?;
+        for (note_id, nodes) in &changed {
+            record_revision(&tx, note_id, Some(&batch_id), "find-replace")
+                .map_err(|e| e.to_string())?;
+            write_note_nodes(&tx, note_id, nodes).map_err(|e| e.to_string())?;
+        }
+        stamp_revision_batch(&tx, &batch_id).map_err(|e| e.to_string())?;
+        let graph_after = graph_snapshot(&tx, &graph_ids).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        emit_graph_delta(&app, graph_before, graph_after);
+        result.batch_id = Some(batch_id);
+        result.applied = true;
+    }
+
+    Ok(result)
+}
+
+#[derive(Debug, Serialize)]
+pub struct SkippedNote {
+    #[serde(rename = "noteId")]
+    pub note_id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UndoReplaceResult {
+    pub restored: usize,
+    /// Notes edited since the batch was applied; these are left untouched.
+    pub skipped: Vec<SkippedNote>,
+}
+
+/// Restore every note touched by a find & replace batch to its prior state,
+/// skipping notes that were edited after the replace.
+#[tauri::command]
+pub fn undo_find_and_replace(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    batch_id: String,
+) -> Result<UndoReplaceResult, String> {
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let mut revisions: Vec<(String, String)> = Vec::new();
+    let mut skipped = Vec::new();
+    {
+        let mut stmt = tx
+            .prepare(
+                "SELECT r.id, r.note_id, n.title,
+                        r.applied_content IS NOT NULL
+                            AND n.content IS NOT r.applied_content
+                 FROM note_revisions r JOIN notes n ON n.id = r.note_id
+                 WHERE r.batch_id = ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![batch_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, bool>(3)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok());
+        for (revision_id, note_id, title, stale) in rows {
+            if stale {
+                skipped.push(SkippedNote { note_id, title });
+            } else {
+                revisions.push((revision_id, note_id));
+            }
+        }
+    }
+
+    let graph_ids: Vec<String> = revisions
+        .iter()
+        .map(|(_, note_id)| note_id.clone())
         .collect();
+    let graph_before = graph_snapshot(&tx, &graph_ids).map_err(|e| e.to_string())?;
+    for (revision_id, _) in &revisions {
+        restore_revision(&tx, revision_id).map_err(|e| e.to_string())?;
+    }
+    let graph_after = graph_snapshot(&tx, &graph_ids).map_err(|e| e.to_string())?;
+
+    tx.commit().map_err(|e| e.to_string())?;
+    emit_graph_delta(&app, graph_before, graph_after);
+    Ok(UndoReplaceResult {
+        restored: revisions.len(),
+        skipped,
+    })
+}
 
-    // Get wikilink edges
-    let mut link_stmt = conn
+#[tauri::command]
+pub fn get_note_revisions(
+    db: State<Database>,
+    note_id: String,
+) -> Result<Vec<NoteRevision>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
         .prepare(
-            "SELECT w.source_note_id, w.target_note_id FROM wikilinks w
-             JOIN notes s ON s.id = w.source_note_id AND s.is_trashed = 0
-             JOIN notes t ON t.id = w.target_note_id AND t.is_trashed = 0",
+            "SELECT id, note_id, batch_id, reason, title, created_at FROM note_revisions
+             WHERE note_id = ?1 ORDER BY created_at DESC, rowid DESC",
         )
         .map_err(|e| e.to_string())?;
 
-    let mut edges: Vec<GraphEdge> = link_stmt
-        .query_map([], |row| {
-            Ok(GraphEdge {
-                source: row.get(0)?,
-                target: row.get(1)?,
-                edge_type: "wikilink".to_string(),
+    let revisions = stmt
+        .query_map(params![note_id], |row| {
+            Ok(NoteRevision {
+                id: row.get(0)?,
+                note_id: row.get(1)?,
+                batch_id: row.get(2)?,
+                reason: row.get(3)?,
+                title: row.get(4)?,
+                created_at: row.get(5)?,
             })
         })
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
 
-    // Get tag-based edges (notes sharing same tag)
-    let mut tag_edge_stmt = conn
-        .prepare(
-            "SELECT DISTINCT nt1.note_id, nt2.note_id
-             FROM note_tags nt1
-             JOIN note_tags nt2 ON nt1.tag_id = nt2.tag_id AND nt1.note_id < nt2.note_id
-             JOIN notes n1 ON n1.id = nt1.note_id AND n1.is_trashed = 0 AND n1.is_folder = 0
-             JOIN notes n2 ON n2.id = nt2.note_id AND n2.is_trashed = 0 AND n2.is_folder = 0",
+    Ok(revisions)
+}
+
+#[tauri::command]
+pub fn restore_note_revision(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    revision_id: String,
+) -> Result<(), String> {
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let note_id: String = tx
+        .query_row(
+            "SELECT note_id FROM note_revisions WHERE id = ?1",
+            params![revision_id],
+            |row| row.get(0),
         )
         .map_err(|e| e.to_string())?;
+    let graph_ids = [note_id];
+    let graph_before = graph_snapshot(&tx, &graph_ids).map_err(|e| e.to_string())?;
+    restore_revision(&tx, &revision_id).map_err(|e| e.to_string())?;
+    let graph_after = graph_snapshot(&tx, &graph_ids).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    emit_graph_delta(&app, graph_before, graph_after);
+    Ok(())
+}
 
-    let tag_edges: Vec<GraphEdge> = tag_edge_stmt
-        .query_map([], |row| {
-            Ok(GraphEdge {
-                source: row.get(0)?,
-                target: row.get(1)?,
-                edge_type: "tag".to_string(),
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .filter_map(|r| r.ok())
-        .collect();
+/// Write a revision back onto its note. The state being replaced is itself
+/// recorded first, so a restore can be undone the same way.
+fn restore_revision(conn: &rusqlite::Connection, revision_id: &str) -> rusqlite::Result<()> {
+    let (note_id, title, content, plain_text): (String, String, Option<String>, Option<String>) =
+        conn.query_row(
+            "SELECT note_id, title, content, plain_text FROM note_revisions WHERE id = ?1",
+            params![revision_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )?;
+    let plain_text = plain_text.unwrap_or_default();
+    let word_count = plain_text.split_whitespace().count() as i64;
 
-    edges.extend(tag_edges);
+    record_revision(conn, &note_id, None, "restore")?;
+    conn.execute(
+        "UPDATE notes SET title = ?1, content = ?2, plain_text = ?3, word_count = ?4,
+                updated_at = unixepoch()
+         WHERE id = ?5",
+        params![title, content, plain_text, word_count, note_id],
+    )?;
+
+    index_note_anchors(conn, &note_id, content.as_deref().unwrap_or("[]"))?;
+    index_note_tasks(conn, &note_id, content.as_deref().unwrap_or("[]"))?;
+    apply_inline_tags(conn, &note_id, &extract_inline_tags(&plain_text))?;
+    apply_wikilinks(
+        conn,
+        &note_id,
+        &extract_wikilinks(&plain_text),
+        &extract_embeds(&plain_text),
+    )?;
+    resolve_pending_links(conn, &note_id)?;
+    Ok(())
+}
 
-    Ok(GraphData { nodes, edges })
+// ─── Templates ───────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct TemplateItem {
+    pub id: String,
+    pub name: String,
+    pub emoji: String,
+    pub description: String,
+    /// Plate JSON with `{{...}}` variables in its text.
+    pub content: String,
+    /// `{{prompt:...}}` variables to ask for before creating a note.
+    pub prompts: Vec<TemplatePrompt>,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TemplatePrompt {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+/// Where `{{cursor}}` was: a Slate path to the text leaf and a UTF-16
+/// offset into it.
+#[derive(Debug, Serialize)]
+pub struct TemplateCursor {
+    pub path: Vec<usize>,
+    pub offset: usize,
 }
 
-// ─── Daily Note Command ──────────────────────────────────
+#[derive(Debug, Serialize)]
+pub struct TemplateNote {
+    #[serde(rename = "noteId")]
+    pub note_id: String,
+    pub title: String,
+    pub cursor: Option<TemplateCursor>,
+}
+
+/// Values the template variables expand to.
+struct TemplateContext<'a> {
+    title: &'a str,
+    /// The day `{{date}}` refers to: today, or a periodic note's start.
+    date: chrono::NaiveDate,
+    now: chrono::NaiveDateTime,
+    prompts: &'a std::collections::HashMap<String, String>,
+}
 
 #[tauri::command]
-pub fn get_or_create_daily_note(db: State<Database>, date: String) -> Result<String, String> {
+pub fn get_templates(db: State<Database>) -> Result<Vec<TemplateItem>, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM templates ORDER BY name COLLATE NOCASE",
+            TEMPLATE_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let templates = stmt
+        .query_map([], map_template_item)
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(templates)
+}
 
-    // Check if a note with this date as title already exists
-    let existing: Option<String> = conn
-        .query_row(
-            "SELECT id FROM notes WHERE title = ?1 AND is_trashed = 0 LIMIT 1",
-            params![date],
-            |row| row.get(0),
-        )
-        .ok();
+#[tauri::command]
+pub fn get_template(db: State<Database>, template_id: String) -> Result<TemplateItem, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    query_template(&conn, &template_id).ok_or_else(|| "Template not found".to_string())
+}
 
-    if let Some(id) = existing {
-        return Ok(id);
+#[tauri::command]
+pub fn create_template(
+    db: State<Database>,
+    name: String,
+    emoji: Option<String>,
+    description: Option<String>,
+    content: String,
+) -> Result<TemplateItem, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Template name cannot be empty".to_string());
     }
-
-    // Create new daily note
+    check_template_content(&content)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let id = Uuid::new_v4().to_string();
-    let content = format!(
-        r#"[{{"type":"h1","children":[{{"text":"{}"}}]}},{{"type":"p","children":[{{"text":""}}]}}]"#,
-        date
-    );
-
     conn.execute(
-        "INSERT INTO notes (id, title, content, emoji) VALUES (?1, ?2, ?3, '📅')",
-        params![id, date, content],
+        "INSERT INTO templates (id, name, emoji, description, content) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            id,
+            name,
+            emoji.unwrap_or_else(|| "📝".to_string()),
+            description.unwrap_or_default(),
+            content
+        ],
     )
     .map_err(|e| e.to_string())?;
-
-    Ok(id)
+    query_template(&conn, &id).ok_or_else(|| "Template not found".to_string())
 }
 
-// ─── Related Notes (AI Suggestions) ─────────────────────
+/// Change any of a template's fields; omitted ones are kept.
+#[tauri::command]
+pub fn update_template(
+    db: State<Database>,
+    template_id: String,
+    name: Option<String>,
+    emoji: Option<String>,
+    description: Option<String>,
+    content: Option<String>,
+) -> Result<TemplateItem, String> {
+    let name = name.map(|n| n.trim().to_string());
+    if name.as_deref() == Some("") {
+        return Err("Template name cannot be empty".to_string());
+    }
+    if let Some(content) = &content {
+        check_template_content(content)?;
+    }
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE templates SET name = COALESCE(?1, name), emoji = COALESCE(?2, emoji),
+                    description = COALESCE(?3, description), content = COALESCE(?4, content),
+                    updated_at = unixepoch()
+             WHERE id = ?5",
+            params![name, emoji, description, content, template_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Template not found".to_string());
+    }
+    query_template(&conn, &template_id).ok_or_else(|| "Template not found".to_string())
+}
 
-#[derive(Debug, Serialize)]
-pub struct RelatedNoteItem {
-    pub id: String,
-    pub title: String,
-    pub emoji: Option<String>,
-    pub score: f64,
+#[tauri::command]
+pub fn delete_template(db: State<Database>, template_id: String) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM templates WHERE id = ?1", params![template_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
 }
 
+/// Create a note from a stored template, expanding its variables. `title`
+/// fills `{{title}}`; as elsewhere, a leading heading becomes the note title.
 #[tauri::command]
-pub fn find_related_notes(
+pub fn create_note_with_template(
+    app: tauri::AppHandle,
     db: State<Database>,
-    note_id: String,
-) -> Result<Vec<RelatedNoteItem>, String> {
+    template_id: String,
+    title: Option<String>,
+    parent_id: Option<String>,
+    prompts: Option<std::collections::HashMap<String, String>>,
+) -> Result<TemplateNote, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let template =
+        query_template(&conn, &template_id).ok_or_else(|| "Template not found".to_string())?;
+    let title = title
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| "Untitled".to_string());
+    let now = local_now(&conn);
+    let prompts = prompts.unwrap_or_default();
+    let (nodes, cursor) = expand_template(
+        &template.content,
+        &TemplateContext {
+            title: &title,
+            date: now.date(),
+            now,
+            prompts: &prompts,
+        },
+    );
 
-    // Get the plain text of the current note
-    let plain_text: String = conn
-        .query_row(
-            "SELECT COALESCE(plain_text, '') FROM notes WHERE id = ?1",
-            params![note_id],
-            |row| row.get(0),
+    let id = Uuid::new_v4().to_string();
+    with_graph_delta(&app, &conn, std::slice::from_ref(&id), || {
+        conn.execute(
+            "INSERT INTO notes (id, title, emoji, parent_id) VALUES (?1, ?2, ?3, ?4)",
+            params![id, title, template.emoji, parent_id],
         )
         .map_err(|e| e.to_string())?;
+        write_note_nodes(&conn, &id, &nodes).map_err(|e| e.to_string())
+    })?;
+
+    let title = plate_title(&nodes).unwrap_or(title);
+    Ok(TemplateNote {
+        note_id: id,
+        title,
+        cursor,
+    })
+}
 
-    if plain_text.trim().is_empty() {
-        return Ok(vec![]);
+const TEMPLATE_COLUMNS: &str = "id, name, emoji, description, content, created_at, updated_at";
+
+fn map_template_item(row: &rusqlite::Row) -> rusqlite::Result<TemplateItem> {
+    let content: String = row.get(4)?;
+    Ok(TemplateItem {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        emoji: row.get(2)?,
+        description: row.get(3)?,
+        prompts: template_prompts(&content),
+        content,
+        created_at: row.get(5)?,
+        updated_at: row.get(6)?,
+    })
+}
+
+fn query_template(conn: &rusqlite::Connection, template_id: &str) -> Option<TemplateItem> {
+    conn.query_row(
+        &format!("SELECT {} FROM templates WHERE id = ?1", TEMPLATE_COLUMNS),
+        params![template_id],
+        map_template_item,
+    )
+    .ok()
+}
+
+fn check_template_content(content: &str) -> Result<(), String> {
+    serde_json::from_str::<Vec<serde_json::Value>>(content)
+        .map(|_| ())
+        .map_err(|_| "Template content must be a Plate JSON array".to_string())
+}
+
+/// `{{name}}` or `{{name:argument}}`.
+fn template_variable_regex() -> &'static regex::Regex {
+    static VARIABLE_RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    VARIABLE_RE.get_or_init(|| regex::Regex::new(r"\{\{\s*(\w+)(?::([^}]*))?\s*\}\}").unwrap())
+}
+
+/// `{{prompt:Question}}` or `{{prompt:Question|default}}`.
+fn split_template_prompt(argument: &str) -> (&str, Option<&str>) {
+    match argument.split_once('|') {
+        Some((name, default)) => (name.trim(), Some(default.trim())),
+        None => (argument.trim(), None),
     }
+}
 
-    // Extract top keywords (words with 4+ chars, skip common words)
-    let stop_words: std::collections::HashSet<&str> = [
-        "the", "and", "for", "that", "this", "with", "from", "have", "been",
-        "will", "are", "was", "were", "not", "but", "can", "all", "has",
-        "each", "which", "their", "there", "about", "would", "make", "like",
-        "just", "over", "such", "take", "than", "them", "very", "some",
-        "into", "most", "other", "also", "more", "what", "when", "your",
-    ]
-    .iter()
-    .copied()
-    .collect();
+fn template_prompts(content: &str) -> Vec<TemplatePrompt> {
+    let mut nodes: Vec<serde_json::Value> = serde_json::from_str(content).unwrap_or_default();
+    let mut prompts: Vec<TemplatePrompt> = Vec::new();
+    for_each_text_leaf_mut(&mut nodes, &mut |text| {
+        for caps in template_variable_regex().captures_iter(text) {
+            if !caps[1].eq_ignore_ascii_case("prompt") {
+                continue;
+            }
+            let Some(argument) = caps.get(2) else {
+                continue;
+            };
+            let (name, default) = split_template_prompt(argument.as_str());
+            if !name.is_empty() && prompts.iter().all(|p| p.name != name) {
+                prompts.push(TemplatePrompt {
+                    name: name.to_string(),
+                    default: default.map(str::to_string),
+                });
+            }
+        }
+    });
+    prompts
+}
 
-    let mut word_freq: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
-    for word in plain_text.to_lowercase().split_whitespace() {
-        let clean: String = word.chars().filter(|c| c.is_alphanumeric()).collect();
-        if clean.len() >= 4 && !stop_words.contains(clean.as_str()) {
-            *word_freq.entry(clean).or_insert(0) += 1;
+/// Expand every variable in a template's text leaves, returning the nodes
+/// and where the first `{{cursor}}` was. Unknown variables are left as is.
+fn expand_template(
+    content: &str,
+    ctx: &TemplateContext,
+) -> (Vec<serde_json::Value>, Option<TemplateCursor>) {
+    fn walk(
+        nodes: &mut [serde_json::Value],
+        ctx: &TemplateContext,
+        path: &mut Vec<usize>,
+        cursor: &mut Option<TemplateCursor>,
+    ) {
+        for (i, node) in nodes.iter_mut().enumerate() {
+            path.push(i);
+            if let Some(serde_json::Value::String(text)) = node.get_mut("text") {
+                let (expanded, offset) = expand_template_text(text, ctx);
+                *text = expanded;
+                if let (None, Some(offset)) = (&cursor, offset) {
+                    *cursor = Some(TemplateCursor {
+                        path: path.clone(),
+                        offset,
+                    });
+                }
+            } else if let Some(serde_json::Value::Array(children)) = node.get_mut("children") {
+                walk(children, ctx, path, cursor);
+            }
+            path.pop();
         }
     }
 
-    let mut keywords: Vec<(String, usize)> = word_freq.into_iter().collect();
-    keywords.sort_by(|a, b| b.1.cmp(&a.1));
-    let top_keywords: Vec<String> = keywords.into_iter().take(8).map(|(w, _)| w).collect();
-
-    if top_keywords.is_empty() {
-        return Ok(vec![]);
+    // A prompt's default applies to every occurrence, not just the one it's on
+    let mut prompts = ctx.prompts.clone();
+    for prompt in template_prompts(content) {
+        if let Some(default) = prompt.default {
+            prompts.entry(prompt.name).or_insert(default);
+        }
     }
+    let ctx = TemplateContext {
+        prompts: &prompts,
+        ..*ctx
+    };
 
-    // Search FTS for each keyword and score results
-    let mut note_scores: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
+    let mut nodes: Vec<serde_json::Value> = serde_json::from_str(content).unwrap_or_default();
+    let mut cursor = None;
+    walk(&mut nodes, &ctx, &mut Vec::new(), &mut cursor);
+    (nodes, cursor)
+}
 
-    for keyword in &top_keywords {
-        let fts_query = format!("\"{}\"", keyword);
-        let mut stmt = conn
-            .prepare(
-                "SELECT n.id FROM notes_fts
-                 JOIN notes n ON n.rowid = notes_fts.rowid
-                 WHERE notes_fts MATCH ?1 AND n.is_trashed = 0 AND n.id != ?2
-                 LIMIT 20",
-            )
-            .map_err(|e| e.to_string())?;
+fn expand_template_text(text: &str, ctx: &TemplateContext) -> (String, Option<usize>) {
+    let mut out = String::with_capacity(text.len());
+    let mut cursor = None;
+    let mut last = 0;
+    for caps in template_variable_regex().captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        out.push_str(&text[last..whole.start()]);
+        last = whole.end();
+        let name = caps[1].to_ascii_lowercase();
+        if name == "cursor" {
+            cursor = cursor.or(Some(out.encode_utf16().count()));
+            continue;
+        }
+        match template_variable(&name, caps.get(2).map(|a| a.as_str().trim()), ctx) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(whole.as_str()),
+        }
+    }
+    out.push_str(&text[last..]);
+    (out, cursor)
+}
 
-        let ids: Vec<String> = stmt
-            .query_map(params![fts_query, note_id], |row| row.get(0))
-            .map_err(|e| e.to_string())?
-            .filter_map(|r| r.ok())
-            .collect();
+/// `title`, `date`/`yesterday`/`tomorrow` (argument: format, default
+/// `YYYY-MM-DD`), `time` (default `HH:mm`) and `prompt`.
+fn template_variable(name: &str, argument: Option<&str>, ctx: &TemplateContext) -> Option<String> {
+    let day = |date: chrono::NaiveDate| {
+        format_moment(
+            &date.and_time(ctx.now.time()),
+            argument.unwrap_or("YYYY-MM-DD"),
+        )
+    };
+    Some(match name {
+        "title" => ctx.title.to_string(),
+        "date" => day(ctx.date),
+        "yesterday" => day(ctx.date.pred_opt()?),
+        "tomorrow" => day(ctx.date.succ_opt()?),
+        "time" => format_moment(&ctx.now, argument.unwrap_or("HH:mm")),
+        "prompt" => {
+            let (prompt, default) = split_template_prompt(argument?);
+            ctx.prompts
+                .get(prompt)
+                .cloned()
+                .or_else(|| default.map(str::to_string))
+                .unwrap_or_default()
+        }
+        _ => return None,
+    })
+}
 
-        for id in ids {
-            *note_scores.entry(id).or_insert(0.0) += 1.0;
+// ─── Plate Content Helpers ───────────────────────────────
+
+/// Plain text of every block that directly holds text, in document order.
+fn plate_text_blocks(nodes: &[serde_json::Value]) -> Vec<String> {
+    let mut blocks = Vec::new();
+    for node in nodes {
+        let Some(children) = node.get("children").and_then(|c| c.as_array()) else {
+            continue;
+        };
+        if children.iter().any(|c| c.get("text").is_some()) {
+            let text = plate_plain_text(children);
+            if !text.is_empty() {
+                blocks.push(text);
+            }
+        } else {
+            blocks.extend(plate_text_blocks(children));
         }
     }
+    blocks
+}
 
-    // Normalize scores
-    let max_score = top_keywords.len() as f64;
-    let mut results: Vec<RelatedNoteItem> = Vec::new();
+/// Mirrors `extractTitle`: the text of the first H1, if the document has one.
+fn plate_title(nodes: &[serde_json::Value]) -> Option<String> {
+    let h1 = nodes
+        .iter()
+        .find(|n| n.get("type").and_then(|t| t.as_str()) == Some("h1"))?;
+    let text: String = h1
+        .get("children")
+        .and_then(|c| c.as_array())
+        .map(|children| {
+            children
+                .iter()
+                .filter_map(|c| c.get("text").and_then(|t| t.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let text = text.trim();
+    Some(if text.is_empty() {
+        "Untitled".to_string()
+    } else {
+        text.to_string()
+    })
+}
 
-    let mut scored: Vec<(String, f64)> = note_scores.into_iter().collect();
-    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+/// Visit every text leaf (`{ "text": ... }`) in document order.
+fn for_each_text_leaf_mut(nodes: &mut [serde_json::Value], f: &mut dyn FnMut(&mut String)) {
+    for node in nodes {
+        if let Some(serde_json::Value::String(text)) = node.get_mut("text") {
+            f(text);
+            continue;
+        }
+        if let Some(serde_json::Value::Array(children)) = node.get_mut("children") {
+            for_each_text_leaf_mut(children, f);
+        }
+    }
+}
 
-    for (nid, score) in scored.into_iter().take(5) {
-        let normalized = (score / max_score * 100.0).round();
-        match conn.query_row(
-            "SELECT title, emoji FROM notes WHERE id = ?1",
-            params![nid],
-            |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
-        ) {
-            Ok((title, emoji)) => {
-                results.push(RelatedNoteItem {
-                    id: nid,
-                    title,
-                    emoji,
-                    score: normalized,
-                });
-            }
-            Err(_) => continue,
+/// Mirrors `TAG_REGEX`: group 1 is the tag path without its `#`.
+fn inline_tag_regex() -> &'static regex::Regex {
+    static TAG_RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    TAG_RE.get_or_init(|| {
+        regex::Regex::new(r"(?:^|\s)#([a-zA-Z][\w-]{0,49}(?:/[\w-]{1,50})*)\b").unwrap()
+    })
+}
+
+/// Mirrors `extractInlineTags`.
+fn extract_inline_tags(text: &str) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    for cap in inline_tag_regex().captures_iter(text) {
+        let tag = cap[1].to_lowercase();
+        if !found.contains(&tag) {
+            found.push(tag);
         }
     }
+    found
+}
 
-    Ok(results)
+/// Mirrors `extractWikilinks`, except that `![[embeds]]` are left to
+/// `extract_embeds`.
+fn extract_wikilinks(text: &str) -> Vec<String> {
+    extract_link_refs(text, false)
 }
 
-// ─── Flashcard Commands ──────────────────────────────────
+/// Targets of `![[Note]]` / `![[Note#Heading]]` embeds.
+fn extract_embeds(text: &str) -> Vec<String> {
+    extract_link_refs(text, true)
+}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct FlashcardData {
-    pub id: String,
-    pub note_id: String,
-    pub question: String,
-    pub answer: String,
-    pub next_review: i64,
-    pub interval: f64,
-    pub ease_factor: f64,
-    pub repetitions: i64,
+fn extract_link_refs(text: &str, embeds: bool) -> Vec<String> {
+    static LINK_RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let re = LINK_RE.get_or_init(|| regex::Regex::new(r"(!?)\[\[([^\]]+)\]\]").unwrap());
+
+    let mut found: Vec<String> = Vec::new();
+    for cap in re.captures_iter(text) {
+        if cap[1].is_empty() == embeds {
+            continue;
+        }
+        let title = cap[2].trim().to_string();
+        if !title.is_empty() && !found.contains(&title) {
+            found.push(title);
+        }
+    }
+    found
 }
 
-#[derive(Debug, Serialize)]
-pub struct FlashcardStats {
-    pub due_today: i64,
-    pub total_cards: i64,
-    pub reviewed_today: i64,
-    pub streak: i64,
+/// Snapshot a note's current state into `note_revisions` before the backend
+/// rewrites it, so the change can be undone.
+fn record_revision(
+    conn: &rusqlite::Connection,
+    note_id: &str,
+    batch_id: Option<&str>,
+    reason: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO note_revisions (id, note_id, batch_id, reason, title, content, plain_text)
+         SELECT ?1, id, ?2, ?3, title, content, plain_text FROM notes WHERE id = ?4",
+        params![Uuid::new_v4().to_string(), batch_id, reason, note_id],
+    )?;
+    Ok(())
+}
+
+/// Remember each batch note's content right after the rewrite, so an undo
+/// can tell which notes were edited since.
+fn stamp_revision_batch(conn: &rusqlite::Connection, batch_id: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE note_revisions
+         SET applied_content = (SELECT content FROM notes WHERE notes.id = note_revisions.note_id)
+         WHERE batch_id = ?1",
+        params![batch_id],
+    )?;
+    Ok(())
+}
+
+/// Persist rewritten Plate nodes for a note, recomputing the derived columns
+/// (title, plain text, word count) and the tags/links extracted from them.
+/// FTS is kept in sync by the `notes_au` trigger.
+fn write_note_nodes(
+    conn: &rusqlite::Connection,
+    note_id: &str,
+    nodes: &[serde_json::Value],
+) -> rusqlite::Result<()> {
+    let content = serde_json::to_string(nodes).unwrap_or_else(|_| "[]".to_string());
+    let plain_text = plate_plain_text(nodes);
+    let word_count = plain_text.split_whitespace().count() as i64;
+
+    conn.execute(
+        "UPDATE notes SET content = ?1, title = COALESCE(?2, title), plain_text = ?3,
+                word_count = ?4, updated_at = unixepoch()
+         WHERE id = ?5",
+        params![content, plate_title(nodes), plain_text, word_count, note_id],
+    )?;
+
+    index_note_anchors(conn, note_id, &content)?;
+    index_note_tasks(conn, note_id, &content)?;
+    apply_inline_tags(conn, note_id, &extract_inline_tags(&plain_text))?;
+    apply_wikilinks(
+        conn,
+        note_id,
+        &extract_wikilinks(&plain_text),
+        &extract_embeds(&plain_text),
+    )?;
+    resolve_pending_links(conn, note_id)?;
+    Ok(())
 }
 
+// ─── Folder Commands ─────────────────────────────────────
+
 #[tauri::command]
-pub fn sync_flashcards(
+pub fn create_folder(
+    db: State<Database>,
+    name: String,
+    parent_id: Option<String>,
+) -> Result<String, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let id = Uuid::new_v4().to_string();
+
+    conn.execute(
+        "INSERT INTO notes (id, title, is_folder, emoji, parent_id) VALUES (?1, ?2, 1, '📁', ?3)",
+        params![id, name, parent_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn move_note(
     db: State<Database>,
     note_id: String,
-    cards: Vec<(String, String)>,
+    new_parent_id: Option<String>,
 ) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE notes SET parent_id = ?1 WHERE id = ?2",
+        params![new_parent_id, note_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    // Remove old flashcards for this note
-    conn.execute("DELETE FROM flashcards WHERE note_id = ?1", params![note_id])
-        .map_err(|e| e.to_string())?;
-
-    // Insert new ones
-    for (question, answer) in &cards {
-        let id = Uuid::new_v4().to_string();
+#[tauri::command]
+pub fn rename_note(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    note_id: String,
+    new_title: String,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    with_graph_delta(&app, &conn, std::slice::from_ref(&note_id), || {
         conn.execute(
-            "INSERT INTO flashcards (id, note_id, question, answer) VALUES (?1, ?2, ?3, ?4)",
-            params![id, note_id, question, answer],
+            "UPDATE notes SET title = ?1, updated_at = unixepoch() WHERE id = ?2",
+            params![new_title, note_id],
         )
         .map_err(|e| e.to_string())?;
-    }
+        resolve_pending_links(&conn, &note_id).map_err(|e| e.to_string())
+    })
+}
 
+#[tauri::command]
+pub fn toggle_favorite(db: State<Database>, note_id: String) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE notes SET is_favorite = CASE WHEN is_favorite = 1 THEN 0 ELSE 1 END WHERE id = ?1",
+        params![note_id],
+    )
+    .map_err(|e| e.to_string())?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn get_due_flashcards(db: State<Database>) -> Result<Vec<FlashcardData>, String> {
+pub fn toggle_pin(db: State<Database>, note_id: String) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE notes SET is_pinned = CASE WHEN is_pinned = 1 THEN 0 ELSE 1 END WHERE id = ?1",
+        params![note_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
+#[tauri::command]
+pub fn get_favorite_notes(db: State<Database>) -> Result<Vec<FavoriteNote>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
     let mut stmt = conn
-        .prepare(
-            "SELECT f.id, f.note_id, f.question, f.answer, f.next_review, f.interval, f.ease_factor, f.repetitions
-             FROM flashcards f
-             JOIN notes n ON n.id = f.note_id AND n.is_trashed = 0
-             WHERE f.next_review <= unixepoch()
-             ORDER BY f.next_review ASC
-             LIMIT 50",
-        )
+        .prepare("SELECT id, title, emoji FROM notes WHERE is_favorite = 1 AND is_trashed = 0")
         .map_err(|e| e.to_string())?;
 
-    let cards = stmt
+    let notes = stmt
         .query_map([], |row| {
-            Ok(FlashcardData {
+            Ok(FavoriteNote {
                 id: row.get(0)?,
-                note_id: row.get(1)?,
-                question: row.get(2)?,
-                answer: row.get(3)?,
-                next_review: row.get(4)?,
-                interval: row.get(5)?,
-                ease_factor: row.get(6)?,
-                repetitions: row.get(7)?,
+                title: row.get(1)?,
+                emoji: row.get(2)?,
             })
         })
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
 
-    Ok(cards)
+    Ok(notes)
 }
 
 #[tauri::command]
-pub fn review_flashcard(
-    db: State<Database>,
-    card_id: String,
-    rating: i32,
-) -> Result<(), String> {
+pub fn get_trashed_notes(db: State<Database>) -> Result<Vec<TrashedNote>, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-
-    // Get current card state
-    let (interval, ease_factor, repetitions): (f64, f64, i64) = conn
-        .query_row(
-            "SELECT interval, ease_factor, repetitions FROM flashcards WHERE id = ?1",
-            params![card_id],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, emoji, trashed_at FROM notes
+             WHERE is_trashed = 1 ORDER BY trashed_at DESC",
         )
         .map_err(|e| e.to_string())?;
 
-    // SM-2 algorithm
-    let (new_interval, new_ef, new_reps) = if rating < 2 {
-        // Failed: reset
-        (1.0_f64, (ease_factor - 0.2_f64).max(1.3_f64), 0_i64)
-    } else {
-        let new_ef = (ease_factor + 0.1 - (4 - rating) as f64 * (0.08 + (4 - rating) as f64 * 0.02)).max(1.3);
-        let new_reps = repetitions + 1;
-        let new_interval = match new_reps {
-            1 => 1.0,
-            2 => 6.0,
-            _ => interval * new_ef,
-        };
-        (new_interval, new_ef, new_reps)
-    };
-
-    let next_review_delta = (new_interval * 86400.0) as i64; // days to seconds
-
-    conn.execute(
-        "UPDATE flashcards SET interval = ?1, ease_factor = ?2, repetitions = ?3, next_review = unixepoch() + ?4, updated_at = unixepoch() WHERE id = ?5",
-        params![new_interval, new_ef, new_reps, next_review_delta, card_id],
-    )
-    .map_err(|e| e.to_string())?;
+    let notes = stmt
+        .query_map([], |row| {
+            Ok(TrashedNote {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                emoji: row.get(2)?,
+                trashed_at: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
 
-    Ok(())
+    Ok(notes)
 }
 
 #[tauri::command]
-pub fn get_flashcard_stats(db: State<Database>) -> Result<FlashcardStats, String> {
+pub fn permanently_delete_note(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    note_id: String,
+) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-
-    let due_today: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM flashcards f JOIN notes n ON n.id = f.note_id AND n.is_trashed = 0 WHERE f.next_review <= unixepoch()",
-            [],
-            |row| row.get(0),
+    with_graph_delta(&app, &conn, std::slice::from_ref(&note_id), || {
+        conn.execute(
+            "DELETE FROM wikilinks WHERE source_note_id = ?1",
+            params![note_id],
         )
-        .unwrap_or(0);
-
-    let total_cards: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM flashcards f JOIN notes n ON n.id = f.note_id AND n.is_trashed = 0",
-            [],
-            |row| row.get(0),
+        .map_err(|e| e.to_string())?;
+        // Links into the deleted note stay behind as unresolved links
+        conn.execute(
+            "UPDATE wikilinks SET target_note_id = NULL WHERE target_note_id = ?1",
+            params![note_id],
         )
-        .unwrap_or(0);
+        .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM note_tags WHERE note_id = ?1", params![note_id])
+            .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM notes WHERE id = ?1", params![note_id])
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    })
+}
 
-    // Reviewed today: cards whose updated_at is today
-    let reviewed_today: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM flashcards WHERE updated_at >= unixepoch('now', 'start of day')",
-            [],
-            |row| row.get(0),
+#[tauri::command]
+pub fn restore_note(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    note_id: String,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    with_graph_delta(&app, &conn, std::slice::from_ref(&note_id), || {
+        conn.execute(
+            "UPDATE notes SET is_trashed = 0, trashed_at = NULL WHERE id = ?1",
+            params![note_id],
         )
-        .unwrap_or(0);
+        .map_err(|e| e.to_string())?;
+        resolve_pending_links(&conn, &note_id).map_err(|e| e.to_string())
+    })
+}
 
-    // Streak: consecutive days with reviews
-    let streak: i64 = conn
-        .query_row(
-            "SELECT COUNT(DISTINCT date(updated_at, 'unixepoch')) FROM flashcards WHERE updated_at >= unixepoch() - 86400 * 30",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
+// ─── Tag Commands ────────────────────────────────────────
 
-    Ok(FlashcardStats {
-        due_today,
-        total_cards,
-        reviewed_today,
-        streak,
-    })
-}
+#[tauri::command]
+pub fn get_all_tags(db: State<Database>) -> Result<Vec<TagInfo>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.name, t.color, t.parent_id, COUNT(n.id) as note_count
+             FROM tags t
+             LEFT JOIN note_tags nt ON nt.tag_id = t.id
+             LEFT JOIN notes n ON n.id = nt.note_id AND n.is_trashed = 0
+             GROUP BY t.id
+             ORDER BY t.name",
+        )
+        .map_err(|e| e.to_string())?;
 
-// ─── Canvas Commands ─────────────────────────────────────
+    let tags: Vec<TagInfo> = stmt
+        .query_map([], |row| {
+            let name: String = row.get(1)?;
+            Ok(TagInfo {
+                id: row.get(0)?,
+                label: tag_label(&name).to_string(),
+                name,
+                color: row.get(2)?,
+                parent_id: row.get(3)?,
+                note_count: row.get(4)?,
+                total_count: 0,
+                children: vec![],
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CanvasItem {
-    pub id: String,
-    pub note_id: Option<String>,
-    pub x: f64,
-    pub y: f64,
-    pub width: f64,
-    pub height: f64,
+    // Roll every live note up to each ancestor of the tags it carries,
+    // counting it once per tag even when it sits under several children.
+    let parents: std::collections::HashMap<String, Option<String>> = tags
+        .iter()
+        .map(|t| (t.id.clone(), t.parent_id.clone()))
+        .collect();
+    let mut members: std::collections::HashMap<String, std::collections::HashSet<String>> =
+        std::collections::HashMap::new();
+    let mut member_stmt = conn
+        .prepare(
+            "SELECT nt.tag_id, nt.note_id FROM note_tags nt
+             JOIN notes n ON n.id = nt.note_id AND n.is_trashed = 0",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = member_stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    for (tag_id, note_id) in rows.flatten() {
+        let mut current = Some(tag_id);
+        let mut depth = 0;
+        while let Some(id) = current {
+            // Guards against a corrupted parent chain looping forever.
+            depth += 1;
+            if depth > parents.len() {
+                break;
+            }
+            current = parents.get(&id).cloned().flatten();
+            members.entry(id).or_default().insert(note_id.clone());
+        }
+    }
+
+    let mut children: std::collections::HashMap<Option<String>, Vec<TagInfo>> =
+        std::collections::HashMap::new();
+    for mut tag in tags {
+        tag.total_count = members.get(&tag.id).map_or(0, |m| m.len() as i64);
+        // Tags whose parent row is gone are shown at the top level.
+        let parent = tag.parent_id.clone().filter(|p| parents.contains_key(p));
+        children.entry(parent).or_default().push(tag);
+    }
+    Ok(build_tag_tree(&mut children, None))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CanvasConnection {
-    pub id: String,
-    pub from_item_id: String,
-    pub to_item_id: String,
+fn build_tag_tree(
+    children: &mut std::collections::HashMap<Option<String>, Vec<TagInfo>>,
+    parent: Option<String>,
+) -> Vec<TagInfo> {
+    let mut level = children.remove(&parent).unwrap_or_default();
+    for tag in &mut level {
+        tag.children = build_tag_tree(children, Some(tag.id.clone()));
+    }
+    level
 }
 
-#[derive(Debug, Serialize)]
-pub struct CanvasData {
-    pub items: Vec<CanvasItem>,
-    pub connections: Vec<CanvasConnection>,
+/// The last segment of a nested tag path.
+fn tag_label(name: &str) -> &str {
+    name.rsplit('/').next().unwrap_or(name)
+}
+
+/// SQL condition matching tag `t` against `?` itself or any descendant.
+/// Binds two parameters: the lowercased tag path and its escaped `LIKE`
+/// prefix (see `tag_subtree_params`).
+const TAG_SUBTREE_SQL: &str = "(LOWER(t.name) = ? OR LOWER(t.name) LIKE ? ESCAPE '\\')";
+
+fn tag_subtree_params(tag: &str) -> [rusqlite::types::Value; 2] {
+    let path = normalize_tag_path(tag).unwrap_or_default().to_lowercase();
+    let prefix = format!("{}/%", like_escape(&path));
+    [
+        rusqlite::types::Value::Text(path),
+        rusqlite::types::Value::Text(prefix),
+    ]
 }
 
+/// Notes carrying `tag_name`, and by default any of its descendants
+/// (`#project` matches notes tagged `#project/alpha`).
 #[tauri::command]
-pub fn get_canvas_data(db: State<Database>) -> Result<CanvasData, String> {
+pub fn get_notes_by_tag(
+    db: State<Database>,
+    tag_name: String,
+    include_descendants: Option<bool>,
+) -> Result<Vec<KanbanCard>, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-
-    let mut item_stmt = conn
-        .prepare("SELECT id, note_id, x, y, width, height FROM canvas_items")
-        .map_err(|e| e.to_string())?;
-
-    let items = item_stmt
-        .query_map([], |row| {
-            Ok(CanvasItem {
+    let mut values = tag_subtree_params(&tag_name).to_vec();
+    let condition = if include_descendants.unwrap_or(true) {
+        TAG_SUBTREE_SQL
+    } else {
+        values.truncate(1);
+        "LOWER(t.name) = ?"
+    };
+    let sql = format!(
+        "SELECT DISTINCT n.id, n.title, n.emoji, SUBSTR(COALESCE(n.plain_text, ''), 1, 100), COALESCE(n.updated_at, 0)
+         FROM notes n
+         JOIN note_tags nt ON nt.note_id = n.id
+         JOIN tags t ON t.id = nt.tag_id
+         WHERE n.is_trashed = 0 AND n.is_folder = 0 AND {}
+         ORDER BY n.updated_at DESC",
+        condition
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let mut cards: Vec<KanbanCard> = stmt
+        .query_map(rusqlite::params_from_iter(values), |row| {
+            Ok(KanbanCard {
                 id: row.get(0)?,
-                note_id: row.get(1)?,
-                x: row.get(2)?,
-                y: row.get(3)?,
-                width: row.get(4)?,
-                height: row.get(5)?,
+                title: row.get(1)?,
+                emoji: row.get(2)?,
+                preview: row.get(3)?,
+                updated_at: row.get(4)?,
+                tags: vec![],
             })
         })
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
 
-    let mut conn_stmt = conn
-        .prepare("SELECT id, from_item_id, to_item_id FROM canvas_connections")
+    let mut tag_stmt = conn
+        .prepare(
+            "SELECT t.name FROM note_tags nt JOIN tags t ON t.id = nt.tag_id WHERE nt.note_id = ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    for card in &mut cards {
+        card.tags = tag_stmt
+            .query_map(params![card.id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+    }
+
+    Ok(cards)
+}
+
+#[tauri::command]
+pub fn get_note_tags(db: State<Database>, note_id: String) -> Result<Vec<NoteTagInfo>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.name, t.color, nt.source
+             FROM note_tags nt JOIN tags t ON t.id = nt.tag_id
+             WHERE nt.note_id = ?1",
+        )
         .map_err(|e| e.to_string())?;
 
-    let connections = conn_stmt
-        .query_map([], |row| {
-            Ok(CanvasConnection {
-                id: row.get(0)?,
-                from_item_id: row.get(1)?,
-                to_item_id: row.get(2)?,
+    let tags = stmt
+        .query_map(params![note_id], |row| {
+            Ok(NoteTagInfo {
+                tag_id: row.get(0)?,
+                tag_name: row.get(1)?,
+                tag_color: row.get(2)?,
+                source: row.get(3)?,
             })
         })
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
 
-    Ok(CanvasData { items, connections })
+    Ok(tags)
 }
 
 #[tauri::command]
-pub fn save_canvas_item(
+pub fn sync_inline_tags(
+    app: tauri::AppHandle,
     db: State<Database>,
-    id: String,
-    note_id: Option<String>,
-    x: f64,
-    y: f64,
-    width: f64,
-    height: f64,
+    note_id: String,
+    tag_names: Vec<String>,
 ) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    with_graph_delta(&app, &conn, std::slice::from_ref(&note_id), || {
+        apply_inline_tags(&conn, &note_id, &tag_names).map_err(|e| e.to_string())
+    })
+}
 
+/// Replace a note's inline tags. Shared by `sync_inline_tags` and backend
+/// rewrites of note content.
+fn apply_inline_tags(
+    conn: &rusqlite::Connection,
+    note_id: &str,
+    tag_names: &[String],
+) -> rusqlite::Result<()> {
+    // Remove old inline tags for this note
     conn.execute(
-        "INSERT OR REPLACE INTO canvas_items (id, note_id, x, y, width, height) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![id, note_id, x, y, width, height],
-    )
-    .map_err(|e| e.to_string())?;
+        "DELETE FROM note_tags WHERE note_id = ?1 AND source = 'inline'",
+        params![note_id],
+    )?;
+
+    // Insert new inline tags
+    for name in tag_names {
+        let actual_tag_id = ensure_tag(conn, name)?;
+
+        conn.execute(
+            "INSERT OR IGNORE INTO note_tags (note_id, tag_id, source) VALUES (?1, ?2, 'inline')",
+            params![note_id, actual_tag_id],
+        )?;
+    }
 
     Ok(())
 }
 
 #[tauri::command]
-pub fn delete_canvas_item(db: State<Database>, id: String) -> Result<(), String> {
+pub fn add_manual_tag(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    note_id: String,
+    tag_name: String,
+) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM canvas_connections WHERE from_item_id = ?1 OR to_item_id = ?1", params![id])
-        .map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM canvas_items WHERE id = ?1", params![id])
+    with_graph_delta(&app, &conn, std::slice::from_ref(&note_id), || {
+        let actual_tag_id = ensure_tag(&conn, &tag_name).map_err(|e| e.to_string())?;
+
+        conn.execute(
+            "INSERT OR IGNORE INTO note_tags (note_id, tag_id, source) VALUES (?1, ?2, 'manual')",
+            params![note_id, actual_tag_id],
+        )
         .map_err(|e| e.to_string())?;
-    Ok(())
+
+        Ok(())
+    })
 }
 
 #[tauri::command]
-pub fn save_canvas_connection(
+pub fn remove_tag(
+    app: tauri::AppHandle,
     db: State<Database>,
-    id: String,
-    from_item_id: String,
-    to_item_id: String,
+    note_id: String,
+    tag_id: String,
 ) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    conn.execute(
-        "INSERT OR REPLACE INTO canvas_connections (id, from_item_id, to_item_id) VALUES (?1, ?2, ?3)",
-        params![id, from_item_id, to_item_id],
-    )
-    .map_err(|e| e.to_string())?;
-    Ok(())
+    with_graph_delta(&app, &conn, std::slice::from_ref(&note_id), || {
+        conn.execute(
+            "DELETE FROM note_tags WHERE note_id = ?1 AND tag_id = ?2",
+            params![note_id, tag_id],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    })
+}
+
+// ─── Tag Management ──────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct TagRewrite {
+    /// The tag the notes now carry; `None` after a delete.
+    #[serde(rename = "tagId")]
+    pub tag_id: Option<String>,
+    pub name: Option<String>,
+    /// Notes whose inline `#tag` text was rewritten.
+    pub notes: Vec<RenameLinkUpdate>,
+    #[serde(rename = "totalReplacements")]
+    pub total_replacements: usize,
+    #[serde(rename = "batchId")]
+    pub batch_id: Option<String>,
 }
 
+/// Rename a tag, moving its descendants along (`a/x` follows `a` to `b/x`)
+/// and rewriting inline `#a` text in every tagged note. Rewritten notes are
+/// recorded as one revision batch, so `undo_find_and_replace` can revert
+/// them.
 #[tauri::command]
-pub fn delete_canvas_connection(db: State<Database>, id: String) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM canvas_connections WHERE id = ?1", params![id])
+pub fn rename_tag(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    tag_id: String,
+    new_name: String,
+) -> Result<TagRewrite, String> {
+    let new_name = valid_tag_path(&new_name)?;
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let old_name = tag_name_by_id(&conn, &tag_id)?;
+    if old_name == new_name {
+        return retag_result(&conn, Some(&new_name), vec![], None);
+    }
+    if tag_in_subtree(&new_name, &old_name) {
+        return Err(format!("\"{}\" cannot be moved inside itself", old_name));
+    }
+    let taken: bool = conn
+        .query_row(
+            "SELECT COUNT(*) > 0 FROM tags WHERE name = ?1",
+            params![new_name],
+            |row| row.get(0),
+        )
         .map_err(|e| e.to_string())?;
-    Ok(())
+    if taken {
+        return Err(format!(
+            "Tag \"{}\" already exists; merge the tags instead",
+            new_name
+        ));
+    }
+    retag_subtree(&app, &mut conn, &old_name, Some(&new_name), "tag_rename")
 }
 
-// ─── Snippet Commands ────────────────────────────────────
+/// Fold `source_tag_id` (and its descendants) into `target_tag_id`. Notes
+/// keep every tag they had, just under the target's name.
+#[tauri::command]
+pub fn merge_tags(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    source_tag_id: String,
+    target_tag_id: String,
+) -> Result<TagRewrite, String> {
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let source = tag_name_by_id(&conn, &source_tag_id)?;
+    let target = tag_name_by_id(&conn, &target_tag_id)?;
+    if source == target {
+        return Err("Cannot merge a tag into itself".to_string());
+    }
+    if tag_in_subtree(&target, &source) {
+        return Err(format!(
+            "\"{}\" cannot be merged into its own descendant",
+            source
+        ));
+    }
+    retag_subtree(&app, &mut conn, &source, Some(&target), "tag_merge")
+}
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SnippetData {
-    pub id: String,
-    pub title: String,
-    pub content: String,
-    pub language: String,
-    pub tags: String,
-    #[serde(rename = "createdAt")]
-    pub created_at: i64,
+/// Delete a tag and its descendants from every note. Inline occurrences
+/// lose their `#` so the next save does not bring the tag back.
+#[tauri::command]
+pub fn delete_tag(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    tag_id: String,
+) -> Result<TagRewrite, String> {
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let name = tag_name_by_id(&conn, &tag_id)?;
+    retag_subtree(&app, &mut conn, &name, None, "tag_delete")
 }
 
+/// Set a tag's color; `None` restores the default.
 #[tauri::command]
-pub fn create_snippet(
+pub fn set_tag_color(
     db: State<Database>,
-    title: String,
-    content: String,
-    language: String,
-    tags: String,
-) -> Result<String, String> {
+    tag_id: String,
+    color: Option<String>,
+) -> Result<(), String> {
+    let color = color.map(|c| c.trim().to_string());
+    if let Some(c) = &color {
+        let hex = c.strip_prefix('#').unwrap_or("");
+        if !matches!(hex.len(), 3 | 6) || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+            return Err(format!("Invalid color \"{}\"", c));
+        }
+    }
+
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let id = Uuid::new_v4().to_string();
-    conn.execute(
-        "INSERT INTO snippets (id, title, content, language, tags) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![id, title, content, language, tags],
-    )
-    .map_err(|e| e.to_string())?;
-    Ok(id)
+    let updated = conn
+        .execute(
+            "UPDATE tags SET color = COALESCE(?1, '#6366f1') WHERE id = ?2",
+            params![color, tag_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Tag not found".to_string());
+    }
+    Ok(())
 }
 
+/// Delete tags that no live note carries, directly or through a descendant.
+/// Trashed notes lose such tags too. Returns how many were removed.
 #[tauri::command]
-pub fn get_snippets(db: State<Database>) -> Result<Vec<SnippetData>, String> {
+pub fn purge_unused_tags(db: State<Database>) -> Result<usize, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let mut stmt = conn
-        .prepare("SELECT id, title, content, language, tags, created_at FROM snippets ORDER BY created_at DESC")
-        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM tags WHERE NOT EXISTS (
+             SELECT 1 FROM tags d
+             JOIN note_tags nt ON nt.tag_id = d.id
+             JOIN notes n ON n.id = nt.note_id AND n.is_trashed = 0
+             WHERE d.id = tags.id
+                OR SUBSTR(d.name, 1, LENGTH(tags.name) + 1) = tags.name || '/'
+         )",
+        [],
+    )
+    .map_err(|e| e.to_string())
+}
 
-    let snippets = stmt
-        .query_map([], |row| {
-            Ok(SnippetData {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                content: row.get(2)?,
-                language: row.get(3)?,
-                tags: row.get(4)?,
-                created_at: row.get(5)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .filter_map(|r| r.ok())
-        .collect();
+fn tag_name_by_id(conn: &rusqlite::Connection, tag_id: &str) -> Result<String, String> {
+    conn.query_row(
+        "SELECT name FROM tags WHERE id = ?1",
+        params![tag_id],
+        |row| row.get(0),
+    )
+    .map_err(|_| "Tag not found".to_string())
+}
 
-    Ok(snippets)
+/// Normalize a user-supplied tag path and check it can be written inline.
+/// Names are lowercased like extracted inline tags so the two never split.
+fn valid_tag_path(name: &str) -> Result<String, String> {
+    let path = normalize_tag_path(name)
+        .ok_or_else(|| "Tag name must not be empty".to_string())?
+        .to_lowercase();
+    let inline = format!(" #{}", path);
+    let full_match = inline_tag_regex()
+        .captures(&inline)
+        .is_some_and(|cap| cap[1].len() == path.len());
+    if !full_match {
+        return Err(format!(
+            "Invalid tag \"{}\": segments must start with a letter and use only letters, digits, '-' or '_'",
+            path
+        ));
+    }
+    Ok(path)
 }
 
-#[tauri::command]
-pub fn delete_snippet(db: State<Database>, id: String) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM snippets WHERE id = ?1", params![id])
-        .map_err(|e| e.to_string())?;
-    Ok(())
+/// Whether `name` is `root` or one of its descendants.
+fn tag_in_subtree(name: &str, root: &str) -> bool {
+    name == root
+        || name
+            .strip_prefix(root)
+            .is_some_and(|rest| rest.starts_with('/'))
 }
 
-#[tauri::command]
-pub fn search_snippets(db: State<Database>, query: String) -> Result<Vec<SnippetData>, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
-    let pattern = format!("%{}%", query);
-    let mut stmt = conn
-        .prepare("SELECT id, title, content, language, tags, created_at FROM snippets WHERE title LIKE ?1 OR content LIKE ?1 OR tags LIKE ?1 ORDER BY created_at DESC")
-        .map_err(|e| e.to_string())?;
+/// Move the `from` subtree under `to` (or delete it when `to` is `None`),
+/// folding into tags that already exist there, then rewrite inline tag
+/// text in the affected notes and emit the graph change.
+fn retag_subtree(
+    app: &tauri::AppHandle,
+    conn: &mut rusqlite::Connection,
+    from: &str,
+    to: Option<&str>,
+    reason: &str,
+) -> Result<TagRewrite, String> {
+    let subtree: Vec<(String, String)> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, name FROM tags
+                 WHERE name = ?1 OR SUBSTR(name, 1, LENGTH(?1) + 1) = ?1 || '/'
+                 ORDER BY LENGTH(name)",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![from], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        rows
+    };
+    let note_ids: Vec<String> = {
+        let placeholders = vec!["?"; subtree.len()].join(", ");
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT DISTINCT note_id FROM note_tags WHERE tag_id IN ({})",
+                placeholders
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(
+                rusqlite::params_from_iter(subtree.iter().map(|t| &t.0)),
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        rows
+    };
 
-    let snippets = stmt
-        .query_map(params![pattern], |row| {
-            Ok(SnippetData {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                content: row.get(2)?,
-                language: row.get(3)?,
-                tags: row.get(4)?,
-                created_at: row.get(5)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .filter_map(|r| r.ok())
-        .collect();
+    let graph_before = graph_snapshot(conn, &note_ids).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
 
-    Ok(snippets)
+    // Parents come first, so a renamed child finds its new parent in place.
+    for (id, name) in &subtree {
+        let Some(to) = to else {
+            tx.execute("DELETE FROM tags WHERE id = ?1", params![id])
+                .map_err(|e| e.to_string())?;
+            continue;
+        };
+        let new_name = format!("{}{}", to, &name[from.len()..]);
+        let existing: Option<String> = tx
+            .query_row(
+                "SELECT id FROM tags WHERE name = ?1",
+                params![new_name],
+                |row| row.get(0),
+            )
+            .ok();
+        match existing {
+            Some(target_id) => {
+                tx.execute(
+                    "INSERT OR IGNORE INTO note_tags (note_id, tag_id, source)
+                     SELECT note_id, ?1, source FROM note_tags WHERE tag_id = ?2",
+                    params![target_id, id],
+                )
+                .map_err(|e| e.to_string())?;
+                tx.execute(
+                    "UPDATE tags SET parent_id = ?1 WHERE parent_id = ?2",
+                    params![target_id, id],
+                )
+                .map_err(|e| e.to_string())?;
+                tx.execute("DELETE FROM tags WHERE id = ?1", params![id])
+                    .map_err(|e| e.to_string())?;
+            }
+            None => {
+                let parent_id = match new_name.rsplit_once('/') {
+                    Some((parent, _)) => Some(ensure_tag(&tx, parent).map_err(|e| e.to_string())?),
+                    None => None,
+                };
+                tx.execute(
+                    "UPDATE tags SET name = ?1, parent_id = ?2 WHERE id = ?3",
+                    params![new_name, parent_id, id],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    // The tag rows are final now, so re-extracting tags from the rewritten
+    // text lands on them rather than recreating the old names.
+    let batch_id = Uuid::new_v4().to_string();
+    let mut notes = Vec::new();
+    for note_id in &note_ids {
+        let (title, content): (String, String) = tx
+            .query_row(
+                "SELECT title, COALESCE(content, '[]') FROM notes WHERE id = ?1",
+                params![note_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| e.to_string())?;
+        let Ok(mut nodes) = serde_json::from_str::<Vec<serde_json::Value>>(&content) else {
+            continue;
+        };
+        let replacements = rewrite_inline_tags(&mut nodes, from, to);
+        if replacements == 0 {
+            continue;
+        }
+        record_revision(&tx, note_id, Some(&batch_id), reason).map_err(|e| e.to_string())?;
+        write_note_nodes(&tx, note_id, &nodes).map_err(|e| e.to_string())?;
+        notes.push(RenameLinkUpdate {
+            note_id: note_id.clone(),
+            title,
+            replacements,
+        });
+    }
+
+    stamp_revision_batch(&tx, &batch_id).map_err(|e| e.to_string())?;
+    let batch_id = (!notes.is_empty()).then_some(batch_id);
+    let result = retag_result(&tx, to, notes, batch_id)?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let graph_after = graph_snapshot(conn, &note_ids).map_err(|e| e.to_string())?;
+    emit_graph_delta(app, graph_before, graph_after);
+    Ok(result)
 }
 
-// ─── Writing Stats Commands ──────────────────────────────
+fn retag_result(
+    conn: &rusqlite::Connection,
+    name: Option<&str>,
+    notes: Vec<RenameLinkUpdate>,
+    batch_id: Option<String>,
+) -> Result<TagRewrite, String> {
+    let tag_id = match name {
+        Some(name) => Some(
+            conn.query_row(
+                "SELECT id FROM tags WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?,
+        ),
+        None => None,
+    };
+    Ok(TagRewrite {
+        tag_id,
+        name: name.map(str::to_string),
+        total_replacements: notes.iter().map(|n| n.replacements).sum(),
+        notes,
+        batch_id,
+    })
+}
 
-#[derive(Debug, Serialize)]
-pub struct WritingStat {
-    pub date: String,
-    pub words_written: i64,
-    pub notes_edited: i64,
-    pub time_spent_seconds: i64,
+/// Rewrite inline `#from` and `#from/...` tags to `#to/...`, or drop their
+/// `#` when `to` is `None`. Matching is case-insensitive, like extraction.
+/// Returns the number of tags rewritten.
+fn rewrite_inline_tags(nodes: &mut [serde_json::Value], from: &str, to: Option<&str>) -> usize {
+    let from_lower = from.to_lowercase();
+    let mut count = 0;
+    for_each_text_leaf_mut(nodes, &mut |text| {
+        let rewritten = inline_tag_regex().replace_all(text, |cap: &regex::Captures| {
+            let whole = &cap[0];
+            let tag = &cap[1];
+            let in_subtree = tag.len() >= from.len()
+                && tag.is_char_boundary(from.len())
+                && tag[..from.len()].to_lowercase() == from_lower
+                && (tag.len() == from.len() || tag[from.len()..].starts_with('/'));
+            if !in_subtree {
+                return whole.to_string();
+            }
+            count += 1;
+            let lead = &whole[..whole.len() - tag.len() - 1];
+            match to {
+                Some(to) => format!("{}#{}{}", lead, to, &tag[from.len()..]),
+                None => format!("{}{}", lead, tag),
+            }
+        });
+        if let std::borrow::Cow::Owned(new_text) = rewritten {
+            *text = new_text;
+        }
+    });
+    count
 }
 
+// ─── Wikilink Commands ───────────────────────────────────
+
 #[tauri::command]
-pub fn record_writing_stat(
+pub fn sync_wikilinks(
+    app: tauri::AppHandle,
     db: State<Database>,
-    words_written: i64,
-    notes_edited: i64,
+    note_id: String,
+    target_titles: Vec<String>,
+    plain_text: Option<String>,
 ) -> Result<(), String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
 
-    conn.execute(
-        "INSERT INTO writing_stats (date, words_written, notes_edited)
-         VALUES (date('now'), ?1, ?2)
-         ON CONFLICT(date) DO UPDATE SET
-           words_written = writing_stats.words_written + excluded.words_written,
-           notes_edited = writing_stats.notes_edited + excluded.notes_edited",
-        params![words_written, notes_edited],
-    )
-    .map_err(|e| e.to_string())?;
+    // The editor's link extraction also matches inside `![[...]]`, so tell
+    // embeds apart using the text the titles were extracted from, falling
+    // back to the stored plain text for callers that don't pass it
+    let plain_text: String = match plain_text {
+        Some(text) => text,
+        None => conn
+            .query_row(
+                "SELECT COALESCE(plain_text, '') FROM notes WHERE id = ?1",
+                params![note_id],
+                |row| row.get(0),
+            )
+            .unwrap_or_default(),
+    };
+    let embeds = extract_embeds(&plain_text);
+    let inline_links = extract_wikilinks(&plain_text);
+    let links: Vec<String> = target_titles
+        .into_iter()
+        .filter(|t| !embeds.contains(t) || inline_links.contains(t))
+        .collect();
 
-    Ok(())
+    with_graph_delta(&app, &conn, std::slice::from_ref(&note_id), || {
+        apply_wikilinks(&conn, &note_id, &links, &embeds).map_err(|e| e.to_string())
+    })
 }
 
-#[tauri::command]
-pub fn get_writing_stats(db: State<Database>, days: i64) -> Result<Vec<WritingStat>, String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+/// Replace a note's outgoing wikilinks and embeds. Shared by
+/// `sync_wikilinks` and backend rewrites of note content.
+fn apply_wikilinks(
+    conn: &rusqlite::Connection,
+    note_id: &str,
+    target_titles: &[String],
+    embed_titles: &[String],
+) -> rusqlite::Result<()> {
+    // Links that already point at a note keep that target even if its title
+    // has changed since, so renames don't strand existing links
+    let mut existing: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT w.link_text, w.target_note_id FROM wikilinks w
+             JOIN notes n ON n.id = w.target_note_id
+             WHERE w.source_note_id = ?1",
+        )?;
+        let rows = stmt.query_map(params![note_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for (text, target) in rows.flatten() {
+            existing.insert(text, target);
+        }
+    }
 
-    let mut stmt = conn
-        .prepare(
-            "SELECT date, words_written, notes_edited, time_spent_seconds
-             FROM writing_stats
-             WHERE date >= date('now', ?1)
-             ORDER BY date ASC",
-        )
-        .map_err(|e| e.to_string())?;
+    // Remove old wikilinks from this note
+    conn.execute(
+        "DELETE FROM wikilinks WHERE source_note_id = ?1",
+        params![note_id],
+    )?;
 
-    let modifier = format!("-{} days", days);
-    let stats = stmt
-        .query_map(params![modifier], |row| {
-            Ok(WritingStat {
-                date: row.get(0)?,
-                words_written: row.get(1)?,
-                notes_edited: row.get(2)?,
-                time_spent_seconds: row.get(3)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .filter_map(|r| r.ok())
-        .collect();
+    // Insert new wikilinks; unresolved ones are kept with a null target.
+    // `[[#Heading]]` points inside the note itself and isn't a link between notes.
+    let refs = target_titles
+        .iter()
+        .map(|t| (t, "link"))
+        .chain(embed_titles.iter().map(|t| (t, "embed")));
+    for (title, link_type) in refs {
+        let link_title = link_title_part(title);
+        if link_title.is_empty() {
+            continue;
+        }
+        let target_id = match existing.get(title) {
+            Some(id) => Some(id.clone()),
+            None => resolve_link_target(conn, link_title, note_id)?,
+        };
 
-    Ok(stats)
+        conn.execute(
+            "INSERT OR IGNORE INTO wikilinks
+                 (source_note_id, target_note_id, link_text, anchor, link_type, link_title)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                note_id,
+                target_id,
+                title,
+                link_anchor_part(title),
+                link_type,
+                link_title
+            ],
+        )?;
+    }
+
+    Ok(())
 }
 
-// ─── Notes by Date (Calendar) ────────────────────────────
+/// Find the note a link text refers to: a case-insensitive title match,
+/// falling back to note aliases.
+fn resolve_link_target(
+    conn: &rusqlite::Connection,
+    link_text: &str,
+    source_note_id: &str,
+) -> rusqlite::Result<Option<String>> {
+    match conn.query_row(
+        "SELECT id, 0 AS priority FROM notes
+         WHERE LOWER(title) = LOWER(?1) AND is_trashed = 0 AND id != ?2
+         UNION ALL
+         SELECT a.note_id, 1 FROM note_aliases a
+         JOIN notes n ON n.id = a.note_id AND n.is_trashed = 0
+         WHERE LOWER(a.alias) = LOWER(?1) AND a.note_id != ?2
+         ORDER BY priority
+         LIMIT 1",
+        params![link_text, source_note_id],
+        |row| row.get(0),
+    ) {
+        Ok(id) => Ok(Some(id)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
 
-#[derive(Debug, Serialize)]
-pub struct NotesByDateItem {
-    pub id: String,
-    pub title: String,
-    pub emoji: Option<String>,
-    #[serde(rename = "createdAt")]
-    pub created_at: i64,
-    #[serde(rename = "updatedAt")]
-    pub updated_at: i64,
+/// Point unresolved links at a note whose title or an alias now matches
+/// their text. Called whenever a note is created, renamed or restored, or
+/// gains an alias.
+fn resolve_pending_links(conn: &rusqlite::Connection, note_id: &str) -> rusqlite::Result<()> {
+    let names: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT LOWER(title) FROM notes WHERE id = ?1 AND is_trashed = 0
+             UNION
+             SELECT LOWER(a.alias) FROM note_aliases a
+             JOIN notes n ON n.id = a.note_id AND n.is_trashed = 0
+             WHERE a.note_id = ?1",
+        )?;
+        let rows = stmt.query_map(params![note_id], |row| row.get(0))?;
+        rows.flatten().collect()
+    };
+
+    // Compare on the title part so `[[Note#Heading]]` resolves like `[[Note]]`.
+    // One plain equality per name keeps this on idx_wikilinks_unresolved;
+    // an IN (subquery) sends the planner through every unresolved link.
+    let mut stmt = conn.prepare(
+        "UPDATE wikilinks SET target_note_id = ?1
+         WHERE target_note_id IS NULL AND LOWER(link_title) = ?2 AND source_note_id != ?1",
+    )?;
+    for name in names {
+        stmt.execute(params![note_id, name])?;
+    }
+    Ok(())
 }
 
 #[tauri::command]
-pub fn get_notes_by_date_range(
-    db: State<Database>,
-    start_ts: i64,
-    end_ts: i64,
-) -> Result<Vec<NotesByDateItem>, String> {
+pub fn get_backlinks(db: State<Database>, note_id: String) -> Result<Vec<BacklinkItem>, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
-
     let mut stmt = conn
         .prepare(
-            "SELECT id, title, emoji, created_at, updated_at FROM notes
-             WHERE is_trashed = 0 AND is_folder = 0
-             AND (created_at BETWEEN ?1 AND ?2 OR updated_at BETWEEN ?1 AND ?2)
-             ORDER BY updated_at DESC",
+            "SELECT n.id, n.title, n.emoji, w.link_type, w.anchor, w.link_text,
+                    COALESCE(n.content, '[]')
+             FROM wikilinks w
+             JOIN notes n ON n.id = w.source_note_id
+             WHERE w.target_note_id = ?1 AND n.is_trashed = 0
+             ORDER BY w.link_type DESC, n.title, n.id, w.anchor",
         )
         .map_err(|e| e.to_string())?;
 
-    let notes = stmt
-        .query_map(params![start_ts, end_ts], |row| {
-            Ok(NotesByDateItem {
+    let rows: Vec<BacklinkRow> = stmt
+        .query_map(params![note_id], |row| {
+            Ok(BacklinkRow {
                 id: row.get(0)?,
                 title: row.get(1)?,
                 emoji: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
+                link_type: row.get(3)?,
+                anchor: row.get(4)?,
+                link_text: row.get(5)?,
+                content: row.get(6)?,
             })
         })
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
 
-    Ok(notes)
+    // One entry per linking note and link type (links first, then embeds),
+    // with the anchors it points at and the blocks the link sits in
+    let mut backlinks: Vec<BacklinkItem> = Vec::new();
+    let mut needles: Vec<(String, Vec<String>)> = Vec::new();
+    for BacklinkRow {
+        id,
+        title,
+        emoji,
+        link_type,
+        anchor,
+        link_text,
+        content,
+    } in rows
+    {
+        let is_new_source = match backlinks.last() {
+            Some(last) => last.id != id || last.link_type != link_type,
+            None => true,
+        };
+        if is_new_source {
+            backlinks.push(BacklinkItem {
+                id,
+                title,
+                emoji,
+                link_type: link_type.clone(),
+                anchors: Vec::new(),
+                contexts: Vec::new(),
+            });
+            needles.push((content, Vec::new()));
+        }
+        if let Some((_, titles)) = needles.last_mut() {
+            titles.push(link_title_part(&link_text).to_lowercase());
+        }
+        if let Some(anchor) = anchor {
+            let resolved = resolve_anchor(&conn, &note_id, &anchor).map_err(|e| e.to_string())?;
+            let entry = backlinks.last_mut().expect("entry pushed above");
+            entry.anchors.push(BacklinkAnchor {
+                found: resolved.is_some(),
+                kind: resolved.as_ref().map(|a| a.kind.clone()),
+                text: resolved.as_ref().map(|a| a.text.clone()),
+                position: resolved.map(|a| a.position),
+                anchor,
+            });
+        }
+    }
+
+    // Blocks are matched on the links parsed out of them, so spacing, anchors
+    // and `|alias` inside the brackets don't matter
+    for (backlink, (content, titles)) in backlinks.iter_mut().zip(needles) {
+        let nodes: Vec<serde_json::Value> = serde_json::from_str(&content).unwrap_or_default();
+        let embeds = backlink.link_type == "embed";
+        backlink.contexts = plate_text_blocks(&nodes)
+            .into_iter()
+            .filter(|block| {
+                extract_link_refs(block, embeds)
+                    .iter()
+                    .any(|link| titles.contains(&link_title_part(link).to_lowercase()))
+            })
+            .collect();
+    }
+
+    Ok(backlinks)
 }
 
-// ─── Kanban: notes by tag ────────────────────────────────
+struct BacklinkRow {
+    id: String,
+    title: String,
+    emoji: Option<String>,
+    link_type: String,
+    anchor: Option<String>,
+    link_text: String,
+    content: String,
+}
+
+// ─── Unlinked Mentions ───────────────────────────────────
 
 #[derive(Debug, Serialize)]
-pub struct KanbanCard {
-    pub id: String,
+pub struct UnlinkedMention {
+    #[serde(rename = "noteId")]
+    pub note_id: String,
     pub title: String,
     pub emoji: Option<String>,
-    pub preview: String,
-    #[serde(rename = "updatedAt")]
-    pub updated_at: i64,
-    pub tags: Vec<String>,
+    pub mentions: Vec<MentionContext>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MentionContext {
+    /// Index of the mention within its note, passed back to `link_mention`
+    pub occurrence: usize,
+    pub text: String,
+    pub context: String,
 }
 
+/// Notes that mention this note's title or one of its aliases as plain text
+/// rather than through a wikilink. `notes_fts` narrows the candidates, then
+/// each note's blocks are scanned for whole-word matches outside links.
 #[tauri::command]
-pub fn get_kanban_data(db: State<Database>) -> Result<Vec<KanbanCard>, String> {
+pub fn get_unlinked_mentions(
+    db: State<Database>,
+    note_id: String,
+) -> Result<Vec<UnlinkedMention>, String> {
     let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let names = note_names(&conn, &note_id).map_err(|e| e.to_string())?;
+    let Some(re) = mention_regex(&names) else {
+        return Ok(Vec::new());
+    };
+
+    let phrases: Vec<String> = names
+        .iter()
+        .filter(|n| n.chars().any(|c| c.is_alphanumeric()))
+        .map(|n| format!("\"{}\"", n.replace('"', "\"\"")))
+        .collect();
+    if phrases.is_empty() {
+        return Ok(Vec::new());
+    }
+    let fts_query = format!("plain_text : ({})", phrases.join(" OR "));
 
-    // Get all non-trashed, non-folder notes that have tags
     let mut stmt = conn
         .prepare(
-            "SELECT DISTINCT n.id, n.title, n.emoji, SUBSTR(COALESCE(n.plain_text, ''), 1, 100), COALESCE(n.updated_at, 0)
-             FROM notes n
-             JOIN note_tags nt ON nt.note_id = n.id
-             WHERE n.is_trashed = 0 AND n.is_folder = 0
-             ORDER BY n.updated_at DESC",
+            "SELECT n.id, n.title, n.emoji, COALESCE(n.content, '[]')
+             FROM notes_fts
+             JOIN notes n ON n.rowid = notes_fts.rowid
+             WHERE notes_fts MATCH ?1 AND n.id != ?2 AND n.is_trashed = 0 AND n.is_folder = 0
+             ORDER BY rank
+             LIMIT 200",
         )
         .map_err(|e| e.to_string())?;
-
-    let mut cards: Vec<KanbanCard> = stmt
-        .query_map([], |row| {
-            Ok(KanbanCard {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                emoji: row.get(2)?,
-                preview: row.get(3)?,
-                updated_at: row.get(4)?,
-                tags: vec![],
-            })
+    let candidates: Vec<(String, String, Option<String>, String)> = stmt
+        .query_map(params![fts_query, note_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
         })
         .map_err(|e| e.to_string())?
         .filter_map(|r| r.ok())
         .collect();
 
-    // Fill in tags for each card
-    for card in &mut cards {
-        let mut tag_stmt = conn
-            .prepare("SELECT t.name FROM note_tags nt JOIN tags t ON t.id = nt.tag_id WHERE nt.note_id = ?1")
-            .map_err(|e| e.to_string())?;
-        card.tags = tag_stmt
-            .query_map(params![card.id], |row| row.get(0))
-            .map_err(|e| e.to_string())?
-            .filter_map(|r| r.ok())
-            .collect();
-    }
+    let mut results = Vec::new();
+    for (id, title, emoji, content) in candidates {
+        let mut nodes: Vec<serde_json::Value> = serde_json::from_str(&content).unwrap_or_default();
+        let mut mentions = Vec::new();
+        walk_text_blocks_mut(&mut nodes, &mut |children| {
+            let context = plate_plain_text(children);
+            for_each_mention_leaf_mut(children, &mut |text| {
+                for (start, end) in mention_ranges(text, &re) {
+                    mentions.push(MentionContext {
+                        occurrence: mentions.len(),
+                        text: text[start..end].to_string(),
+                        context: context.clone(),
+                    });
+                }
+            });
+        });
+        if !mentions.is_empty() {
+            results.push(UnlinkedMention {
+                note_id: id,
+                title,
+                emoji,
+                mentions,
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+/// Turn one unlinked mention of `target_note_id` in `source_note_id` into a
+/// `[[wikilink]]`, keeping the mention's own wording so it still resolves by
+/// title or alias. `occurrence` comes from `get_unlinked_mentions` and
+/// defaults to the first mention. Returns whether anything changed.
+#[tauri::command]
+pub fn link_mention(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    source_note_id: String,
+    target_note_id: String,
+    occurrence: Option<usize>,
+) -> Result<bool, String> {
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let graph_ids = [source_note_id.clone()];
+    let graph_before = graph_snapshot(&conn, &graph_ids).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let names = note_names(&tx, &target_note_id).map_err(|e| e.to_string())?;
+    let Some(re) = mention_regex(&names) else {
+        return Ok(false);
+    };
+    let content: String = tx
+        .query_row(
+            "SELECT COALESCE(content, '[]') FROM notes WHERE id = ?1",
+            params![source_note_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let mut nodes: Vec<serde_json::Value> = serde_json::from_str(&content).unwrap_or_default();
+
+    let wanted = occurrence.unwrap_or(0);
+    let mut seen = 0;
+    let mut linked = false;
+    walk_text_blocks_mut(&mut nodes, &mut |children| {
+        for_each_mention_leaf_mut(children, &mut |text| {
+            if linked {
+                return;
+            }
+            for (start, end) in mention_ranges(text, &re) {
+                if seen == wanted {
+                    text.replace_range(start..end, &format!("[[{}]]", &text[start..end]));
+                    linked = true;
+                    return;
+                }
+                seen += 1;
+            }
+        });
+    });
+    if !linked {
+        return Ok(false);
+    }
+
+    record_revision(&tx, &source_note_id, None, "link_mention").map_err(|e| e.to_string())?;
+    write_note_nodes(&tx, &source_note_id, &nodes).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    let graph_after = graph_snapshot(&conn, &graph_ids).map_err(|e| e.to_string())?;
+    emit_graph_delta(&app, graph_before, graph_after);
+    Ok(true)
+}
+
+/// A note's title followed by its aliases.
+fn note_names(conn: &rusqlite::Connection, note_id: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT title FROM notes WHERE id = ?1
+         UNION ALL
+         SELECT alias FROM note_aliases WHERE note_id = ?1",
+    )?;
+    let names = stmt
+        .query_map(params![note_id], |row| row.get::<_, String>(0))?
+        .filter_map(|r| r.ok())
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .collect();
+    Ok(names)
+}
+
+/// Case-insensitive matcher for any of `names`, longest first so "Rust Book"
+/// wins over "Rust". The name is capture group 1; the text around it must be
+/// a non-word character or the end, which unlike `\b` also works for names
+/// ending in punctuation such as "C++".
+fn mention_regex(names: &[String]) -> Option<regex::Regex> {
+    let mut names: Vec<&String> = names.iter().collect();
+    if names.is_empty() {
+        return None;
+    }
+    names.sort_by_key(|n| std::cmp::Reverse(n.chars().count()));
+    let alternation = names
+        .iter()
+        .map(|n| regex::escape(n))
+        .collect::<Vec<_>>()
+        .join("|");
+    regex::Regex::new(&format!(r"(?i)(?:^|[^\w])({})(?:[^\w]|$)", alternation)).ok()
+}
+
+/// Byte ranges of the names `re` finds in `text`, skipping any already
+/// inside `[[...]]`.
+fn mention_ranges(text: &str, re: &regex::Regex) -> Vec<(usize, usize)> {
+    static LINK_RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let link_re = LINK_RE.get_or_init(|| regex::Regex::new(r"\[\[[^\]]*\]\]").unwrap());
+
+    let links: Vec<(usize, usize)> = link_re
+        .find_iter(text)
+        .map(|m| (m.start(), m.end()))
+        .collect();
+    // The boundary after one match may be the one before the next, so each
+    // search resumes right after the name rather than after the whole match
+    let mut ranges = Vec::new();
+    let mut at = 0;
+    while let Some(name) = re.captures_at(text, at).and_then(|caps| caps.get(1)) {
+        let (start, end) = (name.start(), name.end());
+        if !links.iter().any(|(ls, le)| start < *le && end > *ls) {
+            ranges.push((start, end));
+        }
+        at = end;
+    }
+    ranges
+}
+
+/// Call `f` with the children of every block that directly holds text
+/// (paragraphs, headings, list items), in document order.
+fn walk_text_blocks_mut(
+    nodes: &mut [serde_json::Value],
+    f: &mut dyn FnMut(&mut [serde_json::Value]),
+) {
+    for node in nodes {
+        if let Some(serde_json::Value::Array(children)) = node.get_mut("children") {
+            if children.iter().any(|c| c.get("text").is_some()) {
+                f(children);
+            } else {
+                walk_text_blocks_mut(children, f);
+            }
+        }
+    }
+}
+
+/// Text leaves of a block that a mention may be linked in: everything
+/// except the text of hyperlinks.
+fn for_each_mention_leaf_mut(children: &mut [serde_json::Value], f: &mut dyn FnMut(&mut String)) {
+    for child in children {
+        if child.get("type").and_then(|t| t.as_str()) == Some("a") {
+            continue;
+        }
+        if let Some(serde_json::Value::String(text)) = child.get_mut("text") {
+            f(text);
+        } else if let Some(serde_json::Value::Array(inner)) = child.get_mut("children") {
+            for_each_text_leaf_mut(inner, f);
+        }
+    }
+}
+
+// ─── Link Reports ────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct OutgoingLink {
+    #[serde(rename = "linkText")]
+    pub link_text: String,
+    #[serde(rename = "linkType")]
+    pub link_type: String,
+    pub anchor: Option<String>,
+    #[serde(rename = "targetId")]
+    pub target_id: Option<String>,
+    #[serde(rename = "targetTitle")]
+    pub target_title: Option<String>,
+    #[serde(rename = "targetEmoji")]
+    pub target_emoji: Option<String>,
+    pub status: String, // "ok", "missing", "trashed" or "anchor_missing"
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeadLink {
+    #[serde(rename = "sourceId")]
+    pub source_id: String,
+    #[serde(rename = "sourceTitle")]
+    pub source_title: String,
+    #[serde(rename = "linkText")]
+    pub link_text: String,
+    #[serde(rename = "linkType")]
+    pub link_type: String,
+    #[serde(rename = "targetId")]
+    pub target_id: Option<String>,
+    #[serde(rename = "targetTitle")]
+    pub target_title: Option<String>,
+    pub reason: String, // "missing", "trashed" or "anchor_missing"
+}
+
+#[derive(Debug, Serialize)]
+pub struct LinkReportNote {
+    pub id: String,
+    pub title: String,
+    pub emoji: Option<String>,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LinkedNoteCount {
+    pub id: String,
+    pub title: String,
+    pub emoji: Option<String>,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LinkHealthReport {
+    #[serde(rename = "totalNotes")]
+    pub total_notes: i64,
+    #[serde(rename = "totalLinks")]
+    pub total_links: i64,
+    #[serde(rename = "resolvedLinks")]
+    pub resolved_links: i64,
+    #[serde(rename = "missingTargets")]
+    pub missing_targets: i64,
+    #[serde(rename = "trashedTargets")]
+    pub trashed_targets: i64,
+    #[serde(rename = "brokenAnchors")]
+    pub broken_anchors: i64,
+    #[serde(rename = "orphanCount")]
+    pub orphan_count: i64,
+    #[serde(rename = "noOutgoingCount")]
+    pub no_outgoing_count: i64,
+    #[serde(rename = "noIncomingCount")]
+    pub no_incoming_count: i64,
+    #[serde(rename = "aliasConflicts")]
+    pub alias_conflicts: i64,
+    #[serde(rename = "mostLinked")]
+    pub most_linked: Vec<LinkedNoteCount>,
+    #[serde(rename = "deadLinks")]
+    pub dead_links: Vec<DeadLink>,
+    pub orphans: Vec<LinkReportNote>,
+}
+
+/// Everything a note links to or embeds, with the state of each target.
+#[tauri::command]
+pub fn get_outgoing_links(
+    db: State<Database>,
+    note_id: String,
+) -> Result<Vec<OutgoingLink>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT w.link_text, w.link_type, w.anchor, w.target_note_id, t.title, t.emoji,
+                    t.is_trashed
+             FROM wikilinks w
+             LEFT JOIN notes t ON t.id = w.target_note_id
+             WHERE w.source_note_id = ?1
+             ORDER BY w.link_type DESC, LOWER(w.link_text)",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows: Vec<(OutgoingLink, Option<i64>)> = stmt
+        .query_map(params![note_id], |row| {
+            Ok((
+                OutgoingLink {
+                    link_text: row.get(0)?,
+                    link_type: row.get(1)?,
+                    anchor: row.get(2)?,
+                    target_id: row.get(3)?,
+                    target_title: row.get(4)?,
+                    target_emoji: row.get(5)?,
+                    status: String::new(),
+                },
+                row.get(6)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut links = Vec::with_capacity(rows.len());
+    for (mut link, is_trashed) in rows {
+        link.status = link_status(
+            &conn,
+            link.target_id.as_deref(),
+            is_trashed,
+            link.anchor.as_deref(),
+        )
+        .map_err(|e| e.to_string())?
+        .to_string();
+        links.push(link);
+    }
+
+    Ok(links)
+}
+
+/// Notes with no links to or from any other live note.
+#[tauri::command]
+pub fn get_orphan_notes(db: State<Database>) -> Result<Vec<LinkReportNote>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    query_orphan_notes(&conn).map_err(|e| e.to_string())
+}
+
+/// Links whose target is trashed, doesn't exist, or lacks the heading or
+/// block the link points at.
+#[tauri::command]
+pub fn get_dead_links(db: State<Database>) -> Result<Vec<DeadLink>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    query_dead_links(&conn).map_err(|e| e.to_string())
+}
+
+/// Vault-wide link statistics for the maintenance view.
+#[tauri::command]
+pub fn get_link_health(db: State<Database>) -> Result<LinkHealthReport, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let count = |sql: &str| -> Result<i64, String> {
+        conn.query_row(sql, [], |row| row.get(0))
+            .map_err(|e| e.to_string())
+    };
+
+    let total_notes = count("SELECT COUNT(*) FROM notes WHERE is_trashed = 0 AND is_folder = 0")?;
+    let total_links = count(
+        "SELECT COUNT(*) FROM wikilinks w
+         JOIN notes s ON s.id = w.source_note_id AND s.is_trashed = 0",
+    )?;
+    let no_outgoing_count = count(
+        "SELECT COUNT(*) FROM notes n
+         WHERE n.is_trashed = 0 AND n.is_folder = 0
+           AND NOT EXISTS (
+               SELECT 1 FROM wikilinks w JOIN notes t ON t.id = w.target_note_id
+               WHERE w.source_note_id = n.id AND t.is_trashed = 0 AND t.id != n.id
+           )",
+    )?;
+    let no_incoming_count = count(
+        "SELECT COUNT(*) FROM notes n
+         WHERE n.is_trashed = 0 AND n.is_folder = 0
+           AND NOT EXISTS (
+               SELECT 1 FROM wikilinks w JOIN notes s ON s.id = w.source_note_id
+               WHERE w.target_note_id = n.id AND s.is_trashed = 0 AND s.id != n.id
+           )",
+    )?;
+    let alias_conflicts = count(
+        "SELECT COUNT(*) FROM note_aliases a
+         JOIN notes owner ON owner.id = a.note_id AND owner.is_trashed = 0
+         JOIN notes other ON LOWER(other.title) = LOWER(a.alias)
+             AND other.id != a.note_id AND other.is_trashed = 0 AND other.is_folder = 0",
+    )?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.id, t.title, t.emoji, COUNT(DISTINCT w.source_note_id) AS c
+             FROM wikilinks w
+             JOIN notes t ON t.id = w.target_note_id AND t.is_trashed = 0
+             JOIN notes s ON s.id = w.source_note_id AND s.is_trashed = 0
+             GROUP BY t.id
+             ORDER BY c DESC, t.title
+             LIMIT 10",
+        )
+        .map_err(|e| e.to_string())?;
+    let most_linked = stmt
+        .query_map([], |row| {
+            Ok(LinkedNoteCount {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                emoji: row.get(2)?,
+                count: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let dead_links = query_dead_links(&conn).map_err(|e| e.to_string())?;
+    let orphans = query_orphan_notes(&conn).map_err(|e| e.to_string())?;
+    let dead_count = |reason: &str| dead_links.iter().filter(|d| d.reason == reason).count() as i64;
+
+    Ok(LinkHealthReport {
+        total_notes,
+        total_links,
+        resolved_links: total_links - dead_count("missing") - dead_count("trashed"),
+        missing_targets: dead_count("missing"),
+        trashed_targets: dead_count("trashed"),
+        broken_anchors: dead_count("anchor_missing"),
+        orphan_count: orphans.len() as i64,
+        no_outgoing_count,
+        no_incoming_count,
+        alias_conflicts,
+        most_linked,
+        dead_links,
+        orphans,
+    })
+}
+
+/// Classify a link by its target: "missing", "trashed", "anchor_missing"
+/// or "ok".
+fn link_status(
+    conn: &rusqlite::Connection,
+    target_id: Option<&str>,
+    target_trashed: Option<i64>,
+    anchor: Option<&str>,
+) -> rusqlite::Result<&'static str> {
+    let Some(target_id) = target_id else {
+        return Ok("missing");
+    };
+    match target_trashed {
+        None => return Ok("missing"),
+        Some(t) if t != 0 => return Ok("trashed"),
+        _ => {}
+    }
+    if let Some(anchor) = anchor {
+        if resolve_anchor(conn, target_id, anchor)?.is_none() {
+            return Ok("anchor_missing");
+        }
+    }
+    Ok("ok")
+}
+
+fn query_dead_links(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<DeadLink>> {
+    let mut stmt = conn.prepare(
+        "SELECT w.source_note_id, s.title, w.link_text, w.link_type, w.anchor,
+                w.target_note_id, t.title, t.is_trashed
+         FROM wikilinks w
+         JOIN notes s ON s.id = w.source_note_id AND s.is_trashed = 0
+         LEFT JOIN notes t ON t.id = w.target_note_id
+         WHERE w.target_note_id IS NULL OR t.id IS NULL OR t.is_trashed = 1
+            OR w.anchor IS NOT NULL
+         ORDER BY s.title, LOWER(w.link_text)",
+    )?;
+    let rows: Vec<(DeadLink, Option<String>, Option<i64>)> = stmt
+        .query_map([], |row| {
+            Ok((
+                DeadLink {
+                    source_id: row.get(0)?,
+                    source_title: row.get(1)?,
+                    link_text: row.get(2)?,
+                    link_type: row.get(3)?,
+                    target_id: row.get(5)?,
+                    target_title: row.get(6)?,
+                    reason: String::new(),
+                },
+                row.get(4)?,
+                row.get(7)?,
+            ))
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut dead = Vec::new();
+    for (mut link, anchor, is_trashed) in rows {
+        let status = link_status(
+            conn,
+            link.target_id.as_deref(),
+            is_trashed,
+            anchor.as_deref(),
+        )?;
+        if status != "ok" {
+            link.reason = status.to_string();
+            dead.push(link);
+        }
+    }
+    Ok(dead)
+}
+
+fn query_orphan_notes(conn: &rusqlite::Connection) -> rusqlite::Result<Vec<LinkReportNote>> {
+    let mut stmt = conn.prepare(
+        "SELECT n.id, n.title, n.emoji, n.updated_at FROM notes n
+         WHERE n.is_trashed = 0 AND n.is_folder = 0
+           AND NOT EXISTS (
+               SELECT 1 FROM wikilinks w JOIN notes t ON t.id = w.target_note_id
+               WHERE w.source_note_id = n.id AND t.is_trashed = 0 AND t.id != n.id
+           )
+           AND NOT EXISTS (
+               SELECT 1 FROM wikilinks w JOIN notes s ON s.id = w.source_note_id
+               WHERE w.target_note_id = n.id AND s.is_trashed = 0 AND s.id != n.id
+           )
+         ORDER BY n.updated_at DESC",
+    )?;
+    let orphans = stmt
+        .query_map([], |row| {
+            Ok(LinkReportNote {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                emoji: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(orphans)
+}
+
+// ─── Heading & Block Anchors ─────────────────────────────
+
+/// Headings and `^blockid`-tagged blocks of a note, in document order.
+/// `query` narrows the list for `[[Note#` autocomplete.
+#[tauri::command]
+pub fn get_note_anchors(
+    db: State<Database>,
+    note_id: String,
+    query: Option<String>,
+) -> Result<Vec<NoteAnchor>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let pattern = format!(
+        "%{}%",
+        like_escape(query.as_deref().unwrap_or("").trim_start_matches('^'))
+    );
+    let mut stmt = conn
+        .prepare(
+            "SELECT kind, anchor, text, level, position FROM note_anchors
+             WHERE note_id = ?1 AND anchor LIKE ?2 ESCAPE '\\'
+             ORDER BY position",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let anchors = stmt
+        .query_map(params![note_id, pattern], map_note_anchor)
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(anchors)
+}
+
+fn map_note_anchor(row: &rusqlite::Row) -> rusqlite::Result<NoteAnchor> {
+    Ok(NoteAnchor {
+        kind: row.get(0)?,
+        anchor: row.get(1)?,
+        text: row.get(2)?,
+        level: row.get(3)?,
+        position: row.get(4)?,
+    })
+}
+
+/// Look up the anchor a link points at. Nested heading paths such as
+/// `Setup#Linux` match on their last segment.
+fn resolve_anchor(
+    conn: &rusqlite::Connection,
+    note_id: &str,
+    anchor: &str,
+) -> rusqlite::Result<Option<NoteAnchor>> {
+    let last = anchor.rsplit('#').next().unwrap_or(anchor).trim();
+    match conn.query_row(
+        "SELECT kind, anchor, text, level, position FROM note_anchors
+         WHERE note_id = ?1 AND LOWER(anchor) = LOWER(?2)
+         ORDER BY position LIMIT 1",
+        params![note_id, last],
+        map_note_anchor,
+    ) {
+        Ok(anchor) => Ok(Some(anchor)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[tauri::command]
+pub fn get_all_note_titles(db: State<Database>) -> Result<Vec<NoteTitleItem>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut aliases: std::collections::HashMap<String, Vec<String>> =
+        std::collections::HashMap::new();
+    let mut alias_stmt = conn
+        .prepare("SELECT note_id, alias FROM note_aliases ORDER BY alias")
+        .map_err(|e| e.to_string())?;
+    let alias_rows = alias_stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    for (note_id, alias) in alias_rows.flatten() {
+        aliases.entry(note_id).or_default().push(alias);
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT id, title FROM notes WHERE is_trashed = 0 AND is_folder = 0 ORDER BY title")
+        .map_err(|e| e.to_string())?;
+
+    let titles = stmt
+        .query_map([], |row| {
+            let id: String = row.get(0)?;
+            Ok(NoteTitleItem {
+                aliases: aliases.get(&id).cloned().unwrap_or_default(),
+                id,
+                title: row.get(1)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(titles)
+}
+
+#[tauri::command]
+pub fn find_note_by_title(db: State<Database>, title: String) -> Result<Option<String>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    resolve_link_target(&conn, &title, "").map_err(|e| e.to_string())
+}
+
+// ─── Note Embeds ─────────────────────────────────────────
+
+const EMBED_MAX_DEPTH: usize = 5;
+
+#[derive(Debug, Serialize)]
+pub struct EmbedResult {
+    pub reference: String,
+    #[serde(rename = "noteId")]
+    pub note_id: Option<String>,
+    pub title: Option<String>,
+    pub anchor: Option<String>,
+    /// "ok", "missing" (no such note), "anchor_missing", "cycle" or "depth"
+    pub status: String,
+    pub nodes: Vec<serde_json::Value>,
+}
+
+/// Resolve an `![[Note]]` / `![[Note#Heading]]` / `![[Note#^blockid]]`
+/// reference to the Plate nodes it shows. Blocks inside the result that
+/// consist of a single nested embed are expanded in place into
+/// `{"type": "embed", ...}` elements, stopping at cycles and after
+/// `EMBED_MAX_DEPTH` levels.
+#[tauri::command]
+pub fn resolve_embed(
+    db: State<Database>,
+    reference: String,
+    source_note_id: Option<String>,
+) -> Result<EmbedResult, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stack: Vec<String> = source_note_id.into_iter().collect();
+    resolve_embed_reference(&conn, reference.trim(), &mut stack).map_err(|e| e.to_string())
+}
+
+fn resolve_embed_reference(
+    conn: &rusqlite::Connection,
+    reference: &str,
+    stack: &mut Vec<String>,
+) -> rusqlite::Result<EmbedResult> {
+    let anchor = link_anchor_part(reference);
+    let mut result = EmbedResult {
+        reference: reference.to_string(),
+        note_id: None,
+        title: None,
+        anchor: anchor.clone(),
+        status: "missing".to_string(),
+        nodes: Vec::new(),
+    };
+
+    // The source itself is not excluded: `![[Self]]` is a cycle, not missing
+    let Some(target_id) = resolve_link_target(conn, link_title_part(reference), "")? else {
+        return Ok(result);
+    };
+    let (title, content): (String, String) = conn.query_row(
+        "SELECT title, COALESCE(content, '[]') FROM notes WHERE id = ?1",
+        params![target_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    result.note_id = Some(target_id.clone());
+    result.title = Some(title);
+
+    if stack.contains(&target_id) {
+        result.status = "cycle".to_string();
+        return Ok(result);
+    }
+    if stack.len() > EMBED_MAX_DEPTH {
+        result.status = "depth".to_string();
+        return Ok(result);
+    }
+
+    let nodes: Vec<serde_json::Value> = serde_json::from_str(&content).unwrap_or_default();
+    let mut nodes = match &anchor {
+        Some(anchor) => match plate_anchor_subtree(&nodes, anchor) {
+            Some(subtree) => subtree,
+            None => {
+                result.status = "anchor_missing".to_string();
+                return Ok(result);
+            }
+        },
+        None => nodes,
+    };
+
+    stack.push(target_id);
+    for node in nodes.iter_mut() {
+        expand_nested_embeds(conn, node, stack)?;
+    }
+    stack.pop();
+
+    result.status = "ok".to_string();
+    result.nodes = nodes;
+    Ok(result)
+}
+
+/// Replace blocks whose whole text is a single `![[...]]` with the embedded
+/// content, recursing through containers such as blockquotes and columns.
+fn expand_nested_embeds(
+    conn: &rusqlite::Connection,
+    node: &mut serde_json::Value,
+    stack: &mut Vec<String>,
+) -> rusqlite::Result<()> {
+    static EMBED_BLOCK_RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let re =
+        EMBED_BLOCK_RE.get_or_init(|| regex::Regex::new(r"^\s*!\[\[([^\]]+)\]\]\s*$").unwrap());
+
+    let Some(children) = node.get_mut("children").and_then(|c| c.as_array_mut()) else {
+        return Ok(());
+    };
+
+    if children.iter().any(|c| c.get("text").is_some()) {
+        let text = plate_plain_text(children);
+        if let Some(cap) = re.captures(text.trim()) {
+            let embed = resolve_embed_reference(conn, cap[1].trim(), stack)?;
+            let inner = if embed.nodes.is_empty() {
+                vec![serde_json::json!({ "text": "" })]
+            } else {
+                embed.nodes
+            };
+            *node = serde_json::json!({
+                "type": "embed",
+                "reference": embed.reference,
+                "noteId": embed.note_id,
+                "title": embed.title,
+                "anchor": embed.anchor,
+                "status": embed.status,
+                "children": inner,
+            });
+        }
+        return Ok(());
+    }
+
+    for child in children.iter_mut() {
+        expand_nested_embeds(conn, child, stack)?;
+    }
+    Ok(())
+}
+
+/// The nodes an anchor refers to: a `^blockid` block on its own, or a
+/// heading together with everything up to the next heading of the same or
+/// a higher level.
+fn plate_anchor_subtree(
+    nodes: &[serde_json::Value],
+    anchor: &str,
+) -> Option<Vec<serde_json::Value>> {
+    let last = anchor.rsplit('#').next().unwrap_or(anchor).trim();
+
+    if let Some(block_id) = last.strip_prefix('^') {
+        fn find_block<'a>(
+            nodes: &'a [serde_json::Value],
+            block_id: &str,
+        ) -> Option<&'a serde_json::Value> {
+            for node in nodes {
+                let Some(children) = node.get("children").and_then(|c| c.as_array()) else {
+                    continue;
+                };
+                if children.iter().any(|c| c.get("text").is_some()) {
+                    let text = plate_plain_text(children);
+                    if split_block_id(&text)
+                        .1
+                        .is_some_and(|id| id.eq_ignore_ascii_case(block_id))
+                    {
+                        return Some(node);
+                    }
+                }
+                if let Some(found) = find_block(children, block_id) {
+                    return Some(found);
+                }
+            }
+            None
+        }
+        return find_block(nodes, block_id).map(|n| vec![n.clone()]);
+    }
+
+    let heading_level = |node: &serde_json::Value| -> Option<usize> {
+        let node_type = node.get("type").and_then(|t| t.as_str())?;
+        let level = node_type.strip_prefix('h')?.parse::<usize>().ok()?;
+        (1..=6).contains(&level).then_some(level)
+    };
+
+    let start = nodes.iter().position(|node| {
+        heading_level(node).is_some()
+            && node
+                .get("children")
+                .and_then(|c| c.as_array())
+                .map(|c| {
+                    split_block_id(&plate_plain_text(c))
+                        .0
+                        .eq_ignore_ascii_case(last)
+                })
+                .unwrap_or(false)
+    })?;
+    let level = heading_level(&nodes[start])?;
+    let end = nodes[start + 1..]
+        .iter()
+        .position(|node| heading_level(node).is_some_and(|l| l <= level))
+        .map(|offset| start + 1 + offset)
+        .unwrap_or(nodes.len());
+
+    Some(nodes[start..end].to_vec())
+}
+
+// ─── Note Aliases ────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct AliasConflict {
+    pub alias: String,
+    #[serde(rename = "noteId")]
+    pub note_id: String,
+    #[serde(rename = "noteTitle")]
+    pub note_title: String,
+    #[serde(rename = "conflictingNoteId")]
+    pub conflicting_note_id: String,
+    #[serde(rename = "conflictingTitle")]
+    pub conflicting_title: String,
+}
+
+/// Error out if another note already answers to `alias`, either by title or
+/// by alias. Two notes claiming one name would make links ambiguous.
+fn check_alias_available(
+    conn: &rusqlite::Connection,
+    note_id: &str,
+    alias: &str,
+) -> Result<(), String> {
+    let claimed: Option<(String, String)> = conn
+        .query_row(
+            "SELECT n.title, 'alias' FROM note_aliases a JOIN notes n ON n.id = a.note_id
+             WHERE LOWER(a.alias) = LOWER(?1) AND a.note_id != ?2
+             UNION ALL
+             SELECT title, 'title' FROM notes
+             WHERE LOWER(title) = LOWER(?1) AND id != ?2 AND is_trashed = 0 AND is_folder = 0
+             LIMIT 1",
+            params![alias, note_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .ok();
+
+    match claimed {
+        Some((title, kind)) => Err(format!(
+            "\"{}\" is already the {} of \"{}\"",
+            alias,
+            if kind == "alias" { "alias" } else { "title" },
+            title
+        )),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub fn get_note_aliases(db: State<Database>, note_id: String) -> Result<Vec<String>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT alias FROM note_aliases WHERE note_id = ?1 ORDER BY alias")
+        .map_err(|e| e.to_string())?;
+
+    let aliases = stmt
+        .query_map(params![note_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(aliases)
+}
+
+#[tauri::command]
+pub fn add_note_alias(db: State<Database>, note_id: String, alias: String) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    add_alias(&conn, &note_id, &alias)
+}
+
+fn add_alias(conn: &rusqlite::Connection, note_id: &str, alias: &str) -> Result<(), String> {
+    let alias = alias.trim();
+    if alias.is_empty() {
+        return Err("Alias must not be empty".to_string());
+    }
+    check_alias_available(conn, note_id, alias)?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO note_aliases (note_id, alias) VALUES (?1, ?2)",
+        params![note_id, alias],
+    )
+    .map_err(|e| e.to_string())?;
+    resolve_pending_links(conn, note_id).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn remove_note_alias(
+    db: State<Database>,
+    note_id: String,
+    alias: String,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    remove_alias(&conn, &note_id, &alias).map_err(|e| e.to_string())
+}
+
+fn remove_alias(conn: &rusqlite::Connection, note_id: &str, alias: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM note_aliases WHERE note_id = ?1 AND LOWER(alias) = LOWER(?2)",
+        params![note_id, alias],
+    )?;
+    // Links that only reached this note through the alias become unresolved
+    let title: String = conn.query_row(
+        "SELECT title FROM notes WHERE id = ?1",
+        params![note_id],
+        |row| row.get(0),
+    )?;
+    let linked: Vec<(i64, String)> = {
+        let mut stmt =
+            conn.prepare("SELECT rowid, link_text FROM wikilinks WHERE target_note_id = ?1")?;
+        let rows = stmt
+            .query_map(params![note_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        rows
+    };
+    for (rowid, link_text) in linked {
+        let link_title = link_title_part(&link_text);
+        if link_title.eq_ignore_ascii_case(alias.trim()) && !link_title.eq_ignore_ascii_case(&title)
+        {
+            conn.execute(
+                "UPDATE wikilinks SET target_note_id = NULL WHERE rowid = ?1",
+                params![rowid],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace all aliases of a note. Fails without changes if any alias is
+/// already claimed by another note.
+#[tauri::command]
+pub fn set_note_aliases(
+    db: State<Database>,
+    note_id: String,
+    aliases: Vec<String>,
+) -> Result<(), String> {
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let current: Vec<String> = {
+        let mut stmt = tx
+            .prepare("SELECT alias FROM note_aliases WHERE note_id = ?1")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![note_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        rows
+    };
+    let wanted: Vec<String> = aliases
+        .iter()
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect();
+
+    for alias in &current {
+        if !wanted.iter().any(|w| w.eq_ignore_ascii_case(alias)) {
+            remove_alias(&tx, &note_id, alias).map_err(|e| e.to_string())?;
+        }
+    }
+    for alias in &wanted {
+        add_alias(&tx, &note_id, alias)?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Aliases that collide with another note's title. Alias-vs-alias clashes
+/// are rejected on insert, but titles can change after an alias is added.
+#[tauri::command]
+pub fn get_alias_conflicts(db: State<Database>) -> Result<Vec<AliasConflict>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT a.alias, owner.id, owner.title, other.id, other.title
+             FROM note_aliases a
+             JOIN notes owner ON owner.id = a.note_id AND owner.is_trashed = 0
+             JOIN notes other ON LOWER(other.title) = LOWER(a.alias)
+                 AND other.id != a.note_id AND other.is_trashed = 0 AND other.is_folder = 0
+             ORDER BY a.alias",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let conflicts = stmt
+        .query_map([], |row| {
+            Ok(AliasConflict {
+                alias: row.get(0)?,
+                note_id: row.get(1)?,
+                note_title: row.get(2)?,
+                conflicting_note_id: row.get(3)?,
+                conflicting_title: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(conflicts)
+}
+
+struct AliasMatch {
+    note_id: String,
+    title: String,
+    emoji: Option<String>,
+    alias: String,
+}
+
+/// Notes with an alias starting with `query`, for the quick switcher.
+fn query_alias_matches(
+    conn: &rusqlite::Connection,
+    query: &str,
+    limit: i64,
+) -> rusqlite::Result<Vec<AliasMatch>> {
+    let mut stmt = conn.prepare(
+        "SELECT n.id, n.title, n.emoji, a.alias FROM note_aliases a
+         JOIN notes n ON n.id = a.note_id AND n.is_trashed = 0
+         WHERE a.alias LIKE ?1 ESCAPE '\\'
+         ORDER BY LENGTH(a.alias)
+         LIMIT ?2",
+    )?;
+    let matches = stmt
+        .query_map(
+            params![format!("{}%", like_escape(query.trim())), limit],
+            |row| {
+                Ok(AliasMatch {
+                    note_id: row.get(0)?,
+                    title: row.get(1)?,
+                    emoji: row.get(2)?,
+                    alias: row.get(3)?,
+                })
+            },
+        )?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(matches)
+}
+
+// ─── Rename Propagation ──────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct RenameLinkUpdate {
+    #[serde(rename = "noteId")]
+    pub note_id: String,
+    pub title: String,
+    pub replacements: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenamePropagation {
+    #[serde(rename = "oldTitle")]
+    pub old_title: String,
+    #[serde(rename = "newTitle")]
+    pub new_title: String,
+    pub notes: Vec<RenameLinkUpdate>,
+    #[serde(rename = "totalReplacements")]
+    pub total_replacements: usize,
+    pub applied: bool,
+    #[serde(rename = "batchId")]
+    pub batch_id: Option<String>,
+}
+
+/// The note-title part of a link's text, without any `#anchor` or `|alias`.
+fn link_title_part(link_text: &str) -> &str {
+    link_text
+        .split(['#', '|'])
+        .next()
+        .unwrap_or(link_text)
+        .trim()
+}
+
+/// The `#Heading` / `#^blockid` part of a link's text, if any.
+fn link_anchor_part(link_text: &str) -> Option<String> {
+    let (_, rest) = link_text.split_once('#')?;
+    let anchor = rest.split('|').next().unwrap_or(rest).trim();
+    (!anchor.is_empty()).then(|| anchor.to_string())
+}
+
+/// Rewrite `[[Old]]`, `[[Old#...]]` and `[[Old|...]]` to point at `new_title`
+/// in every text leaf. Returns the number of links rewritten.
+fn rewrite_wikilink_titles(
+    nodes: &mut [serde_json::Value],
+    old_titles: &[String],
+    new_title: &str,
+) -> usize {
+    let alternatives: Vec<String> = old_titles.iter().map(|t| regex::escape(t)).collect();
+    if alternatives.is_empty() {
+        return 0;
+    }
+    let re = regex::Regex::new(&format!(
+        r"(?i)\[\[\s*(?:{})\s*([#|][^\]]*)?\]\]",
+        alternatives.join("|")
+    ))
+    .expect("escaped titles form a valid pattern");
+
+    let mut count = 0;
+    for_each_text_leaf_mut(nodes, &mut |text| {
+        let found = re.find_iter(text).count();
+        if found == 0 {
+            return;
+        }
+        count += found;
+        *text = re
+            .replace_all(text, |caps: &regex::Captures| {
+                format!(
+                    "[[{}{}]]",
+                    new_title,
+                    caps.get(1).map(|m| m.as_str()).unwrap_or("")
+                )
+            })
+            .into_owned();
+    });
+    count
+}
+
+/// Rename a note and rewrite every wikilink to it in the notes that link
+/// here. With `dry_run` (the default) only the per-note counts are returned.
+/// Applied renames are recorded as one revision batch, so
+/// `undo_find_and_replace` can revert them.
+#[tauri::command]
+pub fn rename_note_with_links(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    note_id: String,
+    new_title: String,
+    dry_run: Option<bool>,
+) -> Result<RenamePropagation, String> {
+    let new_title = new_title.trim().to_string();
+    if new_title.is_empty() {
+        return Err("Title must not be empty".to_string());
+    }
+    let dry_run = dry_run.unwrap_or(true);
+
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let graph_ids = [note_id.clone()];
+    let graph_before = graph_snapshot(&conn, &graph_ids).map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (old_title, own_content): (String, String) = tx
+        .query_row(
+            "SELECT title, COALESCE(content, '[]') FROM notes WHERE id = ?1",
+            params![note_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let aliases: Vec<String> = {
+        let mut stmt = tx
+            .prepare("SELECT LOWER(alias) FROM note_aliases WHERE note_id = ?1")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![note_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        rows
+    };
+
+    // Link texts that currently resolve to this note, grouped by source.
+    // Links written with one of the note's aliases are left alone.
+    let mut sources: Vec<(String, String, String, Vec<String>)> = Vec::new();
+    {
+        let mut stmt = tx
+            .prepare(
+                "SELECT w.source_note_id, n.title, COALESCE(n.content, '[]'), w.link_text
+                 FROM wikilinks w JOIN notes n ON n.id = w.source_note_id
+                 WHERE w.target_note_id = ?1 AND w.source_note_id != ?1
+                 ORDER BY n.title",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![note_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })
+            .map_err(|e| e.to_string())?;
+        for (source_id, title, content, link_text) in rows.flatten() {
+            let link_title = link_title_part(&link_text).to_string();
+            if aliases.contains(&link_title.to_lowercase()) {
+                continue;
+            }
+            match sources.iter_mut().find(|s| s.0 == source_id) {
+                Some(source) => {
+                    if !source.3.contains(&link_title) {
+                        source.3.push(link_title);
+                    }
+                }
+                None => sources.push((source_id, title, content, vec![link_title])),
+            }
+        }
+    }
+
+    let batch_id = Uuid::new_v4().to_string();
+    let mut result = RenamePropagation {
+        old_title: old_title.clone(),
+        new_title: new_title.clone(),
+        notes: vec![],
+        total_replacements: 0,
+        applied: false,
+        batch_id: None,
+    };
+
+    if !dry_run {
+        // Rename first so rewritten links resolve straight back to this note.
+        // The H1 is updated too, otherwise the next save would restore the
+        // old title from the document.
+        record_revision(&tx, &note_id, Some(&batch_id), "rename").map_err(|e| e.to_string())?;
+        let mut own_nodes: Vec<serde_json::Value> =
+            serde_json::from_str(&own_content).unwrap_or_default();
+        let heading = own_nodes
+            .iter_mut()
+            .find(|n| n.get("type").and_then(|t| t.as_str()) == Some("h1"));
+        match heading {
+            Some(h1)
+                if plate_title(std::slice::from_ref(h1)).as_deref() == Some(old_title.as_str()) =>
+            {
+                h1["children"] = serde_json::json!([{ "text": new_title }]);
+                write_note_nodes(&tx, &note_id, &own_nodes).map_err(|e| e.to_string())?;
+            }
+            _ => {
+                tx.execute(
+                    "UPDATE notes SET title = ?1, updated_at = unixepoch() WHERE id = ?2",
+                    params![new_title, note_id],
+                )
+                .map_err(|e| e.to_string())?;
+                resolve_pending_links(&tx, &note_id).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    for (source_id, title, content, link_titles) in sources {
+        let Ok(mut nodes) = serde_json::from_str::<Vec<serde_json::Value>>(&content) else {
+            continue;
+        };
+        let replacements = rewrite_wikilink_titles(&mut nodes, &link_titles, &new_title);
+        if replacements == 0 {
+            continue;
+        }
+
+        if !dry_run {
+            record_revision(&tx, &source_id, Some(&batch_id), "rename")
+                .map_err(|e| e.to_string())?;
+            // Carry the existing rows over to the new text so the links stay
+            // bound to this note's id instead of being re-resolved by title
+            for link_title in &link_titles {
+                tx.execute(
+                    "UPDATE OR IGNORE wikilinks
+                     SET link_text = ?1 || SUBSTR(link_text, LENGTH(?2) + 1), link_title = ?1
+                     WHERE source_note_id = ?3 AND target_note_id = ?4
+                       AND LOWER(SUBSTR(link_text, 1, LENGTH(?2))) = LOWER(?2)",
+                    params![new_title, link_title, source_id, note_id],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            write_note_nodes(&tx, &source_id, &nodes).map_err(|e| e.to_string())?;
+        }
+
+        result.total_replacements += replacements;
+        result.notes.push(RenameLinkUpdate {
+            note_id: source_id,
+            title,
+            replacements,
+        });
+    }
+
+    if !dry_run {
+        stamp_revision_batch(&tx, &batch_id).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        result.applied = true;
+        result.batch_id = Some(batch_id);
+
+        let graph_after = graph_snapshot(&conn, &graph_ids).map_err(|e| e.to_string())?;
+        emit_graph_delta(&app, graph_before, graph_after);
+    }
+
+    Ok(result)
+}
+
+// ─── Graph Command ───────────────────────────────────────
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GraphFilter {
+    /// Only notes inside these folders (at any depth)
+    #[serde(rename = "folderIds")]
+    pub folder_ids: Option<Vec<String>>,
+    /// Only notes carrying at least one of these tags
+    pub tags: Option<Vec<String>>,
+    /// Any of "wikilink", "embed" and "tag"; all three by default
+    #[serde(rename = "edgeTypes")]
+    pub edge_types: Option<Vec<String>>,
+    #[serde(rename = "createdAfter")]
+    pub created_after: Option<i64>,
+    #[serde(rename = "createdBefore")]
+    pub created_before: Option<i64>,
+    #[serde(rename = "updatedAfter")]
+    pub updated_after: Option<i64>,
+    #[serde(rename = "updatedBefore")]
+    pub updated_before: Option<i64>,
+    /// Keep notes without any edge in the result; true by default
+    #[serde(rename = "includeOrphans")]
+    pub include_orphans: Option<bool>,
+    /// Show tags as nodes linked to their notes instead of an edge between
+    /// every pair of notes sharing a tag
+    #[serde(rename = "tagNodes")]
+    pub tag_nodes: Option<bool>,
+}
+
+#[tauri::command]
+pub fn get_graph_data(
+    db: State<Database>,
+    filter: Option<GraphFilter>,
+) -> Result<GraphData, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let filter = filter.unwrap_or_default();
+    let edge_types = filter.edge_types.clone().unwrap_or_else(|| {
+        vec![
+            "wikilink".to_string(),
+            "embed".to_string(),
+            "tag".to_string(),
+        ]
+    });
+    let tag_nodes = filter.tag_nodes.unwrap_or(false);
+
+    let graph = NoteGraph::load(&conn, &edge_types).map_err(|e| e.to_string())?;
+    let keep = graph_filter_mask(&conn, &graph, &filter).map_err(|e| e.to_string())?;
+    let mut edges = graph.edges_within(&keep, !tag_nodes);
+
+    let mut nodes: Vec<GraphNode> = graph
+        .nodes
+        .iter()
+        .zip(&keep)
+        .filter(|(_, &k)| k)
+        .map(|(n, _)| n.clone())
+        .collect();
+
+    if tag_nodes {
+        let tags = tag_names(&conn).map_err(|e| e.to_string())?;
+        let mut emitted: std::collections::HashSet<String> = std::collections::HashSet::new();
+        let mut linked: std::collections::HashSet<String> = std::collections::HashSet::new();
+        let mut push_tag = |nodes: &mut Vec<GraphNode>, tag_id: &str| {
+            if emitted.insert(tag_id.to_string()) {
+                nodes.push(GraphNode {
+                    id: format!("tag:{}", tag_id),
+                    title: tags
+                        .get(tag_id)
+                        .map(|(name, _)| name.clone())
+                        .unwrap_or_default(),
+                    emoji: None,
+                    kind: "tag".to_string(),
+                });
+            }
+        };
+        for (t, members) in graph.tags.iter().enumerate() {
+            let kept: Vec<usize> = members.iter().copied().filter(|&m| keep[m]).collect();
+            if kept.is_empty() {
+                continue;
+            }
+            let tag_node_id = format!("tag:{}", graph.tag_ids[t]);
+            push_tag(&mut nodes, &graph.tag_ids[t]);
+            for m in kept {
+                edges.push(GraphEdge {
+                    source: graph.nodes[m].id.clone(),
+                    target: tag_node_id.clone(),
+                    edge_type: "tag".to_string(),
+                });
+            }
+
+            // Hang nested tags off their ancestors, stopping once the chain
+            // reaches a tag that is already linked upwards.
+            let mut child = graph.tag_ids[t].clone();
+            while let Some(parent) = tags.get(&child).and_then(|(_, p)| p.clone()) {
+                if !tags.contains_key(&parent) || !linked.insert(child.clone()) {
+                    break;
+                }
+                edges.push(GraphEdge {
+                    source: format!("tag:{}", child),
+                    target: format!("tag:{}", parent),
+                    edge_type: "tag".to_string(),
+                });
+                push_tag(&mut nodes, &parent);
+                child = parent;
+            }
+        }
+    }
+
+    if !filter.include_orphans.unwrap_or(true) {
+        let connected: std::collections::HashSet<&str> = edges
+            .iter()
+            .flat_map(|e| [e.source.as_str(), e.target.as_str()])
+            .collect();
+        nodes.retain(|n| connected.contains(n.id.as_str()));
+    }
+
+    Ok(GraphData { nodes, edges })
+}
+
+/// Which notes of `graph` pass the folder, tag and date filters.
+fn graph_filter_mask(
+    conn: &rusqlite::Connection,
+    graph: &NoteGraph,
+    filter: &GraphFilter,
+) -> rusqlite::Result<Vec<bool>> {
+    let mut clauses: Vec<String> = Vec::new();
+    let mut values: Vec<rusqlite::types::Value> = Vec::new();
+    let mut cte = String::new();
+
+    if let Some(folder_ids) = filter.folder_ids.as_ref().filter(|f| !f.is_empty()) {
+        let placeholders = vec!["?"; folder_ids.len()].join(", ");
+        cte = format!(
+            "WITH RECURSIVE scope(id) AS (
+                 SELECT id FROM notes WHERE id IN ({})
+                 UNION SELECT n.id FROM notes n JOIN scope s ON n.parent_id = s.id
+             ) ",
+            placeholders
+        );
+        values.extend(folder_ids.iter().cloned().map(rusqlite::types::Value::Text));
+        clauses.push("id IN (SELECT id FROM scope)".to_string());
+    }
+    if let Some(tags) = filter.tags.as_ref().filter(|t| !t.is_empty()) {
+        // A parent tag also selects notes filed under its descendants.
+        let conditions = vec![TAG_SUBTREE_SQL; tags.len()].join(" OR ");
+        clauses.push(format!(
+            "id IN (SELECT nt.note_id FROM note_tags nt JOIN tags t ON t.id = nt.tag_id
+                    WHERE {})",
+            conditions
+        ));
+        values.extend(tags.iter().flat_map(|t| tag_subtree_params(t)));
+    }
+    for (bound, clause) in [
+        (filter.created_after, "created_at >= ?"),
+        (filter.created_before, "created_at < ?"),
+        (filter.updated_after, "updated_at >= ?"),
+        (filter.updated_before, "updated_at < ?"),
+    ] {
+        if let Some(bound) = bound {
+            clauses.push(clause.to_string());
+            values.push(rusqlite::types::Value::Integer(bound));
+        }
+    }
+
+    if clauses.is_empty() {
+        return Ok(vec![true; graph.nodes.len()]);
+    }
+
+    let sql = format!(
+        "{}SELECT id FROM notes WHERE is_trashed = 0 AND is_folder = 0 AND {}",
+        cte,
+        clauses.join(" AND ")
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let mut keep = vec![false; graph.nodes.len()];
+    let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+        row.get::<_, String>(0)
+    })?;
+    for id in rows.flatten() {
+        if let Some(&i) = graph.index.get(&id) {
+            keep[i] = true;
+        }
+    }
+    Ok(keep)
+}
+
+/// Tag id → (full name, parent id).
+fn tag_names(
+    conn: &rusqlite::Connection,
+) -> rusqlite::Result<std::collections::HashMap<String, (String, Option<String>)>> {
+    let mut stmt = conn.prepare("SELECT id, name, parent_id FROM tags")?;
+    let names = stmt
+        .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(names)
+}
+
+// ─── Graph Events ────────────────────────────────────────
+
+/// Incremental change to the graph, emitted as a `graph-delta` event so the
+/// graph view can patch itself instead of reloading. Tag relationships are
+/// sent as edges to tag nodes; `added_nodes` are upserts.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GraphDelta {
+    #[serde(rename = "addedNodes")]
+    pub added_nodes: Vec<GraphNode>,
+    #[serde(rename = "removedNodes")]
+    pub removed_nodes: Vec<String>,
+    #[serde(rename = "addedEdges")]
+    pub added_edges: Vec<GraphEdge>,
+    #[serde(rename = "removedEdges")]
+    pub removed_edges: Vec<GraphEdge>,
+}
+
+/// The part of the graph touching a set of notes: the notes themselves
+/// (while live), their link edges in both directions and their tags.
+struct GraphSnapshot {
+    nodes: Vec<GraphNode>,
+    edges: std::collections::HashSet<GraphEdge>,
+}
+
+fn graph_snapshot(
+    conn: &rusqlite::Connection,
+    note_ids: &[String],
+) -> rusqlite::Result<GraphSnapshot> {
+    let mut snapshot = GraphSnapshot {
+        nodes: Vec::new(),
+        edges: std::collections::HashSet::new(),
+    };
+
+    for note_id in note_ids {
+        let node = conn.query_row(
+            "SELECT id, title, emoji FROM notes WHERE id = ?1 AND is_trashed = 0 AND is_folder = 0",
+            params![note_id],
+            |row| {
+                Ok(GraphNode {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    emoji: row.get(2)?,
+                    kind: "note".to_string(),
+                })
+            },
+        );
+        match node {
+            Ok(node) => snapshot.nodes.push(node),
+            Err(rusqlite::Error::QueryReturnedNoRows) => continue,
+            Err(e) => return Err(e),
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT w.source_note_id, w.target_note_id, w.link_type FROM wikilinks w
+             JOIN notes s ON s.id = w.source_note_id AND s.is_trashed = 0
+             JOIN notes t ON t.id = w.target_note_id AND t.is_trashed = 0
+             WHERE (w.source_note_id = ?1 OR w.target_note_id = ?1)
+               AND w.source_note_id != w.target_note_id",
+        )?;
+        let links = stmt.query_map(params![note_id], |row| {
+            let link_type: String = row.get(2)?;
+            Ok(GraphEdge {
+                source: row.get(0)?,
+                target: row.get(1)?,
+                edge_type: if link_type == "embed" {
+                    "embed"
+                } else {
+                    "wikilink"
+                }
+                .to_string(),
+            })
+        })?;
+        snapshot.edges.extend(links.flatten());
+
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.name FROM note_tags nt JOIN tags t ON t.id = nt.tag_id
+             WHERE nt.note_id = ?1",
+        )?;
+        let tags = stmt.query_map(params![note_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for (tag_id, name) in tags.flatten() {
+            let tag_node_id = format!("tag:{}", tag_id);
+            snapshot.edges.insert(GraphEdge {
+                source: note_id.clone(),
+                target: tag_node_id.clone(),
+                edge_type: "tag".to_string(),
+            });
+            if !snapshot.nodes.iter().any(|n| n.id == tag_node_id) {
+                snapshot.nodes.push(GraphNode {
+                    id: tag_node_id,
+                    title: name,
+                    emoji: None,
+                    kind: "tag".to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(snapshot)
+}
+
+fn graph_delta(before: GraphSnapshot, after: GraphSnapshot) -> GraphDelta {
+    let mut delta = GraphDelta {
+        removed_nodes: before
+            .nodes
+            .iter()
+            .filter(|n| n.kind == "note" && !after.nodes.iter().any(|a| a.id == n.id))
+            .map(|n| n.id.clone())
+            .collect(),
+        removed_edges: before.edges.difference(&after.edges).cloned().collect(),
+        added_edges: after.edges.difference(&before.edges).cloned().collect(),
+        ..Default::default()
+    };
+    delta.added_nodes = after
+        .nodes
+        .into_iter()
+        .filter(|n| !before.nodes.contains(n))
+        .collect();
+    delta
+}
+
+/// Run `change` and emit the resulting `graph-delta` for `note_ids`, if the
+/// graph changed at all.
+fn with_graph_delta<T>(
+    app: &tauri::AppHandle,
+    conn: &rusqlite::Connection,
+    note_ids: &[String],
+    change: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    let before = graph_snapshot(conn, note_ids).map_err(|e| e.to_string())?;
+    let result = change()?;
+    let after = graph_snapshot(conn, note_ids).map_err(|e| e.to_string())?;
+    emit_graph_delta(app, before, after);
+    Ok(result)
+}
+
+fn emit_graph_delta(app: &tauri::AppHandle, before: GraphSnapshot, after: GraphSnapshot) {
+    let delta = graph_delta(before, after);
+    if !delta.added_nodes.is_empty()
+        || !delta.removed_nodes.is_empty()
+        || !delta.added_edges.is_empty()
+        || !delta.removed_edges.is_empty()
+    {
+        let _ = app.emit("graph-delta", delta);
+    }
+}
+
+// ─── Graph Analytics ─────────────────────────────────────
+
+const LOCAL_GRAPH_MAX_DEPTH: u32 = 5;
+const PAGERANK_DAMPING: f64 = 0.85;
+const PAGERANK_ITERATIONS: usize = 100;
+/// Tags shared by more notes than this are skipped when tag relationships
+/// are turned into pairwise edges; they connect everything and say little,
+/// and their edge count grows with the square of their size.
+const TAG_EDGE_MAX_MEMBERS: usize = 100;
+
+#[derive(Debug, Serialize)]
+pub struct GraphPath {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NoteCentrality {
+    pub id: String,
+    pub title: String,
+    pub emoji: Option<String>,
+    #[serde(rename = "inDegree")]
+    pub in_degree: usize,
+    #[serde(rename = "outDegree")]
+    pub out_degree: usize,
+    pub degree: usize,
+    pub pagerank: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NoteCluster {
+    #[serde(rename = "noteId")]
+    pub note_id: String,
+    pub component: usize,
+    pub community: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphClusters {
+    pub clusters: Vec<NoteCluster>,
+    #[serde(rename = "componentCount")]
+    pub component_count: usize,
+    #[serde(rename = "communityCount")]
+    pub community_count: usize,
+    pub modularity: f64,
+}
+
+/// Live notes plus their link and tag relationships, indexed by position
+/// for the graph algorithms below.
+struct NoteGraph {
+    nodes: Vec<GraphNode>,
+    index: std::collections::HashMap<String, usize>,
+    /// Directed, deduplicated (source, target, edge type) link edges
+    links: Vec<(usize, usize, &'static str)>,
+    /// Member notes of each tag; empty unless tag edges were requested
+    tags: Vec<Vec<usize>>,
+    /// Id of each tag in `tags`
+    tag_ids: Vec<String>,
+    /// Tags of each note, as indexes into `tags`
+    note_tags: Vec<Vec<usize>>,
+}
+
+impl NoteGraph {
+    fn load(conn: &rusqlite::Connection, edge_types: &[String]) -> rusqlite::Result<Self> {
+        let mut stmt = conn.prepare(
+            "SELECT id, title, emoji FROM notes WHERE is_trashed = 0 AND is_folder = 0
+             ORDER BY created_at, id",
+        )?;
+        let nodes: Vec<GraphNode> = stmt
+            .query_map([], |row| {
+                Ok(GraphNode {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    emoji: row.get(2)?,
+                    kind: "note".to_string(),
+                })
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        let index: std::collections::HashMap<String, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.id.clone(), i))
+            .collect();
+
+        let mut links = Vec::new();
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT source_note_id, target_note_id, link_type FROM wikilinks
+             WHERE target_note_id IS NOT NULL AND target_note_id != source_note_id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+        for (source, target, link_type) in rows.flatten() {
+            let edge_type = if link_type == "embed" {
+                "embed"
+            } else {
+                "wikilink"
+            };
+            if !edge_types.iter().any(|t| t == edge_type) {
+                continue;
+            }
+            if let (Some(&s), Some(&t)) = (index.get(&source), index.get(&target)) {
+                links.push((s, t, edge_type));
+            }
+        }
+
+        let mut tags: Vec<Vec<usize>> = Vec::new();
+        let mut tag_ids: Vec<String> = Vec::new();
+        let mut note_tags: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+        if edge_types.iter().any(|t| t == "tag") {
+            let mut stmt = conn.prepare("SELECT tag_id, note_id FROM note_tags ORDER BY tag_id")?;
+            let rows = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?;
+            let mut last_tag: Option<String> = None;
+            for (tag_id, note_id) in rows.flatten() {
+                let Some(&n) = index.get(&note_id) else {
+                    continue;
+                };
+                if last_tag.as_deref() != Some(tag_id.as_str()) {
+                    tags.push(Vec::new());
+                    tag_ids.push(tag_id.clone());
+                    last_tag = Some(tag_id);
+                }
+                let t = tags.len() - 1;
+                tags[t].push(n);
+                note_tags[n].push(t);
+            }
+        }
+
+        Ok(NoteGraph {
+            nodes,
+            index,
+            links,
+            tags,
+            tag_ids,
+            note_tags,
+        })
+    }
+
+    /// Neighbours of every node. Shared tags make notes neighbours, except
+    /// tags with more than `TAG_EDGE_MAX_MEMBERS` members, which would add
+    /// an edge for every pair of their notes.
+    fn adjacency(&self, directed: bool) -> Vec<Vec<(usize, &'static str)>> {
+        let mut adj: Vec<Vec<(usize, &'static str)>> = vec![Vec::new(); self.nodes.len()];
+        for &(s, t, edge_type) in &self.links {
+            adj[s].push((t, edge_type));
+            if !directed {
+                adj[t].push((s, edge_type));
+            }
+        }
+        for (n, tags) in self.note_tags.iter().enumerate() {
+            for &t in tags {
+                if self.tags[t].len() > TAG_EDGE_MAX_MEMBERS {
+                    continue;
+                }
+                for &m in &self.tags[t] {
+                    if m != n {
+                        adj[n].push((m, "tag"));
+                    }
+                }
+            }
+        }
+        adj
+    }
+
+    /// Undirected weighted edges for centrality and community detection.
+    /// A link counts once per direction whatever its type, and tags add
+    /// `tag_weights`.
+    fn weighted_edges(&self) -> Vec<(usize, usize, f64)> {
+        let mut weights = self.tag_weights();
+        let mut link_pairs = std::collections::HashSet::new();
+        for &(s, t, _) in &self.links {
+            if link_pairs.insert((s, t)) {
+                *weights.entry((s.min(t), s.max(t))).or_default() += 1.0;
+            }
+        }
+        let mut edges: Vec<(usize, usize, f64)> =
+            weights.into_iter().map(|((a, b), w)| (a, b, w)).collect();
+        edges.sort_by_key(|e| (e.0, e.1));
+        edges
+    }
+
+    /// Weight of shared tags per unordered note pair. Each shared tag
+    /// contributes `1 / (members - 1)` so large tags don't swamp explicit
+    /// links.
+    fn tag_weights(&self) -> std::collections::HashMap<(usize, usize), f64> {
+        let mut weights: std::collections::HashMap<(usize, usize), f64> =
+            std::collections::HashMap::new();
+        for members in &self.tags {
+            if members.len() < 2 || members.len() > TAG_EDGE_MAX_MEMBERS {
+                continue;
+            }
+            let w = 1.0 / (members.len() - 1) as f64;
+            for (i, &a) in members.iter().enumerate() {
+                for &b in &members[i + 1..] {
+                    *weights.entry((a.min(b), a.max(b))).or_default() += w;
+                }
+            }
+        }
+        weights
+    }
+
+    /// Edges between the given nodes, for returning a subgraph. Shared tags
+    /// become an edge per pair of notes when `tag_pairs` is set, except for
+    /// tags with more than `TAG_EDGE_MAX_MEMBERS` of the given notes.
+    fn edges_within(&self, keep: &[bool], tag_pairs: bool) -> Vec<GraphEdge> {
+        let mut edges: Vec<GraphEdge> = self
+            .links
+            .iter()
+            .filter(|(s, t, _)| keep[*s] && keep[*t])
+            .map(|&(s, t, edge_type)| GraphEdge {
+                source: self.nodes[s].id.clone(),
+                target: self.nodes[t].id.clone(),
+                edge_type: edge_type.to_string(),
+            })
+            .collect();
+
+        if !tag_pairs {
+            return edges;
+        }
+        let mut seen = std::collections::HashSet::new();
+        for members in &self.tags {
+            let kept: Vec<usize> = members.iter().copied().filter(|&m| keep[m]).collect();
+            if kept.len() > TAG_EDGE_MAX_MEMBERS {
+                continue;
+            }
+            for (i, &a) in kept.iter().enumerate() {
+                for &b in &kept[i + 1..] {
+                    if seen.insert((a.min(b), a.max(b))) {
+                        edges.push(GraphEdge {
+                            source: self.nodes[a.min(b)].id.clone(),
+                            target: self.nodes[a.max(b)].id.clone(),
+                            edge_type: "tag".to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        edges
+    }
+}
+
+/// Requested edge types, defaulting to explicit links only.
+fn graph_edge_types(edge_types: Option<Vec<String>>) -> Vec<String> {
+    match edge_types {
+        Some(types) if !types.is_empty() => types,
+        _ => vec!["wikilink".to_string(), "embed".to_string()],
+    }
+}
+
+/// The neighbourhood of a note up to `depth` hops (default 1), following
+/// links in either direction.
+#[tauri::command]
+pub fn get_local_graph(
+    db: State<Database>,
+    note_id: String,
+    depth: Option<u32>,
+    edge_types: Option<Vec<String>>,
+) -> Result<GraphData, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let graph = NoteGraph::load(&conn, &graph_edge_types(edge_types)).map_err(|e| e.to_string())?;
+    let Some(&start) = graph.index.get(&note_id) else {
+        return Err("Note not found".to_string());
+    };
+    let depth = depth.unwrap_or(1).min(LOCAL_GRAPH_MAX_DEPTH);
+
+    let adj = graph.adjacency(false);
+    let mut keep = vec![false; graph.nodes.len()];
+    keep[start] = true;
+    let mut frontier = vec![start];
+    for _ in 0..depth {
+        let mut next = Vec::new();
+        for &n in &frontier {
+            for &(m, _) in &adj[n] {
+                if !keep[m] {
+                    keep[m] = true;
+                    next.push(m);
+                }
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        frontier = next;
+    }
+
+    let edges = graph.edges_within(&keep, true);
+    let nodes = graph
+        .nodes
+        .iter()
+        .zip(&keep)
+        .filter(|(_, &k)| k)
+        .map(|(n, _)| n.clone())
+        .collect();
+
+    Ok(GraphData { nodes, edges })
+}
+
+/// Fewest-hops path between two notes, or `None` if they aren't connected.
+/// Links are followed in either direction unless `directed` is set.
+#[tauri::command]
+pub fn find_shortest_path(
+    db: State<Database>,
+    from_note_id: String,
+    to_note_id: String,
+    edge_types: Option<Vec<String>>,
+    directed: Option<bool>,
+) -> Result<Option<GraphPath>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let graph = NoteGraph::load(&conn, &graph_edge_types(edge_types)).map_err(|e| e.to_string())?;
+    let (Some(&from), Some(&to)) = (graph.index.get(&from_note_id), graph.index.get(&to_note_id))
+    else {
+        return Err("Note not found".to_string());
+    };
+
+    let adj = graph.adjacency(directed.unwrap_or(false));
+    let mut previous: Vec<Option<(usize, &'static str)>> = vec![None; graph.nodes.len()];
+    let mut visited = vec![false; graph.nodes.len()];
+    let mut queue = std::collections::VecDeque::from([from]);
+    visited[from] = true;
+    while let Some(n) = queue.pop_front() {
+        if n == to {
+            break;
+        }
+        for &(m, edge_type) in &adj[n] {
+            if !visited[m] {
+                visited[m] = true;
+                previous[m] = Some((n, edge_type));
+                queue.push_back(m);
+            }
+        }
+    }
+    if !visited[to] {
+        return Ok(None);
+    }
+
+    let mut path = GraphPath {
+        nodes: vec![graph.nodes[to].clone()],
+        edges: Vec::new(),
+    };
+    let mut current = to;
+    while let Some((prev, edge_type)) = previous[current] {
+        path.nodes.push(graph.nodes[prev].clone());
+        path.edges.push(GraphEdge {
+            source: graph.nodes[prev].id.clone(),
+            target: graph.nodes[current].id.clone(),
+            edge_type: edge_type.to_string(),
+        });
+        current = prev;
+    }
+    path.nodes.reverse();
+    path.edges.reverse();
+
+    Ok(Some(path))
+}
+
+/// Degree and PageRank for every note, highest PageRank first, to surface
+/// hub notes. PageRank follows link direction; tag relationships count both
+/// ways.
+#[tauri::command]
+pub fn get_note_centrality(
+    db: State<Database>,
+    edge_types: Option<Vec<String>>,
+    limit: Option<usize>,
+) -> Result<Vec<NoteCentrality>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let graph = NoteGraph::load(&conn, &graph_edge_types(edge_types)).map_err(|e| e.to_string())?;
+    let n = graph.nodes.len();
+    if n == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut in_degree = vec![0usize; n];
+    let mut out_degree = vec![0usize; n];
+    let mut out: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
+    let mut link_pairs = std::collections::HashSet::new();
+    for &(s, t, _) in &graph.links {
+        if link_pairs.insert((s, t)) {
+            out_degree[s] += 1;
+            in_degree[t] += 1;
+            out[s].push((t, 1.0));
+        }
+    }
+    // Link weight is already in `out`; tags count both ways
+    for ((a, b), w) in graph.tag_weights() {
+        out[a].push((b, w));
+        out[b].push((a, w));
+    }
+
+    let totals: Vec<f64> = out.iter().map(|o| o.iter().map(|(_, w)| w).sum()).collect();
+    let mut rank = vec![1.0 / n as f64; n];
+    for _ in 0..PAGERANK_ITERATIONS {
+        // Rank of notes without outgoing edges is spread evenly
+        let dangling: f64 = (0..n).filter(|&i| totals[i] == 0.0).map(|i| rank[i]).sum();
+        let base = (1.0 - PAGERANK_DAMPING) / n as f64 + PAGERANK_DAMPING * dangling / n as f64;
+        let mut next = vec![base; n];
+        for i in 0..n {
+            if totals[i] > 0.0 {
+                for &(j, w) in &out[i] {
+                    next[j] += PAGERANK_DAMPING * rank[i] * w / totals[i];
+                }
+            }
+        }
+        let delta: f64 = next.iter().zip(&rank).map(|(a, b)| (a - b).abs()).sum();
+        rank = next;
+        if delta < 1e-9 {
+            break;
+        }
+    }
+
+    let mut results: Vec<NoteCentrality> = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| NoteCentrality {
+            id: node.id.clone(),
+            title: node.title.clone(),
+            emoji: node.emoji.clone(),
+            in_degree: in_degree[i],
+            out_degree: out_degree[i],
+            degree: in_degree[i] + out_degree[i],
+            pagerank: rank[i],
+        })
+        .collect();
+    results.sort_by(|a, b| {
+        b.pagerank
+            .partial_cmp(&a.pagerank)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.degree.cmp(&a.degree))
+    });
+    if let Some(limit) = limit {
+        results.truncate(limit);
+    }
+
+    Ok(results)
+}
+
+/// Connected components and Louvain communities for colouring clusters in
+/// the graph view. Ids are ordered by size, largest first.
+#[tauri::command]
+pub fn get_graph_clusters(
+    db: State<Database>,
+    edge_types: Option<Vec<String>>,
+) -> Result<GraphClusters, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let graph = NoteGraph::load(&conn, &graph_edge_types(edge_types)).map_err(|e| e.to_string())?;
+    let n = graph.nodes.len();
+    let edges = graph.weighted_edges();
+
+    let mut adj: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
+    for &(a, b, w) in &edges {
+        adj[a].push((b, w));
+        adj[b].push((a, w));
+    }
+
+    // Connected components by flood fill
+    let mut component = vec![usize::MAX; n];
+    let mut component_count = 0;
+    for root in 0..n {
+        if component[root] != usize::MAX {
+            continue;
+        }
+        let mut stack = vec![root];
+        component[root] = component_count;
+        while let Some(i) = stack.pop() {
+            for &(j, _) in &adj[i] {
+                if component[j] == usize::MAX {
+                    component[j] = component_count;
+                    stack.push(j);
+                }
+            }
+        }
+        component_count += 1;
+    }
+
+    let community = louvain(&adj);
+    let modularity = modularity(&adj, &community);
+    let component = relabel_by_size(&component);
+    let community = relabel_by_size(&community);
+    let community_count = community.iter().max().map_or(0, |m| m + 1);
+
+    let clusters = graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| NoteCluster {
+            note_id: node.id.clone(),
+            component: component[i],
+            community: community[i],
+        })
+        .collect();
+
+    Ok(GraphClusters {
+        clusters,
+        component_count,
+        community_count,
+        modularity,
+    })
+}
+
+/// Louvain community detection on an undirected weighted graph given as
+/// adjacency lists (self-loops appear once). Returns a community per node.
+fn louvain(adj: &[Vec<(usize, f64)>]) -> Vec<usize> {
+    let mut assignment: Vec<usize> = (0..adj.len()).collect();
+    let mut level: Vec<Vec<(usize, f64)>> = adj.to_vec();
+
+    loop {
+        let n = level.len();
+        let degree: Vec<f64> = level
+            .iter()
+            .map(|a| a.iter().map(|(_, w)| w).sum())
+            .collect();
+        let m2: f64 = degree.iter().sum();
+        if m2 == 0.0 {
+            break;
+        }
+
+        // Local moving: shift nodes to the neighbouring community with the
+        // best modularity gain until nothing moves
+        let mut community: Vec<usize> = (0..n).collect();
+        let mut total = degree.clone();
+        let mut moved_any = false;
+        loop {
+            let mut moved = false;
+            for i in 0..n {
+                let current = community[i];
+                total[current] -= degree[i];
+
+                let mut links_to: std::collections::HashMap<usize, f64> =
+                    std::collections::HashMap::new();
+                for &(j, w) in &level[i] {
+                    if j != i {
+                        *links_to.entry(community[j]).or_default() += w;
+                    }
+                }
+
+                let gain = |c: usize, k_in: f64| k_in - total[c] * degree[i] / m2;
+                let mut best = current;
+                let mut best_gain = gain(current, links_to.get(&current).copied().unwrap_or(0.0));
+                let mut candidates: Vec<(&usize, &f64)> = links_to.iter().collect();
+                candidates.sort_by_key(|(c, _)| **c);
+                for (&c, &k_in) in candidates {
+                    let g = gain(c, k_in);
+                    if g > best_gain + 1e-12 {
+                        best = c;
+                        best_gain = g;
+                    }
+                }
+
+                total[best] += degree[i];
+                if best != current {
+                    community[i] = best;
+                    moved = true;
+                    moved_any = true;
+                }
+            }
+            if !moved {
+                break;
+            }
+        }
+        if !moved_any {
+            break;
+        }
+
+        // Aggregate each community into a single node and repeat
+        let mut renumber: std::collections::HashMap<usize, usize> =
+            std::collections::HashMap::new();
+        for c in &community {
+            let next = renumber.len();
+            renumber.entry(*c).or_insert(next);
+        }
+        let mut aggregated: Vec<std::collections::HashMap<usize, f64>> =
+            vec![std::collections::HashMap::new(); renumber.len()];
+        for (i, edges) in level.iter().enumerate() {
+            let ci = renumber[&community[i]];
+            for &(j, w) in edges {
+                *aggregated[ci].entry(renumber[&community[j]]).or_default() += w;
+            }
+        }
+        for a in assignment.iter_mut() {
+            *a = renumber[&community[*a]];
+        }
+        level = aggregated
+            .into_iter()
+            .map(|edges| {
+                let mut edges: Vec<(usize, f64)> = edges.into_iter().collect();
+                edges.sort_by_key(|(j, _)| *j);
+                edges
+            })
+            .collect();
+    }
+
+    assignment
+}
+
+fn modularity(adj: &[Vec<(usize, f64)>], community: &[usize]) -> f64 {
+    let m2: f64 = adj.iter().flatten().map(|(_, w)| w).sum();
+    if m2 == 0.0 {
+        return 0.0;
+    }
+    let mut inside: std::collections::HashMap<usize, f64> = std::collections::HashMap::new();
+    let mut total: std::collections::HashMap<usize, f64> = std::collections::HashMap::new();
+    for (i, edges) in adj.iter().enumerate() {
+        for &(j, w) in edges {
+            *total.entry(community[i]).or_default() += w;
+            if community[i] == community[j] {
+                *inside.entry(community[i]).or_default() += w;
+            }
+        }
+    }
+    total
+        .iter()
+        .map(|(c, tot)| inside.get(c).copied().unwrap_or(0.0) / m2 - (tot / m2).powi(2))
+        .sum()
+}
+
+/// Renumber group ids so 0 is the largest group, ties broken by first
+/// appearance.
+fn relabel_by_size(groups: &[usize]) -> Vec<usize> {
+    let mut sizes: std::collections::HashMap<usize, (usize, usize)> =
+        std::collections::HashMap::new();
+    for (i, g) in groups.iter().enumerate() {
+        let entry = sizes.entry(*g).or_insert((0, i));
+        entry.0 += 1;
+    }
+    let mut order: Vec<(usize, (usize, usize))> = sizes.into_iter().collect();
+    order.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.1 .1.cmp(&b.1 .1)));
+    let labels: std::collections::HashMap<usize, usize> = order
+        .iter()
+        .enumerate()
+        .map(|(label, (g, _))| (*g, label))
+        .collect();
+    groups.iter().map(|g| labels[g]).collect()
+}
+
+// ─── Periodic Notes ──────────────────────────────────────
+
+const PERIOD_TYPES: [&str; 5] = ["day", "week", "month", "quarter", "year"];
+
+#[derive(Debug, Serialize)]
+pub struct PeriodicNoteSettings {
+    #[serde(rename = "periodType")]
+    pub period_type: String,
+    /// Moment-style format, e.g. `YYYY-MM-DD` or `GGGG-[W]WW`.
+    #[serde(rename = "titleFormat")]
+    pub title_format: String,
+    /// Folder new notes of this period are created in.
+    #[serde(rename = "folderId")]
+    pub folder_id: Option<String>,
+    /// Template new notes of this period are created from.
+    #[serde(rename = "templateId")]
+    pub template_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PeriodicNote {
+    #[serde(rename = "noteId")]
+    pub note_id: String,
+    pub title: String,
+    #[serde(rename = "periodType")]
+    pub period_type: String,
+    /// First day of the period, `YYYY-MM-DD`.
+    #[serde(rename = "periodDate")]
+    pub period_date: String,
+    /// Last day of the period, `YYYY-MM-DD`.
+    #[serde(rename = "periodEnd")]
+    pub period_end: String,
+}
+
+/// `date` defaults to today in the app timezone.
+#[tauri::command]
+pub fn get_or_create_daily_note(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    date: Option<String>,
+) -> Result<String, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let day = parse_period_date(&conn, date.as_deref())?;
+    find_or_create_periodic_note(&app, &conn, "day", day).map(|note| note.note_id)
+}
+
+/// The note for the day, week, month, quarter or year containing `date`
+/// (today by default), created from the period's settings if missing.
+#[tauri::command]
+pub fn get_or_create_periodic_note(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    period_type: String,
+    date: Option<String>,
+) -> Result<PeriodicNote, String> {
+    let period_type = check_period_type(&period_type)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let date = parse_period_date(&conn, date.as_deref())?;
+    find_or_create_periodic_note(&app, &conn, period_type, period_start(period_type, date))
+}
+
+/// Period details of a note, or `None` if it isn't a periodic note.
+#[tauri::command]
+pub fn get_periodic_note(
+    db: State<Database>,
+    note_id: String,
+) -> Result<Option<PeriodicNote>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(query_periodic_note(&conn, "id = ?1", params![note_id]))
+}
+
+/// The periodic note before or after `note_id`. Without `create` this is
+/// the nearest existing note of the same period type, skipping gaps; with
+/// it, the immediately adjacent period, created if needed.
+#[tauri::command]
+pub fn get_adjacent_periodic_note(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    note_id: String,
+    direction: String,
+    create: Option<bool>,
+) -> Result<Option<PeriodicNote>, String> {
+    let forward = match direction.as_str() {
+        "next" => true,
+        "prev" | "previous" => false,
+        other => return Err(format!("Unknown direction \"{}\"", other)),
+    };
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let current =
+        query_periodic_note(&conn, "id = ?1", params![note_id]).ok_or("Not a periodic note")?;
+    let period_type = check_period_type(&current.period_type)?;
+
+    if create.unwrap_or(false) {
+        let start = parse_period_date(&conn, Some(&current.period_date))?;
+        let adjacent = shift_period(period_type, start, if forward { 1 } else { -1 })
+            .ok_or("Date out of range")?;
+        return find_or_create_periodic_note(&app, &conn, period_type, adjacent).map(Some);
+    }
+
+    let filter = if forward {
+        "period_type = ?1 AND period_date > ?2 AND is_trashed = 0
+         ORDER BY period_date ASC"
+    } else {
+        "period_type = ?1 AND period_date < ?2 AND is_trashed = 0
+         ORDER BY period_date DESC"
+    };
+    Ok(query_periodic_note(
+        &conn,
+        filter,
+        params![period_type, current.period_date],
+    ))
+}
+
+#[tauri::command]
+pub fn get_periodic_note_settings(
+    db: State<Database>,
+) -> Result<Vec<PeriodicNoteSettings>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(PERIOD_TYPES
+        .iter()
+        .map(|period_type| periodic_note_settings(&conn, period_type))
+        .collect())
+}
+
+/// Set a period's title format, folder and template; an empty format
+/// restores the default.
+#[tauri::command]
+pub fn set_periodic_note_settings(
+    db: State<Database>,
+    period_type: String,
+    title_format: Option<String>,
+    folder_id: Option<String>,
+    template_id: Option<String>,
+) -> Result<PeriodicNoteSettings, String> {
+    let period_type = check_period_type(&period_type)?;
+    let title_format = title_format
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty())
+        .unwrap_or_else(|| default_period_title_format(period_type).to_string());
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    if let Some(folder_id) = &folder_id {
+        let is_folder: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM notes WHERE id = ?1 AND is_folder = 1",
+                params![folder_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if !is_folder {
+            return Err("Folder not found".to_string());
+        }
+    }
+    if let Some(template_id) = &template_id {
+        if query_template(&conn, template_id).is_none() {
+            return Err("Template not found".to_string());
+        }
+    }
+    conn.execute(
+        "INSERT INTO periodic_note_settings (period_type, title_format, folder_id, template_id)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(period_type) DO UPDATE SET
+            title_format = excluded.title_format, folder_id = excluded.folder_id,
+            template_id = excluded.template_id",
+        params![period_type, title_format, folder_id, template_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(periodic_note_settings(&conn, period_type))
+}
+
+fn check_period_type(period_type: &str) -> Result<&'static str, String> {
+    PERIOD_TYPES
+        .iter()
+        .find(|t| **t == period_type)
+        .copied()
+        .ok_or_else(|| format!("Unknown period type \"{}\"", period_type))
+}
+
+fn default_period_title_format(period_type: &str) -> &'static str {
+    match period_type {
+        "week" => "GGGG-[W]WW",
+        "month" => "YYYY-MM",
+        "quarter" => "YYYY-[Q]Q",
+        "year" => "YYYY",
+        _ => "YYYY-MM-DD",
+    }
+}
+
+fn parse_period_date(
+    conn: &rusqlite::Connection,
+    date: Option<&str>,
+) -> Result<chrono::NaiveDate, String> {
+    match date {
+        Some(date) => chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date \"{}\"", date)),
+        None => Ok(local_now(conn).date()),
+    }
+}
+
+/// First day of the period containing `date`. Weeks start on Monday.
+fn period_start(period_type: &str, date: chrono::NaiveDate) -> chrono::NaiveDate {
+    use chrono::Datelike;
+    let first_of = |month: u32| chrono::NaiveDate::from_ymd_opt(date.year(), month, 1);
+    match period_type {
+        "week" => date.checked_sub_signed(chrono::Duration::days(
+            date.weekday().num_days_from_monday() as i64,
+        )),
+        "month" => first_of(date.month()),
+        "quarter" => first_of((date.month() - 1) / 3 * 3 + 1),
+        "year" => first_of(1),
+        _ => Some(date),
+    }
+    .unwrap_or(date)
+}
+
+/// Move a period start by `steps` whole periods.
+fn shift_period(
+    period_type: &str,
+    start: chrono::NaiveDate,
+    steps: i32,
+) -> Option<chrono::NaiveDate> {
+    let shift_months = |months: i32| {
+        let by = chrono::Months::new(months.unsigned_abs());
+        if months >= 0 {
+            start.checked_add_months(by)
+        } else {
+            start.checked_sub_months(by)
+        }
+    };
+    match period_type {
+        "week" => start.checked_add_signed(chrono::Duration::weeks(steps as i64)),
+        "month" => shift_months(steps),
+        "quarter" => shift_months(steps * 3),
+        "year" => shift_months(steps * 12),
+        _ => start.checked_add_signed(chrono::Duration::days(steps as i64)),
+    }
+}
+
+fn period_end(period_type: &str, start: chrono::NaiveDate) -> chrono::NaiveDate {
+    shift_period(period_type, start, 1)
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(start)
+}
+
+fn periodic_note_settings(conn: &rusqlite::Connection, period_type: &str) -> PeriodicNoteSettings {
+    let stored: Option<(String, Option<String>, Option<String>)> = conn
+        .query_row(
+            "SELECT title_format, folder_id, template_id FROM periodic_note_settings
+             WHERE period_type = ?1",
+            params![period_type],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .ok();
+    let (title_format, folder_id, template_id) = stored.unwrap_or_else(|| {
+        (
+            default_period_title_format(period_type).to_string(),
+            None,
+            None,
+        )
+    });
+    PeriodicNoteSettings {
+        period_type: period_type.to_string(),
+        title_format,
+        folder_id,
+        template_id,
+    }
+}
+
+fn query_periodic_note(
+    conn: &rusqlite::Connection,
+    filter: &str,
+    params: impl rusqlite::Params,
+) -> Option<PeriodicNote> {
+    conn.query_row(
+        &format!(
+            "SELECT id, title, period_type, period_date FROM notes
+             WHERE period_type IS NOT NULL AND {} LIMIT 1",
+            filter
+        ),
+        params,
+        |row| {
+            let period_type: String = row.get(2)?;
+            let period_date: String = row.get(3)?;
+            let period_end = chrono::NaiveDate::parse_from_str(&period_date, "%Y-%m-%d")
+                .map(|start| period_end(&period_type, start).to_string())
+                .unwrap_or_else(|_| period_date.clone());
+            Ok(PeriodicNote {
+                note_id: row.get(0)?,
+                title: row.get(1)?,
+                period_type,
+                period_date,
+                period_end,
+            })
+        },
+    )
+    .ok()
+}
+
+/// Look a period's note up by its recorded period rather than its title,
+/// so renamed notes are still found. A live note titled with the bare date
+/// is adopted as that day's note.
+fn find_or_create_periodic_note(
+    app: &tauri::AppHandle,
+    conn: &rusqlite::Connection,
+    period_type: &str,
+    start: chrono::NaiveDate,
+) -> Result<PeriodicNote, String> {
+    let period_date = start.format("%Y-%m-%d").to_string();
+    let existing = query_periodic_note(
+        conn,
+        "period_type = ?1 AND period_date = ?2 AND is_trashed = 0 ORDER BY created_at",
+        params![period_type, period_date],
+    );
+    if let Some(note) = existing {
+        return Ok(note);
+    }
+
+    if period_type == "day" {
+        let adopted = conn
+            .execute(
+                "UPDATE notes SET period_type = 'day', period_date = ?1
+                 WHERE id = (SELECT id FROM notes
+                             WHERE title = ?1 AND period_type IS NULL
+                               AND is_folder = 0 AND is_trashed = 0
+                             ORDER BY created_at LIMIT 1)",
+                params![period_date],
+            )
+            .map_err(|e| e.to_string())?;
+        if adopted > 0 {
+            return query_periodic_note(
+                conn,
+                "period_type = 'day' AND period_date = ?1 AND is_trashed = 0",
+                params![period_date],
+            )
+            .ok_or_else(|| "Daily note not found".to_string());
+        }
+    }
+
+    let settings = periodic_note_settings(conn, period_type);
+    let title = format_moment(
+        &start.and_time(chrono::NaiveTime::MIN),
+        &settings.title_format,
+    );
+    let folder_id: Option<String> = settings.folder_id.and_then(|folder_id| {
+        conn.query_row(
+            "SELECT id FROM notes WHERE id = ?1 AND is_folder = 1 AND is_trashed = 0",
+            params![folder_id],
+            |row| row.get(0),
+        )
+        .ok()
+    });
+    let emoji = if period_type == "day" {
+        "📅"
+    } else {
+        "🗓️"
+    };
+    let template = settings
+        .template_id
+        .and_then(|template_id| query_template(conn, &template_id));
+    let nodes = match template {
+        Some(template) => {
+            let now = local_now(conn);
+            expand_template(
+                &template.content,
+                &TemplateContext {
+                    title: &title,
+                    date: start,
+                    now,
+                    prompts: &std::collections::HashMap::new(),
+                },
+            )
+            .0
+        }
+        None => vec![
+            serde_json::json!({"type": "h1", "children": [{"text": title}]}),
+            serde_json::json!({"type": "p", "children": [{"text": ""}]}),
+        ],
+    };
+
+    let id = Uuid::new_v4().to_string();
+    with_graph_delta(app, conn, std::slice::from_ref(&id), || {
+        conn.execute(
+            "INSERT INTO notes (id, title, emoji, parent_id, period_type, period_date)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![id, title, emoji, folder_id, period_type, period_date],
+        )
+        .map_err(|e| e.to_string())?;
+        write_note_nodes(conn, &id, &nodes).map_err(|e| e.to_string())
+    })?;
+
+    Ok(PeriodicNote {
+        note_id: id,
+        title: plate_title(&nodes).unwrap_or(title),
+        period_type: period_type.to_string(),
+        period_end: period_end(period_type, start).to_string(),
+        period_date,
+    })
+}
+
+/// Format a date the way moment.js does, which is what users know from
+/// other note apps: `YYYY YY Q MMMM MMM MM M DD D Do dddd ddd dd d E
+/// GGGG GG WW W HH H hh h mm m ss s A a X`, with `[...]` for literal text.
+/// `gggg`/`ww`/`w` are accepted as their ISO counterparts.
+pub(crate) fn format_moment(dt: &chrono::NaiveDateTime, format: &str) -> String {
+    use chrono::{Datelike, Timelike};
+    const TOKENS: [&str; 34] = [
+        "YYYY", "GGGG", "gggg", "MMMM", "dddd", "MMM", "ddd", "YY", "GG", "gg", "MM", "DD", "Do",
+        "dd", "WW", "ww", "HH", "hh", "mm", "ss", "M", "D", "Q", "W", "w", "d", "E", "H", "h", "m",
+        "s", "A", "a", "X",
+    ];
+
+    let mut out = String::new();
+    let mut rest = format;
+    while let Some(c) = rest.chars().next() {
+        if c == '[' {
+            if let Some(end) = rest.find(']') {
+                out.push_str(&rest[1..end]);
+                rest = &rest[end + 1..];
+                continue;
+            }
+        }
+        let Some(token) = TOKENS.iter().find(|t| rest.starts_with(**t)) else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        };
+        let hour12 = match dt.hour() % 12 {
+            0 => 12,
+            h => h,
+        };
+        let iso = dt.iso_week();
+        let piece = match *token {
+            "YYYY" => format!("{:04}", dt.year()),
+            "YY" => format!("{:02}", dt.year().rem_euclid(100)),
+            "GGGG" | "gggg" => format!("{:04}", iso.year()),
+            "GG" | "gg" => format!("{:02}", iso.year().rem_euclid(100)),
+            "Q" => ((dt.month() - 1) / 3 + 1).to_string(),
+            "MMMM" => dt.format("%B").to_string(),
+            "MMM" => dt.format("%b").to_string(),
+            "MM" => format!("{:02}", dt.month()),
+            "M" => dt.month().to_string(),
+            "DD" => format!("{:02}", dt.day()),
+            "D" => dt.day().to_string(),
+            "Do" => {
+                let day = dt.day();
+                let suffix = match (day % 10, day % 100) {
+                    (_, 11..=13) => "th",
+                    (1, _) => "st",
+                    (2, _) => "nd",
+                    (3, _) => "rd",
+                    _ => "th",
+                };
+                format!("{}{}", day, suffix)
+            }
+            "dddd" => dt.format("%A").to_string(),
+            "ddd" => dt.format("%a").to_string(),
+            "dd" => dt.format("%a").to_string().chars().take(2).collect(),
+            "d" => dt.weekday().num_days_from_sunday().to_string(),
+            "E" => dt.weekday().number_from_monday().to_string(),
+            "WW" | "ww" => format!("{:02}", iso.week()),
+            "W" | "w" => iso.week().to_string(),
+            "HH" => format!("{:02}", dt.hour()),
+            "H" => dt.hour().to_string(),
+            "hh" => format!("{:02}", hour12),
+            "h" => hour12.to_string(),
+            "mm" => format!("{:02}", dt.minute()),
+            "m" => dt.minute().to_string(),
+            "ss" => format!("{:02}", dt.second()),
+            "s" => dt.second().to_string(),
+            "A" => if dt.hour() < 12 { "AM" } else { "PM" }.to_string(),
+            "a" => if dt.hour() < 12 { "am" } else { "pm" }.to_string(),
+            _ => dt.and_utc().timestamp().to_string(),
+        };
+        out.push_str(&piece);
+        rest = &rest[token.len()..];
+    }
+    out
+}
+
+// ─── Related Notes (AI Suggestions) ─────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct RelatedNoteItem {
+    pub id: String,
+    pub title: String,
+    pub emoji: Option<String>,
+    pub score: f64,
+}
+
+#[tauri::command]
+pub fn find_related_notes(
+    db: State<Database>,
+    note_id: String,
+) -> Result<Vec<RelatedNoteItem>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    // Get the plain text of the current note
+    let plain_text: String = conn
+        .query_row(
+            "SELECT COALESCE(plain_text, '') FROM notes WHERE id = ?1",
+            params![note_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+
+    if plain_text.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    // Extract top keywords (words with 4+ chars, skip common words)
+    let word_freq = term_frequencies(&plain_text);
+
+    let mut keywords: Vec<(String, usize)> = word_freq.into_iter().collect();
+    keywords.sort_by(|a, b| b.1.cmp(&a.1));
+    let top_keywords: Vec<String> = keywords.into_iter().take(8).map(|(w, _)| w).collect();
+
+    if top_keywords.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // Search FTS for each keyword and score results
+    let mut note_scores: std::collections::HashMap<String, f64> = std::collections::HashMap::new();
+
+    for keyword in &top_keywords {
+        let fts_query = format!("\"{}\"", keyword);
+        let mut stmt = conn
+            .prepare(
+                "SELECT n.id FROM notes_fts
+                 JOIN notes n ON n.rowid = notes_fts.rowid
+                 WHERE notes_fts MATCH ?1 AND n.is_trashed = 0 AND n.id != ?2
+                 LIMIT 20",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let ids: Vec<String> = stmt
+            .query_map(params![fts_query, note_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        for id in ids {
+            *note_scores.entry(id).or_insert(0.0) += 1.0;
+        }
+    }
+
+    // Normalize scores
+    let max_score = top_keywords.len() as f64;
+    let mut results: Vec<RelatedNoteItem> = Vec::new();
+
+    let mut scored: Vec<(String, f64)> = note_scores.into_iter().collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    for (nid, score) in scored.into_iter().take(5) {
+        let normalized = (score / max_score * 100.0).round();
+        match conn.query_row(
+            "SELECT title, emoji FROM notes WHERE id = ?1",
+            params![nid],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
+        ) {
+            Ok((title, emoji)) => {
+                results.push(RelatedNoteItem {
+                    id: nid,
+                    title,
+                    emoji,
+                    score: normalized,
+                });
+            }
+            Err(_) => continue,
+        }
+    }
+
+    Ok(results)
+}
+
+/// Words of 4+ characters, minus common stop words, with their counts.
+fn term_frequencies(text: &str) -> std::collections::HashMap<String, usize> {
+    const STOP_WORDS: &[&str] = &[
+        "the", "and", "for", "that", "this", "with", "from", "have", "been", "will", "are", "was",
+        "were", "not", "but", "can", "all", "has", "each", "which", "their", "there", "about",
+        "would", "make", "like", "just", "over", "such", "take", "than", "them", "very", "some",
+        "into", "most", "other", "also", "more", "what", "when", "your",
+    ];
+
+    let mut word_freq: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    for word in text.to_lowercase().split_whitespace() {
+        let clean: String = word.chars().filter(|c| c.is_alphanumeric()).collect();
+        if clean.len() >= 4 && !STOP_WORDS.contains(&clean.as_str()) {
+            *word_freq.entry(clean).or_insert(0) += 1;
+        }
+    }
+    word_freq
+}
+
+// ─── Tag Suggestions ─────────────────────────────────────
+
+/// Suggestions scoring below this cosine similarity are dropped.
+const TAG_SUGGESTION_MIN_CONFIDENCE: f64 = 0.05;
+
+#[derive(Debug, Serialize)]
+pub struct TagSuggestion {
+    #[serde(rename = "tagId")]
+    pub tag_id: String,
+    pub name: String,
+    pub color: Option<String>,
+    /// Cosine similarity between the note and the tag's profile, 0–1.
+    pub confidence: f64,
+    /// The terms that contributed most to the match.
+    #[serde(rename = "sharedTerms")]
+    pub shared_terms: Vec<String>,
+    /// How many other notes carry the tag.
+    #[serde(rename = "noteCount")]
+    pub note_count: usize,
+}
+
+type TermVector = std::collections::HashMap<String, f64>;
+
+/// Propose tags for a note by comparing its TF-IDF term vector with each
+/// tag's profile: the centroid of the vectors of the other notes carrying
+/// that tag. Tags the note already has, and their ancestors, are skipped.
+#[tauri::command]
+pub fn suggest_tags(
+    db: State<Database>,
+    note_id: String,
+    limit: Option<usize>,
+) -> Result<Vec<TagSuggestion>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(5);
+
+    let own_text: String = conn
+        .query_row(
+            "SELECT COALESCE(plain_text, '') FROM notes WHERE id = ?1",
+            params![note_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let own_terms = term_frequencies(&own_text);
+    if own_terms.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let docs: std::collections::HashMap<String, std::collections::HashMap<String, usize>> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, COALESCE(plain_text, '') FROM notes
+                 WHERE is_trashed = 0 AND is_folder = 0 AND id != ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![note_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .map(|(id, text)| (id, term_frequencies(&text)))
+            .collect();
+        rows
+    };
+
+    // Document frequencies over the whole corpus, this note included.
+    let mut df: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
+    for terms in docs.values().chain(std::iter::once(&own_terms)) {
+        for term in terms.keys() {
+            *df.entry(term.as_str()).or_insert(0) += 1;
+        }
+    }
+    let total_docs = docs.len() + 1;
+    let tf_idf = |terms: &std::collections::HashMap<String, usize>| -> TermVector {
+        let mut vector: TermVector = terms
+            .iter()
+            .map(|(term, &tf)| {
+                let idf = ((total_docs as f64 + 1.0)
+                    / (df.get(term.as_str()).copied().unwrap_or(0) as f64 + 1.0))
+                    .ln()
+                    + 1.0;
+                (term.clone(), (1.0 + (tf as f64).ln()) * idf)
+            })
+            .collect();
+        let norm = vector.values().map(|w| w * w).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            vector.values_mut().for_each(|w| *w /= norm);
+        }
+        vector
+    };
+    let own_vector = tf_idf(&own_terms);
+
+    let mut own_tags: Vec<String> = Vec::new();
+    let mut profiles: std::collections::HashMap<String, (TermVector, usize)> =
+        std::collections::HashMap::new();
+    {
+        let mut stmt = conn
+            .prepare("SELECT nt.note_id, t.name FROM note_tags nt JOIN tags t ON t.id = nt.tag_id")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+        let mut vectors: std::collections::HashMap<String, TermVector> =
+            std::collections::HashMap::new();
+        for (member, tag) in rows.flatten() {
+            if member == note_id {
+                own_tags.push(tag);
+                continue;
+            }
+            if !vectors.contains_key(&member) {
+                // Trashed notes and folders have no document.
+                let Some(terms) = docs.get(&member) else {
+                    continue;
+                };
+                vectors.insert(member.clone(), tf_idf(terms));
+            }
+            let vector = &vectors[&member];
+            let (profile, count) = profiles.entry(tag).or_default();
+            for (term, weight) in vector {
+                *profile.entry(term.clone()).or_insert(0.0) += weight;
+            }
+            *count += 1;
+        }
+    }
+
+    let mut suggestions: Vec<TagSuggestion> = Vec::new();
+    for (name, (profile, count)) in profiles {
+        if own_tags.iter().any(|own| tag_in_subtree(own, &name)) {
+            continue;
+        }
+        let norm = profile.values().map(|w| w * w).sum::<f64>().sqrt();
+        if norm == 0.0 {
+            continue;
+        }
+        let mut contributions: Vec<(&String, f64)> = own_vector
+            .iter()
+            .filter_map(|(term, w)| profile.get(term).map(|p| (term, w * p / norm)))
+            .collect();
+        let confidence: f64 = contributions.iter().map(|(_, c)| c).sum();
+        if confidence < TAG_SUGGESTION_MIN_CONFIDENCE {
+            continue;
+        }
+        contributions.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let (tag_id, color): (String, Option<String>) = conn
+            .query_row(
+                "SELECT id, color FROM tags WHERE name = ?1",
+                params![name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| e.to_string())?;
+        suggestions.push(TagSuggestion {
+            tag_id,
+            name,
+            color,
+            confidence: (confidence * 1000.0).round() / 1000.0,
+            shared_terms: contributions
+                .iter()
+                .take(5)
+                .map(|(t, _)| t.to_string())
+                .collect(),
+            note_count: count,
+        });
+    }
+
+    suggestions.sort_by(|a, b| {
+        b.confidence
+            .partial_cmp(&a.confidence)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    suggestions.truncate(limit);
+    Ok(suggestions)
+}
+
+/// Add a suggested tag to a note. It is stored with source `suggested`, so
+/// later inline tag syncs leave it alone.
+#[tauri::command]
+pub fn accept_tag_suggestion(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    note_id: String,
+    tag_id: String,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    with_graph_delta(&app, &conn, std::slice::from_ref(&note_id), || {
+        conn.execute(
+            "INSERT OR IGNORE INTO note_tags (note_id, tag_id, source) VALUES (?1, ?2, 'suggested')",
+            params![note_id, tag_id],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    })
+}
+
+// ─── Flashcard Commands ──────────────────────────────────
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FlashcardData {
+    pub id: String,
+    pub note_id: String,
+    pub question: String,
+    pub answer: String,
+    pub next_review: i64,
+    pub interval: f64,
+    pub ease_factor: f64,
+    pub repetitions: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FlashcardStats {
+    pub due_today: i64,
+    pub total_cards: i64,
+    pub reviewed_today: i64,
+    pub streak: i64,
+}
+
+#[tauri::command]
+pub fn sync_flashcards(
+    db: State<Database>,
+    note_id: String,
+    cards: Vec<(String, String)>,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    // Remove old flashcards for this note
+    conn.execute("DELETE FROM flashcards WHERE note_id = ?1", params![note_id])
+        .map_err(|e| e.to_string())?;
+
+    // Insert new ones
+    for (question, answer) in &cards {
+        let id = Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO flashcards (id, note_id, question, answer) VALUES (?1, ?2, ?3, ?4)",
+            params![id, note_id, question, answer],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_due_flashcards(db: State<Database>) -> Result<Vec<FlashcardData>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT f.id, f.note_id, f.question, f.answer, f.next_review, f.interval, f.ease_factor, f.repetitions
+             FROM flashcards f
+             JOIN notes n ON n.id = f.note_id AND n.is_trashed = 0
+             WHERE f.next_review <= unixepoch()
+             ORDER BY f.next_review ASC
+             LIMIT 50",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let cards = stmt
+        .query_map([], |row| {
+            Ok(FlashcardData {
+                id: row.get(0)?,
+                note_id: row.get(1)?,
+                question: row.get(2)?,
+                answer: row.get(3)?,
+                next_review: row.get(4)?,
+                interval: row.get(5)?,
+                ease_factor: row.get(6)?,
+                repetitions: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(cards)
+}
+
+#[tauri::command]
+pub fn review_flashcard(
+    db: State<Database>,
+    card_id: String,
+    rating: i32,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    // Get current card state
+    let (interval, ease_factor, repetitions): (f64, f64, i64) = conn
+        .query_row(
+            "SELECT interval, ease_factor, repetitions FROM flashcards WHERE id = ?1",
+            params![card_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    // SM-2 algorithm
+    let (new_interval, new_ef, new_reps) = if rating < 2 {
+        // Failed: reset
+        (1.0_f64, (ease_factor - 0.2_f64).max(1.3_f64), 0_i64)
+    } else {
+        let new_ef = (ease_factor + 0.1 - (4 - rating) as f64 * (0.08 + (4 - rating) as f64 * 0.02)).max(1.3);
+        let new_reps = repetitions + 1;
+        let new_interval = match new_reps {
+            1 => 1.0,
+            2 => 6.0,
+            _ => interval * new_ef,
+        };
+        (new_interval, new_ef, new_reps)
+    };
+
+    let next_review_delta = (new_interval * 86400.0) as i64; // days to seconds
+
+    conn.execute(
+        "UPDATE flashcards SET interval = ?1, ease_factor = ?2, repetitions = ?3, next_review = unixepoch() + ?4, updated_at = unixepoch() WHERE id = ?5",
+        params![new_interval, new_ef, new_reps, next_review_delta, card_id],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO flashcard_reviews (card_id, note_id, rating)
+         SELECT id, note_id, ?2 FROM flashcards WHERE id = ?1",
+        params![card_id, rating],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_flashcard_stats(db: State<Database>) -> Result<FlashcardStats, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let due_today: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM flashcards f JOIN notes n ON n.id = f.note_id AND n.is_trashed = 0 WHERE f.next_review <= unixepoch()",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
+    let total_cards: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM flashcards f JOIN notes n ON n.id = f.note_id AND n.is_trashed = 0",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
+    // Reviewed today: entries in the review log since local midnight
+    let timezone = app_timezone(&conn);
+    let today = chrono::Utc::now().with_timezone(&timezone).date_naive();
+    let reviewed_today: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM flashcard_reviews WHERE reviewed_at >= ?1",
+            params![local_day_start(timezone, today)],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
+    let streak = review_streak(&conn, timezone, today).unwrap_or(0);
+
+    Ok(FlashcardStats {
+        due_today,
+        total_cards,
+        reviewed_today,
+        streak,
+    })
+}
+
+/// Consecutive local days with reviews, walking back from `today` and
+/// stopping at the first gap. A day without reviews yet doesn't break a
+/// streak that ran until yesterday.
+fn review_streak(
+    conn: &rusqlite::Connection,
+    timezone: chrono_tz::Tz,
+    today: chrono::NaiveDate,
+) -> rusqlite::Result<i64> {
+    let mut stmt =
+        conn.prepare("SELECT reviewed_at FROM flashcard_reviews ORDER BY reviewed_at DESC")?;
+    let days = stmt
+        .query_map([], |row| row.get::<_, i64>(0))?
+        .filter_map(|r| r.ok())
+        .filter_map(|ts| local_datetime(timezone, ts))
+        .map(|dt| dt.date());
+
+    let mut streak = 0;
+    let mut expected = today;
+    for day in days {
+        if day > expected {
+            continue;
+        }
+        if day == expected || (streak == 0 && Some(day) == today.pred_opt()) {
+            streak += 1;
+            match day.pred_opt() {
+                Some(prev) => expected = prev,
+                None => break,
+            }
+        } else {
+            break;
+        }
+    }
+    Ok(streak)
+}
+
+// ─── Canvas Commands ─────────────────────────────────────
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CanvasItem {
+    pub id: String,
+    pub note_id: Option<String>,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CanvasConnection {
+    pub id: String,
+    pub from_item_id: String,
+    pub to_item_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CanvasData {
+    pub items: Vec<CanvasItem>,
+    pub connections: Vec<CanvasConnection>,
+}
+
+#[tauri::command]
+pub fn get_canvas_data(db: State<Database>) -> Result<CanvasData, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut item_stmt = conn
+        .prepare("SELECT id, note_id, x, y, width, height FROM canvas_items")
+        .map_err(|e| e.to_string())?;
+
+    let items = item_stmt
+        .query_map([], |row| {
+            Ok(CanvasItem {
+                id: row.get(0)?,
+                note_id: row.get(1)?,
+                x: row.get(2)?,
+                y: row.get(3)?,
+                width: row.get(4)?,
+                height: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut conn_stmt = conn
+        .prepare("SELECT id, from_item_id, to_item_id FROM canvas_connections")
+        .map_err(|e| e.to_string())?;
+
+    let connections = conn_stmt
+        .query_map([], |row| {
+            Ok(CanvasConnection {
+                id: row.get(0)?,
+                from_item_id: row.get(1)?,
+                to_item_id: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(CanvasData { items, connections })
+}
+
+#[tauri::command]
+pub fn save_canvas_item(
+    db: State<Database>,
+    id: String,
+    note_id: Option<String>,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "INSERT OR REPLACE INTO canvas_items (id, note_id, x, y, width, height) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![id, note_id, x, y, width, height],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_canvas_item(db: State<Database>, id: String) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM canvas_connections WHERE from_item_id = ?1 OR to_item_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM canvas_items WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn save_canvas_connection(
+    db: State<Database>,
+    id: String,
+    from_item_id: String,
+    to_item_id: String,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO canvas_connections (id, from_item_id, to_item_id) VALUES (?1, ?2, ?3)",
+        params![id, from_item_id, to_item_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_canvas_connection(db: State<Database>, id: String) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM canvas_connections WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// ─── Snippet Commands ────────────────────────────────────
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnippetData {
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    pub language: String,
+    pub tags: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+}
+
+#[tauri::command]
+pub fn create_snippet(
+    db: State<Database>,
+    title: String,
+    content: String,
+    language: String,
+    tags: String,
+) -> Result<String, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO snippets (id, title, content, language, tags) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id, title, content, language, tags],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn get_snippets(db: State<Database>) -> Result<Vec<SnippetData>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT id, title, content, language, tags, created_at FROM snippets ORDER BY created_at DESC")
+        .map_err(|e| e.to_string())?;
+
+    let snippets = stmt
+        .query_map([], |row| {
+            Ok(SnippetData {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                content: row.get(2)?,
+                language: row.get(3)?,
+                tags: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(snippets)
+}
+
+#[tauri::command]
+pub fn delete_snippet(db: State<Database>, id: String) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM snippets WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct SnippetSearchItem {
+    #[serde(flatten)]
+    pub snippet: SnippetData,
+    #[serde(rename = "titleHighlight")]
+    pub title_highlight: String,
+    pub excerpt: String,
+    #[serde(skip)]
+    pub rank: f64,
+}
+
+#[tauri::command]
+pub fn search_snippets(
+    db: State<Database>,
+    query: String,
+    language: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<Vec<SnippetSearchItem>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    query_snippets_fts(
+        &conn,
+        &query,
+        language.as_deref(),
+        &tags.unwrap_or_default(),
+        50,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Normalize a snippet tag for comparison against the comma-separated
+/// `snippets.tags` column.
+fn normalize_snippet_tag(tag: &str) -> String {
+    tag.to_lowercase()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+/// Ranked snippet search. An empty query lists snippets matching the
+/// language/tag filters, newest first.
+fn query_snippets_fts(
+    conn: &rusqlite::Connection,
+    query: &str,
+    language: Option<&str>,
+    tags: &[String],
+    limit: i64,
+) -> rusqlite::Result<Vec<SnippetSearchItem>> {
+    let fts_query = fts_phrase_prefix(query);
+    let mut args: Vec<String> = Vec::new();
+    let mut filters: Vec<String> = Vec::new();
+
+    if let Some(q) = &fts_query {
+        args.push(q.clone());
+        filters.push(format!("snippets_fts MATCH ?{}", args.len()));
+    }
+    if let Some(lang) = language.filter(|l| !l.trim().is_empty()) {
+        args.push(lang.trim().to_lowercase());
+        filters.push(format!("LOWER(s.language) = ?{}", args.len()));
+    }
+    for tag in tags {
+        let tag = normalize_snippet_tag(tag);
+        if tag.is_empty() {
+            continue;
+        }
+        args.push(format!(",{},", tag));
+        filters.push(format!(
+            "instr(',' || REPLACE(REPLACE(LOWER(s.tags), ' ', ''), char(9), '') || ',', ?{}) > 0",
+            args.len()
+        ));
+    }
+
+    let where_clause = if filters.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", filters.join(" AND "))
+    };
+    let sql = if fts_query.is_some() {
+        format!(
+            "SELECT s.id, s.title, s.content, s.language, s.tags, s.created_at,
+                    highlight(snippets_fts, 0, '<mark>', '</mark>'),
+                    snippet(snippets_fts, 1, '<mark>', '</mark>', '...', 24),
+                    rank
+             FROM snippets_fts
+             JOIN snippets s ON s.rowid = snippets_fts.rowid
+             {}
+             ORDER BY rank
+             LIMIT {}",
+            where_clause, limit
+        )
+    } else {
+        format!(
+            "SELECT s.id, s.title, s.content, s.language, s.tags, s.created_at,
+                    s.title, SUBSTR(s.content, 1, 160), 0.0
+             FROM snippets s
+             {}
+             ORDER BY s.created_at DESC
+             LIMIT {}",
+            where_clause, limit
+        )
+    };
+
+    let mut stmt = conn.prepare(&sql)?;
+    let snippets = stmt
+        .query_map(rusqlite::params_from_iter(args.iter()), |row| {
+            Ok(SnippetSearchItem {
+                snippet: SnippetData {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    content: row.get(2)?,
+                    language: row.get(3)?,
+                    tags: row.get(4)?,
+                    created_at: row.get(5)?,
+                },
+                title_highlight: row.get(6)?,
+                excerpt: row.get(7)?,
+                rank: row.get(8)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(snippets)
+}
+
+// ─── Timezone ────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct TimezoneInfo {
+    /// IANA name days are bucketed in.
+    pub timezone: String,
+    /// The operating system's zone, if it could be read.
+    pub detected: Option<String>,
+    /// Whether `timezone` was set by the user rather than detected.
+    pub configured: bool,
+    #[serde(rename = "offsetMinutes")]
+    pub offset_minutes: i32,
+    /// Today's date in `timezone`.
+    pub today: String,
+}
+
+#[tauri::command]
+pub fn get_timezone(db: State<Database>) -> Result<TimezoneInfo, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    Ok(timezone_info(&conn))
+}
+
+/// Set the IANA timezone (e.g. `America/Los_Angeles`); `None` goes back to
+/// following the system.
+#[tauri::command]
+pub fn set_timezone(db: State<Database>, timezone: Option<String>) -> Result<TimezoneInfo, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    match timezone
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+    {
+        Some(name) => {
+            let tz: chrono_tz::Tz = name
+                .parse()
+                .map_err(|_| format!("Unknown timezone \"{}\"", name))?;
+            conn.execute(
+                "INSERT INTO app_settings (key, value) VALUES ('timezone', ?1)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                params![tz.name()],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        None => {
+            conn.execute("DELETE FROM app_settings WHERE key = 'timezone'", [])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(timezone_info(&conn))
+}
+
+fn timezone_info(conn: &rusqlite::Connection) -> TimezoneInfo {
+    use chrono::Offset;
+    let configured = configured_timezone(conn);
+    let timezone = app_timezone(conn);
+    let now = chrono::Utc::now().with_timezone(&timezone);
+    TimezoneInfo {
+        timezone: timezone.name().to_string(),
+        detected: detected_timezone().map(|tz| tz.name().to_string()),
+        configured: configured.is_some(),
+        offset_minutes: now.offset().fix().local_minus_utc() / 60,
+        today: now.date_naive().to_string(),
+    }
+}
+
+fn configured_timezone(conn: &rusqlite::Connection) -> Option<chrono_tz::Tz> {
+    conn.query_row(
+        "SELECT value FROM app_settings WHERE key = 'timezone'",
+        [],
+        |row| row.get::<_, String>(0),
+    )
+    .ok()?
+    .parse()
+    .ok()
+}
+
+fn detected_timezone() -> Option<chrono_tz::Tz> {
+    iana_time_zone::get_timezone().ok()?.parse().ok()
+}
+
+/// The zone all day bucketing uses: the configured one, else the system's,
+/// else UTC.
+fn app_timezone(conn: &rusqlite::Connection) -> chrono_tz::Tz {
+    configured_timezone(conn)
+        .or_else(detected_timezone)
+        .unwrap_or(chrono_tz::UTC)
+}
+
+fn local_now(conn: &rusqlite::Connection) -> chrono::NaiveDateTime {
+    chrono::Utc::now()
+        .with_timezone(&app_timezone(conn))
+        .naive_local()
+}
+
+fn local_datetime(timezone: chrono_tz::Tz, ts: i64) -> Option<chrono::NaiveDateTime> {
+    chrono::DateTime::from_timestamp(ts, 0).map(|dt| dt.with_timezone(&timezone).naive_local())
+}
+
+/// Unix time at which `date` starts locally. Where a DST change skips
+/// midnight, the day starts at the first hour that exists.
+fn local_day_start(timezone: chrono_tz::Tz, date: chrono::NaiveDate) -> i64 {
+    use chrono::TimeZone;
+    let midnight = date.and_time(chrono::NaiveTime::MIN);
+    (0..=3)
+        .find_map(|hour| {
+            timezone
+                .from_local_datetime(&(midnight + chrono::Duration::hours(hour)))
+                .earliest()
+        })
+        .map_or_else(|| midnight.and_utc().timestamp(), |dt| dt.timestamp())
+}
+
+// ─── Writing Stats Commands ──────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct WritingStat {
+    pub date: String,
+    pub words_written: i64,
+    pub notes_edited: i64,
+    pub time_spent_seconds: i64,
+}
+
+#[tauri::command]
+pub fn record_writing_stat(
+    db: State<Database>,
+    words_written: i64,
+    notes_edited: i64,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let today = local_now(&conn).date().to_string();
+
+    conn.execute(
+        "INSERT INTO writing_stats (date, words_written, notes_edited)
+         VALUES (?3, ?1, ?2)
+         ON CONFLICT(date) DO UPDATE SET
+           words_written = writing_stats.words_written + excluded.words_written,
+           notes_edited = writing_stats.notes_edited + excluded.notes_edited",
+        params![words_written, notes_edited, today],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_writing_stats(db: State<Database>, days: i64) -> Result<Vec<WritingStat>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT date, words_written, notes_edited, time_spent_seconds
+             FROM writing_stats
+             WHERE ?1 IS NULL OR date >= ?1
+             ORDER BY date ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    // A range reaching past the earliest representable date means everything
+    let since = chrono::TimeDelta::try_days(days.max(0))
+        .and_then(|span| local_now(&conn).date().checked_sub_signed(span))
+        .map(|date| date.to_string());
+    let stats = stmt
+        .query_map(params![since], |row| {
+            Ok(WritingStat {
+                date: row.get(0)?,
+                words_written: row.get(1)?,
+                notes_edited: row.get(2)?,
+                time_spent_seconds: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(stats)
+}
+
+// ─── Notes by Date (Calendar) ────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct NotesByDateItem {
+    pub id: String,
+    pub title: String,
+    pub emoji: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+}
+
+#[tauri::command]
+pub fn get_notes_by_date_range(
+    db: State<Database>,
+    start_ts: i64,
+    end_ts: i64,
+) -> Result<Vec<NotesByDateItem>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, title, emoji, created_at, updated_at FROM notes
+             WHERE is_trashed = 0 AND is_folder = 0
+             AND (created_at BETWEEN ?1 AND ?2 OR updated_at BETWEEN ?1 AND ?2)
+             ORDER BY updated_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let notes = stmt
+        .query_map(params![start_ts, end_ts], |row| {
+            Ok(NotesByDateItem {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                emoji: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    Ok(notes)
+}
+
+/// Longest range `get_calendar_days` serves in one call.
+const MAX_CALENDAR_DAYS: i64 = 1000;
+
+#[derive(Debug, Serialize)]
+pub struct CalendarDay {
+    /// `YYYY-MM-DD` in the app timezone.
+    pub date: String,
+    #[serde(rename = "notesCreated")]
+    pub notes_created: i64,
+    /// Notes edited that day, as recorded in the writing stats along with
+    /// `wordsWritten`.
+    #[serde(rename = "notesEdited")]
+    pub notes_edited: i64,
+    #[serde(rename = "wordsWritten")]
+    pub words_written: i64,
+    #[serde(rename = "flashcardsReviewed")]
+    pub flashcards_reviewed: i64,
+    #[serde(rename = "tasksDue")]
+    pub tasks_due: i64,
+    #[serde(rename = "tasksOpen")]
+    pub tasks_open: i64,
+    #[serde(rename = "dailyNoteId")]
+    pub daily_note_id: Option<String>,
+}
+
+/// Per-day activity for every day from `start_date` to `end_date`
+/// (inclusive, `YYYY-MM-DD`), bucketed in the app timezone, for calendar
+/// heatmaps.
+#[tauri::command]
+pub fn get_calendar_days(
+    db: State<Database>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<CalendarDay>, String> {
+    let parse = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date \"{}\"", date))
+    };
+    let (start, end) = (parse(&start_date)?, parse(&end_date)?);
+    let span = (end - start).num_days() + 1;
+    if span < 1 {
+        return Err("End date is before start date".to_string());
+    }
+    if span > MAX_CALENDAR_DAYS {
+        return Err(format!(
+            "Calendar range is limited to {} days",
+            MAX_CALENDAR_DAYS
+        ));
+    }
+
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let timezone = app_timezone(&conn);
+    let from_ts = local_day_start(timezone, start);
+    let to_ts = local_day_start(timezone, end + chrono::Duration::days(1));
+
+    let mut days: Vec<CalendarDay> = start
+        .iter_days()
+        .take(span as usize)
+        .map(|date| CalendarDay {
+            date: date.to_string(),
+            notes_created: 0,
+            notes_edited: 0,
+            words_written: 0,
+            flashcards_reviewed: 0,
+            tasks_due: 0,
+            tasks_open: 0,
+            daily_note_id: None,
+        })
+        .collect();
+    let day_index = |date: chrono::NaiveDate| {
+        let offset = (date - start).num_days();
+        (0..span).contains(&offset).then_some(offset as usize)
+    };
+    let ts_index = |ts: i64| local_datetime(timezone, ts).and_then(|dt| day_index(dt.date()));
+    let date_index = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .ok()
+            .and_then(day_index)
+    };
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT created_at FROM notes
+             WHERE is_trashed = 0 AND is_folder = 0 AND created_at >= ?1 AND created_at < ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_ts, to_ts], |row| row.get::<_, i64>(0))
+        .map_err(|e| e.to_string())?;
+    for i in rows.flatten().filter_map(ts_index) {
+        days[i].notes_created += 1;
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT reviewed_at FROM flashcard_reviews WHERE reviewed_at >= ?1 AND reviewed_at < ?2")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![from_ts, to_ts], |row| row.get::<_, i64>(0))
+        .map_err(|e| e.to_string())?;
+    for i in rows.flatten().filter_map(ts_index) {
+        days[i].flashcards_reviewed += 1;
+    }
+
+    let (start_key, end_key) = (start.to_string(), end.to_string());
+    let mut stmt = conn
+        .prepare(
+            "SELECT date, words_written, notes_edited FROM writing_stats
+             WHERE date BETWEEN ?1 AND ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![start_key, end_key], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    for (date, words, edited) in rows.flatten() {
+        if let Some(i) = date_index(&date) {
+            days[i].words_written += words;
+            days[i].notes_edited += edited;
+        }
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT k.due_date, COUNT(*), SUM(k.checked = 0) FROM tasks k
+             JOIN notes n ON n.id = k.note_id AND n.is_trashed = 0
+             WHERE k.due_date BETWEEN ?1 AND ?2
+             GROUP BY k.due_date",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![start_key, end_key], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    for (date, due, open) in rows.flatten() {
+        if let Some(i) = date_index(&date) {
+            days[i].tasks_due += due;
+            days[i].tasks_open += open;
+        }
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT period_date, id FROM notes
+             WHERE period_type = 'day' AND is_trashed = 0 AND period_date BETWEEN ?1 AND ?2
+             ORDER BY created_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![start_key, end_key], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    for (date, id) in rows.flatten() {
+        if let Some(i) = date_index(&date) {
+            days[i].daily_note_id = Some(id);
+        }
+    }
+
+    Ok(days)
+}
+
+// ─── Kanban: notes by tag ────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct KanbanCard {
+    pub id: String,
+    pub title: String,
+    pub emoji: Option<String>,
+    pub preview: String,
+    #[serde(rename = "updatedAt")]
+    pub updated_at: i64,
+    pub tags: Vec<String>,
+}
+
+#[tauri::command]
+pub fn get_kanban_data(db: State<Database>) -> Result<Vec<KanbanCard>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    // Get all non-trashed, non-folder notes that have tags
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT n.id, n.title, n.emoji, SUBSTR(COALESCE(n.plain_text, ''), 1, 100), COALESCE(n.updated_at, 0)
+             FROM notes n
+             JOIN note_tags nt ON nt.note_id = n.id
+             WHERE n.is_trashed = 0 AND n.is_folder = 0
+             ORDER BY n.updated_at DESC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut cards: Vec<KanbanCard> = stmt
+        .query_map([], |row| {
+            Ok(KanbanCard {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                emoji: row.get(2)?,
+                preview: row.get(3)?,
+                updated_at: row.get(4)?,
+                tags: vec![],
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    // Fill in tags for each card
+    for card in &mut cards {
+        let mut tag_stmt = conn
+            .prepare("SELECT t.name FROM note_tags nt JOIN tags t ON t.id = nt.tag_id WHERE nt.note_id = ?1")
+            .map_err(|e| e.to_string())?;
+        card.tags = tag_stmt
+            .query_map(params![card.id], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+    }
+
+    Ok(cards)
+}
+
+#[tauri::command]
+pub fn move_note_to_tag(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    note_id: String,
+    from_tag: String,
+    to_tag: String,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+
+    with_graph_delta(&app, &conn, std::slice::from_ref(&note_id), || {
+        // Remove old tag
+        let from_tag_id: Option<String> = conn
+            .query_row("SELECT id FROM tags WHERE name = ?1", params![from_tag], |row| row.get(0))
+            .ok();
+        if let Some(tid) = from_tag_id {
+            conn.execute(
+                "DELETE FROM note_tags WHERE note_id = ?1 AND tag_id = ?2",
+                params![note_id, tid],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+
+        // Add new tag
+        let actual_id = ensure_tag(&conn, &to_tag).map_err(|e| e.to_string())?;
+
+        conn.execute(
+            "INSERT OR IGNORE INTO note_tags (note_id, tag_id, source) VALUES (?1, ?2, 'manual')",
+            params![note_id, actual_id],
+        )
+        .map_err(|e| e.to_string())?;
+
+        Ok(())
+    })
+}
+
+// ─── Note Properties ─────────────────────────────────────
+
+/// The value types a property can have. A key's type is fixed by its entry
+/// in `property_schemas`, created the first time the key is used.
+const PROPERTY_TYPES: &[&str] = &["text", "number", "date", "checkbox", "list", "note-link"];
+
+#[derive(Debug, Serialize)]
+pub struct PropertySchema {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub property_type: String,
+    #[serde(rename = "noteCount")]
+    pub note_count: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NoteProperty {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub property_type: String,
+    /// Text, number, `YYYY-MM-DD` date string, boolean, array of strings,
+    /// or the id of the linked note.
+    pub value: serde_json::Value,
+    /// Title of the linked note, for `note-link` properties.
+    #[serde(rename = "linkTitle")]
+    pub link_title: Option<String>,
+}
+
+#[tauri::command]
+pub fn get_note_properties(
+    db: State<Database>,
+    note_id: String,
+) -> Result<Vec<NoteProperty>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    query_note_properties(&conn, &note_id).map_err(|e| e.to_string())
+}
+
+/// Set one property on a note. A new key is registered with `property_type`,
+/// or a type inferred from the value; an existing key keeps its registered
+/// type and the value must fit it.
+#[tauri::command]
+pub fn set_note_property(
+    db: State<Database>,
+    note_id: String,
+    key: String,
+    value: serde_json::Value,
+    property_type: Option<String>,
+) -> Result<NoteProperty, String> {
+    let key = key.trim().to_string();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    store_note_property(&conn, &note_id, &key, value, property_type.as_deref())?;
+    query_note_properties(&conn, &note_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|p| p.key.eq_ignore_ascii_case(&key))
+        .ok_or_else(|| "Property not found".to_string())
+}
+
+#[tauri::command]
+pub fn delete_note_property(
+    db: State<Database>,
+    note_id: String,
+    key: String,
+) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM note_properties WHERE note_id = ?1 AND key = ?2",
+        params![note_id, key.trim()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_property_schemas(db: State<Database>) -> Result<Vec<PropertySchema>, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.key, s.type, COUNT(n.id)
+             FROM property_schemas s
+             LEFT JOIN note_properties p ON p.key = s.key
+             LEFT JOIN notes n ON n.id = p.note_id AND n.is_trashed = 0
+             GROUP BY s.key
+             ORDER BY s.key COLLATE NOCASE",
+        )
+        .map_err(|e| e.to_string())?;
+    let schemas = stmt
+        .query_map([], |row| {
+            Ok(PropertySchema {
+                key: row.get(0)?,
+                property_type: row.get(1)?,
+                note_count: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(schemas)
+}
+
+/// Register `key` with a type, or change the type of an existing key. Every
+/// stored value is converted to the new type; if any cannot be, nothing
+/// changes.
+#[tauri::command]
+pub fn set_property_schema(
+    db: State<Database>,
+    key: String,
+    property_type: String,
+) -> Result<(), String> {
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        return Err("Property name must not be empty".to_string());
+    }
+    if !PROPERTY_TYPES.contains(&property_type.as_str()) {
+        return Err(format!("Unknown property type \"{}\"", property_type));
+    }
+
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let values: Vec<(String, String)> = {
+        let mut stmt = tx
+            .prepare("SELECT note_id, value FROM note_properties WHERE key = ?1")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![key], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?
+            .filter_map(|r| r.ok())
+            .collect();
+        rows
+    };
+    let mut failed = 0;
+    for (note_id, raw) in values {
+        let value = serde_json::from_str(&raw).unwrap_or(serde_json::Value::String(raw));
+        match coerce_property_value(&tx, &property_type, value) {
+            Ok(converted) => {
+                tx.execute(
+                    "UPDATE note_properties SET value = ?1, updated_at = unixepoch()
+                     WHERE note_id = ?2 AND key = ?3",
+                    params![converted.to_string(), note_id, key],
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            Err(_) => failed += 1,
+        }
+    }
+    if failed > 0 {
+        return Err(format!(
+            "{} {} a \"{}\" value that is not a valid {}",
+            failed,
+            if failed == 1 { "note has" } else { "notes have" },
+            key,
+            property_type
+        ));
+    }
+
+    tx.execute(
+        "INSERT INTO property_schemas (key, type) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET type = excluded.type",
+        params![key, property_type],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Remove a key from the registry. Refused while notes still use it.
+#[tauri::command]
+pub fn delete_property_schema(db: State<Database>, key: String) -> Result<(), String> {
+    let key = key.trim();
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let used: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM note_properties WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    if used > 0 {
+        return Err(format!(
+            "Property \"{}\" is still set on {} note{}",
+            key,
+            used,
+            if used == 1 { "" } else { "s" }
+        ));
+    }
+    conn.execute("DELETE FROM property_schemas WHERE key = ?1", params![key])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Render a note's properties as a YAML frontmatter block (`---` fenced).
+/// Returns an empty string when the note has none.
+#[tauri::command]
+pub fn export_note_frontmatter(db: State<Database>, note_id: String) -> Result<String, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    note_frontmatter(&conn, &note_id)
+}
+
+/// Read YAML frontmatter (a full Markdown document or just the YAML) into
+/// a note's properties. Keys that are already registered are converted to
+/// their type; new keys get an inferred type. With `replace`, properties
+/// missing from the YAML are removed.
+#[tauri::command]
+pub fn import_note_frontmatter(
+    db: State<Database>,
+    note_id: String,
+    yaml: String,
+    replace: Option<bool>,
+) -> Result<Vec<NoteProperty>, String> {
+    let (frontmatter, _) = split_frontmatter(&yaml);
+    let mapping: serde_norway::Mapping = match frontmatter.unwrap_or(&yaml).trim() {
+        "" => serde_norway::Mapping::new(),
+        text => serde_norway::from_str(text).map_err(|e| format!("Invalid frontmatter: {}", e))?,
+    };
+
+    let mut entries: Vec<(String, serde_json::Value)> = Vec::new();
+    for (key, value) in mapping {
+        let key = match key {
+            serde_norway::Value::String(s) => s,
+            other => serde_norway::to_string(&other)
+                .map_err(|e| e.to_string())?
+                .trim()
+                .to_string(),
+        };
+        let value = serde_json::to_value(&value).map_err(|e| format!("\"{}\": {}", key, e))?;
+        if !value.is_null() {
+            entries.push((key.trim().to_string(), value));
+        }
+    }
+
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    if replace.unwrap_or(false) {
+        tx.execute(
+            "DELETE FROM note_properties WHERE note_id = ?1",
+            params![note_id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    for (key, value) in entries {
+        store_note_property(&tx, &note_id, &key, value, None)
+            .map_err(|e| format!("\"{}\": {}", key, e))?;
+    }
+    let properties = query_note_properties(&tx, &note_id).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(properties)
+}
+
+fn query_note_properties(
+    conn: &rusqlite::Connection,
+    note_id: &str,
+) -> rusqlite::Result<Vec<NoteProperty>> {
+    let mut stmt = conn.prepare(
+        "SELECT p.key, s.type, p.value, l.title
+         FROM note_properties p
+         JOIN property_schemas s ON s.key = p.key
+         LEFT JOIN notes l ON s.type = 'note-link' AND l.id = JSON_EXTRACT(p.value, '$')
+         WHERE p.note_id = ?1
+         ORDER BY p.position, p.key COLLATE NOCASE",
+    )?;
+    let properties = stmt
+        .query_map(params![note_id], |row| {
+            let raw: String = row.get(2)?;
+            Ok(NoteProperty {
+                key: row.get(0)?,
+                property_type: row.get(1)?,
+                value: serde_json::from_str(&raw).unwrap_or(serde_json::Value::String(raw)),
+                link_title: row.get(3)?,
+            })
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(properties)
+}
+
+/// Validate and write one property, registering the key if it is new.
+fn store_note_property(
+    conn: &rusqlite::Connection,
+    note_id: &str,
+    key: &str,
+    value: serde_json::Value,
+    property_type: Option<&str>,
+) -> Result<(), String> {
+    if key.is_empty() {
+        return Err("Property name must not be empty".to_string());
+    }
+    let registered: Option<String> = conn
+        .query_row(
+            "SELECT type FROM property_schemas WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .ok();
+    let property_type = match (registered, property_type) {
+        (Some(registered), Some(requested)) if registered != requested => {
+            return Err(format!(
+                "Property \"{}\" is a {} property, not {}",
+                key, registered, requested
+            ))
+        }
+        (Some(registered), _) => registered,
+        (None, Some(requested)) if PROPERTY_TYPES.contains(&requested) => requested.to_string(),
+        (None, Some(requested)) => return Err(format!("Unknown property type \"{}\"", requested)),
+        (None, None) => infer_property_type(&value).to_string(),
+    };
+    let value = coerce_property_value(conn, &property_type, value)?;
+
+    conn.execute(
+        "INSERT OR IGNORE INTO property_schemas (key, type) VALUES (?1, ?2)",
+        params![key, property_type],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO note_properties (note_id, key, value, position)
+         VALUES (?1, ?2, ?3,
+                 (SELECT COALESCE(MAX(position), -1) + 1 FROM note_properties WHERE note_id = ?1))
+         ON CONFLICT(note_id, key) DO UPDATE SET value = excluded.value, updated_at = unixepoch()",
+        params![note_id, key, value.to_string()],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn infer_property_type(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Bool(_) => "checkbox",
+        serde_json::Value::Number(_) => "number",
+        serde_json::Value::Array(_) => "list",
+        serde_json::Value::String(s) if wikilink_target(s).is_some() => "note-link",
+        serde_json::Value::String(s) if valid_property_date(s) => "date",
+        _ => "text",
+    }
+}
+
+/// Check `value` against a property type, converting the obvious cases
+/// (`"42"` for a number, a single string for a list, `[[Title]]` or a title
+/// for a note link).
+fn coerce_property_value(
+    conn: &rusqlite::Connection,
+    property_type: &str,
+    value: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    use serde_json::Value;
+
+    let invalid = |value: &Value| format!("{} is not a valid {}", value, property_type);
+    match (property_type, value) {
+        (_, Value::Null) => Err(format!("A {} property needs a value", property_type)),
+        ("text", Value::String(s)) => Ok(Value::String(s)),
+        ("text", v @ (Value::Number(_) | Value::Bool(_))) => Ok(Value::String(v.to_string())),
+        ("number", Value::Number(n)) => Ok(Value::Number(n)),
+        ("number", Value::String(s)) => s
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| invalid(&Value::String(s))),
+        ("date", Value::String(s)) if valid_property_date(s.trim()) => {
+            Ok(Value::String(s.trim().to_string()))
+        }
+        ("checkbox", Value::Bool(b)) => Ok(Value::Bool(b)),
+        ("checkbox", Value::String(s)) => match s.trim().to_ascii_lowercase().as_str() {
+            "true" | "yes" | "x" => Ok(Value::Bool(true)),
+            "false" | "no" | "" => Ok(Value::Bool(false)),
+            _ => Err(invalid(&Value::String(s))),
+        },
+        ("list", Value::Array(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(Value::String(s)),
+                v @ (Value::Number(_) | Value::Bool(_)) => Ok(Value::String(v.to_string())),
+                other => Err(invalid(&other)),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        ("list", Value::String(s)) => Ok(Value::Array(vec![Value::String(s)])),
+        ("note-link", Value::String(s)) => {
+            // Only live notes can be linked: no folders, nothing in the trash
+            let exists: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) > 0 FROM notes
+                     WHERE id = ?1 AND is_folder = 0 AND is_trashed = 0",
+                    params![s],
+                    |row| row.get(0),
+                )
+                .map_err(|e| e.to_string())?;
+            if exists {
+                return Ok(Value::String(s));
+            }
+            let title = wikilink_target(&s).unwrap_or(s.trim());
+            conn.query_row(
+                "SELECT id FROM (
+                     SELECT id, 0 AS priority FROM notes
+                     WHERE LOWER(title) = LOWER(?1) AND is_folder = 0 AND is_trashed = 0
+                     UNION ALL
+                     SELECT a.note_id, 1 FROM note_aliases a
+                     JOIN notes n ON n.id = a.note_id AND n.is_folder = 0 AND n.is_trashed = 0
+                     WHERE LOWER(a.alias) = LOWER(?1)
+                 )
+                 ORDER BY priority
+                 LIMIT 1",
+                params![link_title_part(title)],
+                |row| row.get::<_, String>(0),
+            )
+            .ok()
+            .map(Value::String)
+            .ok_or_else(|| format!("No note titled \"{}\"", title))
+        }
+        (_, value) => Err(invalid(&value)),
+    }
+}
+
+/// The inside of a value written as exactly one `[[wikilink]]`.
+fn wikilink_target(s: &str) -> Option<&str> {
+    s.trim()
+        .strip_prefix("[[")?
+        .strip_suffix("]]")
+        .filter(|inner| !inner.contains("]]"))
+        .map(str::trim)
+}
+
+/// `YYYY-MM-DD`, optionally followed by `THH:MM[:SS]`.
+fn valid_property_date(s: &str) -> bool {
+    chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok()
+        || [
+            "%Y-%m-%dT%H:%M",
+            "%Y-%m-%dT%H:%M:%S",
+            "%Y-%m-%d %H:%M",
+            "%Y-%m-%d %H:%M:%S",
+        ]
+        .iter()
+        .any(|format| chrono::NaiveDateTime::parse_from_str(s, format).is_ok())
+}
+
+fn note_frontmatter(conn: &rusqlite::Connection, note_id: &str) -> Result<String, String> {
+    let properties = query_note_properties(conn, note_id).map_err(|e| e.to_string())?;
+    if properties.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut mapping = serde_norway::Mapping::new();
+    for property in properties {
+        let value = match (property.property_type.as_str(), property.link_title) {
+            ("note-link", Some(title)) => serde_json::Value::String(format!("[[{}]]", title)),
+            _ => property.value,
+        };
+        let value = serde_norway::to_value(&value).map_err(|e| e.to_string())?;
+        mapping.insert(serde_norway::Value::String(property.key), value);
+    }
+    let yaml = serde_norway::to_string(&mapping).map_err(|e| e.to_string())?;
+    Ok(format!("---\n{}---\n", yaml))
+}
+
+/// Split a `---` fenced YAML block off the top of a Markdown document.
+fn split_frontmatter(markdown: &str) -> (Option<&str>, &str) {
+    let text = markdown.trim_start_matches('\u{feff}');
+    let Some(rest) = text.strip_prefix("---") else {
+        return (None, markdown);
+    };
+    let Some(rest) = rest
+        .strip_prefix('\n')
+        .or_else(|| rest.strip_prefix("\r\n"))
+    else {
+        return (None, markdown);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), "---" | "...") {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, markdown)
+}
+
+// ─── Tasks ───────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct TaskItem {
+    /// `<note id>/<block key>`; stable while the block keeps its id.
+    pub id: String,
+    #[serde(rename = "noteId")]
+    pub note_id: String,
+    #[serde(rename = "noteTitle")]
+    pub note_title: String,
+    #[serde(rename = "noteEmoji")]
+    pub note_emoji: Option<String>,
+    #[serde(rename = "blockId")]
+    pub block_id: Option<String>,
+    /// Order among the note's tasks.
+    pub position: i64,
+    /// Task text with the due date and priority markers removed.
+    pub text: String,
+    pub checked: bool,
+    /// `YYYY-MM-DD`, from `📅 2026-11-01`.
+    #[serde(rename = "dueDate")]
+    pub due_date: Option<String>,
+    /// `high`, `medium` or `low`, from `!high` or ⏫ / 🔼 / 🔽.
+    pub priority: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TaskFilter {
+    /// "open", "done" or "all" (the default)
+    pub status: Option<String>,
+    #[serde(rename = "noteId")]
+    pub note_id: Option<String>,
+    /// Only tasks in notes carrying this tag or one nested under it
+    pub tag: Option<String>,
+    /// Inclusive `YYYY-MM-DD` bounds on the due date
+    #[serde(rename = "dueAfter")]
+    pub due_after: Option<String>,
+    #[serde(rename = "dueBefore")]
+    pub due_before: Option<String>,
+    /// Only tasks with a due date (true) or without one (false)
+    #[serde(rename = "hasDueDate")]
+    pub has_due_date: Option<bool>,
+    pub priority: Option<String>,
+    /// Substring of the task text
+    pub query: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Tasks across the vault, open ones first, then by due date and priority.
+#[tauri::command]
+pub fn get_tasks(db: State<Database>, filter: Option<TaskFilter>) -> Result<Vec<TaskItem>, String> {
+    let filter = filter.unwrap_or_default();
+    let mut clauses: Vec<String> = vec!["n.is_trashed = 0".to_string()];
+    let mut values: Vec<rusqlite::types::Value> = Vec::new();
+    let mut text = |clause: &str, value: String| {
+        clauses.push(clause.to_string());
+        values.push(rusqlite::types::Value::Text(value));
+    };
+
+    if let Some(note_id) = filter.note_id {
+        text("k.note_id = ?", note_id);
+    }
+    if let Some(after) = filter.due_after {
+        text("k.due_date >= ?", after);
+    }
+    if let Some(before) = filter.due_before {
+        text("k.due_date <= ?", before);
+    }
+    if let Some(priority) = filter.priority {
+        text("k.priority = ?", priority.to_lowercase());
+    }
+    if let Some(query) = filter.query.filter(|q| !q.trim().is_empty()) {
+        text(
+            "k.text LIKE ? ESCAPE '\\'",
+            like_contains_pattern(query.trim()),
+        );
+    }
+    match filter.status.as_deref() {
+        None | Some("all") => {}
+        Some("open") => clauses.push("k.checked = 0".to_string()),
+        Some("done") => clauses.push("k.checked = 1".to_string()),
+        Some(other) => return Err(format!("Unknown task status \"{}\"", other)),
+    }
+    match filter.has_due_date {
+        Some(true) => clauses.push("k.due_date IS NOT NULL".to_string()),
+        Some(false) => clauses.push("k.due_date IS NULL".to_string()),
+        None => {}
+    }
+    if let Some(tag) = filter.tag {
+        clauses.push(format!(
+            "k.note_id IN (SELECT nt.note_id FROM note_tags nt JOIN tags t ON t.id = nt.tag_id
+                           WHERE {})",
+            TAG_SUBTREE_SQL
+        ));
+        values.extend(tag_subtree_params(&tag));
+    }
+    let limit = filter.limit.map_or(-1, |l| l as i64);
+    values.push(rusqlite::types::Value::Integer(limit));
+
+    let sql = format!(
+        "SELECT k.id, k.note_id, n.title, n.emoji, k.block_id, k.position, k.text, k.checked,
+                k.due_date, k.priority
+         FROM tasks k JOIN notes n ON n.id = k.note_id
+         WHERE {}
+         ORDER BY k.checked, k.due_date IS NULL, k.due_date,
+                  CASE k.priority WHEN 'high' THEN 0 WHEN 'medium' THEN 1 WHEN 'low' THEN 2 ELSE 3 END,
+                  n.title COLLATE NOCASE, k.position
+         LIMIT ?",
+        clauses.join(" AND ")
+    );
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let tasks = stmt
+        .query_map(rusqlite::params_from_iter(values), map_task_item)
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(tasks)
+}
+
+/// Check or uncheck a task (flip it when `checked` is omitted), writing the
+/// change into the note's Plate JSON. Returns the updated task.
+#[tauri::command]
+pub fn toggle_task(
+    app: tauri::AppHandle,
+    db: State<Database>,
+    task_id: String,
+    checked: Option<bool>,
+) -> Result<TaskItem, String> {
+    let mut conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+
+    let (note_id, content): (String, String) = tx
+        .query_row(
+            "SELECT k.note_id, COALESCE(n.content, '[]')
+             FROM tasks k JOIN notes n ON n.id = k.note_id
+             WHERE k.id = ?1",
+            params![task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|_| "Task not found".to_string())?;
+
+    let mut nodes: Vec<serde_json::Value> = serde_json::from_str(&content).unwrap_or_default();
+    let found = collect_plate_tasks(&nodes)
+        .into_iter()
+        .find(|task| format!("{}/{}", note_id, task.key) == task_id);
+    let Some(task) = found else {
+        return Err(
+            "The task has changed since it was indexed; reload the note and try again".to_string(),
+        );
+    };
+
+    let checked = checked.unwrap_or(!task.checked);
+    let graph_ids = [note_id.clone()];
+    let graph_before = graph_snapshot(&tx, &graph_ids).map_err(|e| e.to_string())?;
+    if checked != task.checked {
+        let node = plate_node_at_mut(&mut nodes, &task.path)
+            .ok_or_else(|| "Task not found".to_string())?;
+        node["checked"] = serde_json::Value::Bool(checked);
+        record_revision(&tx, &note_id, None, "toggle_task").map_err(|e| e.to_string())?;
+        write_note_nodes(&tx, &note_id, &nodes).map_err(|e| e.to_string())?;
+    }
+    let graph_after = graph_snapshot(&tx, &graph_ids).map_err(|e| e.to_string())?;
+
+    let item = tx
+        .query_row(
+            "SELECT k.id, k.note_id, n.title, n.emoji, k.block_id, k.position, k.text, k.checked,
+                    k.due_date, k.priority
+             FROM tasks k JOIN notes n ON n.id = k.note_id
+             WHERE k.id = ?1",
+            params![task_id],
+            map_task_item,
+        )
+        .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+    emit_graph_delta(&app, graph_before, graph_after);
+    Ok(item)
+}
+
+fn map_task_item(row: &rusqlite::Row) -> rusqlite::Result<TaskItem> {
+    Ok(TaskItem {
+        id: row.get(0)?,
+        note_id: row.get(1)?,
+        note_title: row.get(2)?,
+        note_emoji: row.get(3)?,
+        block_id: row.get(4)?,
+        position: row.get(5)?,
+        text: row.get(6)?,
+        checked: row.get::<_, i64>(7)? != 0,
+        due_date: row.get(8)?,
+        priority: row.get(9)?,
+    })
+}
+
+fn plate_node_at_mut<'a>(
+    nodes: &'a mut [serde_json::Value],
+    path: &[usize],
+) -> Option<&'a mut serde_json::Value> {
+    let (first, rest) = path.split_first()?;
+    let mut node = nodes.get_mut(*first)?;
+    for &i in rest {
+        node = node.get_mut("children")?.get_mut(i)?;
+    }
+    Some(node)
+}
+
+/// Rebuild a note's rows in `tasks` from its Plate JSON, keeping reminders
+/// attached to reworded tasks and detaching them from removed ones.
+fn index_note_tasks(
+    conn: &rusqlite::Connection,
+    note_id: &str,
+    content: &str,
+) -> rusqlite::Result<()> {
+    let task_positions = |conn: &rusqlite::Connection| -> rusqlite::Result<Vec<(String, i64)>> {
+        let mut stmt = conn.prepare("SELECT id, position FROM tasks WHERE note_id = ?1")?;
+        let rows = stmt.query_map(params![note_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.flatten().collect())
+    };
+    let before = task_positions(conn)?;
+    write_note_task_rows(conn, note_id, content)?;
+    let after = task_positions(conn)?;
+
+    // A task without a block id changes id when reworded; its reminders
+    // follow the new task that took its place
+    for (old_id, position) in &before {
+        if after.iter().any(|(id, _)| id == old_id) {
+            continue;
+        }
+        let replacement = after
+            .iter()
+            .find(|(id, p)| p == position && !before.iter().any(|(old, _)| old == id));
+        if let Some((new_id, _)) = replacement {
+            conn.execute(
+                "UPDATE reminders SET task_id = ?1 WHERE task_id = ?2",
+                params![new_id, old_id],
+            )?;
+        }
+    }
+    // Reminders of removed tasks stay on as note reminders
+    conn.execute(
+        "UPDATE reminders SET task_id = NULL
+         WHERE note_id = ?1 AND task_id IS NOT NULL
+           AND task_id NOT IN (SELECT id FROM tasks WHERE note_id = ?1)",
+        params![note_id],
+    )?;
+    Ok(())
+}
+
+// ─── Reminders ───────────────────────────────────────────
+
+/// How often the scheduler looks for due reminders.
+const REMINDER_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+/// Reminders missed while the app was closed get their own notification up
+/// to this many; beyond that a single summary is shown.
+const MISSED_REMINDER_NOTIFICATIONS: usize = 3;
+const DEFAULT_SNOOZE_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReminderItem {
+    pub id: String,
+    #[serde(rename = "noteId")]
+    pub note_id: String,
+    #[serde(rename = "noteTitle")]
+    pub note_title: String,
+    #[serde(rename = "taskId")]
+    pub task_id: Option<String>,
+    pub title: String,
+    /// Unix seconds.
+    #[serde(rename = "remindAt")]
+    pub remind_at: i64,
+    /// `pending`, `fired` or `dismissed`.
+    pub status: String,
+    #[serde(rename = "firedAt")]
+    pub fired_at: Option<i64>,
+    #[serde(rename = "snoozeCount")]
+    pub snooze_count: i64,
+}
+
+/// Payload of the `reminder-fired` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReminderFired {
+    pub reminder: ReminderItem,
+    /// Came due while the app was closed.
+    pub missed: bool,
+}
+
+/// Schedule a reminder for a note, or for a task (whose note is implied).
+/// The title defaults to the task text or the note title.
+#[tauri::command]
+pub fn create_reminder(
+    db: State<Database>,
+    note_id: Option<String>,
+    task_id: Option<String>,
+    remind_at: i64,
+    title: Option<String>,
+) -> Result<ReminderItem, String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let (note_id, default_title) = match (&task_id, note_id) {
+        (Some(task_id), _) => conn
+            .query_row(
+                "SELECT note_id, text FROM tasks WHERE id = ?1",
+                params![task_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .map_err(|_| "Task not found".to_string())?,
+        (None, Some(note_id)) => {
+            let title: String = conn
+                .query_row(
+                    "SELECT title FROM notes WHERE id = ?1",
+                    params![note_id],
+                    |row| row.get(0),
+                )
+                .map_err(|_| "Note not found".to_string())?;
+            (note_id, title)
+        }
+        (None, None) => return Err("A reminder needs a note or a task".to_string()),
+    };
+    let title = title
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .unwrap_or(default_title);
+
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO reminders (id, note_id, task_id, title, remind_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![id, note_id, task_id, title, remind_at],
+    )
+    .map_err(|e| e.to_string())?;
+    query_reminder(&conn, &id).map_err(|e| e.to_string())
+}
+
+/// Reminders, soonest first. `status` is `pending`, `fired`, `dismissed`
+/// or `all`; by default everything not yet dismissed.
+#[tauri::command]
+pub fn get_reminders(
+    db: State<Database>,
+    status: Option<String>,
+    note_id: Option<String>,
+) -> Result<Vec<ReminderItem>, String> {
+    let status_clause = match status.as_deref() {
+        None => "r.status != 'dismissed'",
+        Some("all") => "1 = 1",
+        Some("pending") => "r.status = 'pending'",
+        Some("fired") => "r.status = 'fired'",
+        Some("dismissed") => "r.status = 'dismissed'",
+        Some(other) => return Err(format!("Unknown reminder status \"{}\"", other)),
+    };
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "{} WHERE {} AND (?1 IS NULL OR r.note_id = ?1) ORDER BY r.remind_at",
+            REMINDER_SELECT, status_clause
+        ))
+        .map_err(|e| e.to_string())?;
+    let reminders = stmt
+        .query_map(params![note_id], map_reminder_item)
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .collect();
+    Ok(reminders)
+}
+
+/// Push a reminder back by `minutes` (10 by default) or to `until`, and
+/// make it pending again.
+#[tauri::command]
+pub fn snooze_reminder(
+    db: State<Database>,
+    reminder_id: String,
+    minutes: Option<i64>,
+    until: Option<i64>,
+) -> Result<ReminderItem, String> {
+    let remind_at = match until {
+        Some(until) => until,
+        None => unix_now() + minutes.unwrap_or(DEFAULT_SNOOZE_MINUTES).max(1) * 60,
+    };
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE reminders
+             SET remind_at = ?1, status = 'pending', fired_at = NULL, snooze_count = snooze_count + 1
+             WHERE id = ?2",
+            params![remind_at, reminder_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err("Reminder not found".to_string());
+    }
+    query_reminder(&conn, &reminder_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn dismiss_reminder(db: State<Database>, reminder_id: String) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE reminders SET status = 'dismissed' WHERE id = ?1",
+        params![reminder_id],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_reminder(db: State<Database>, reminder_id: String) -> Result<(), String> {
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM reminders WHERE id = ?1", params![reminder_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+const REMINDER_SELECT: &str =
+    "SELECT r.id, r.note_id, n.title, r.task_id, r.title, r.remind_at, r.status,
+            r.fired_at, r.snooze_count
+     FROM reminders r JOIN notes n ON n.id = r.note_id";
+
+fn map_reminder_item(row: &rusqlite::Row) -> rusqlite::Result<ReminderItem> {
+    Ok(ReminderItem {
+        id: row.get(0)?,
+        note_id: row.get(1)?,
+        note_title: row.get(2)?,
+        task_id: row.get(3)?,
+        title: row.get(4)?,
+        remind_at: row.get(5)?,
+        status: row.get(6)?,
+        fired_at: row.get(7)?,
+        snooze_count: row.get(8)?,
+    })
+}
+
+fn query_reminder(
+    conn: &rusqlite::Connection,
+    reminder_id: &str,
+) -> rusqlite::Result<ReminderItem> {
+    conn.query_row(
+        &format!("{} WHERE r.id = ?1", REMINDER_SELECT),
+        params![reminder_id],
+        map_reminder_item,
+    )
+}
+
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Background loop started from `lib::run`. Its first pass also catches up
+/// on reminders that came due while the app was closed.
+pub fn run_reminder_scheduler(app: tauri::AppHandle) {
+    let started_at = unix_now();
+    loop {
+        let fired = {
+            let db = app.state::<Database>();
+            // A command that panicked while holding the lock leaves it
+            // poisoned, but its open transaction was rolled back on drop,
+            // so the connection is still usable.
+            let conn = db
+                .conn
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            fire_due_reminders(&conn, unix_now(), started_at)
+        };
+        if let Ok(fired) = fired {
+            notify_reminders(&app, fired);
+        }
+        std::thread::sleep(REMINDER_POLL_INTERVAL);
+    }
+}
+
+/// Mark every pending reminder due by `now` as fired. Reminders on trashed
+/// notes wait until the note is restored; reminders on tasks that have been
+/// checked off are dismissed instead.
+pub(crate) fn fire_due_reminders(
+    conn: &rusqlite::Connection,
+    now: i64,
+    started_at: i64,
+) -> rusqlite::Result<Vec<ReminderFired>> {
+    conn.execute(
+        "UPDATE reminders SET status = 'dismissed'
+         WHERE status = 'pending' AND remind_at <= ?1
+           AND task_id IN (SELECT id FROM tasks WHERE checked = 1)",
+        params![now],
+    )?;
+
+    let due: Vec<ReminderItem> = {
+        let mut stmt = conn.prepare(&format!(
+            "{} WHERE r.status = 'pending' AND r.remind_at <= ?1 AND n.is_trashed = 0
+             ORDER BY r.remind_at",
+            REMINDER_SELECT
+        ))?;
+        let rows = stmt
+            .query_map(params![now], map_reminder_item)?
+            .filter_map(|r| r.ok())
+            .collect();
+        rows
+    };
+
+    let mut fired = Vec::with_capacity(due.len());
+    for mut reminder in due {
+        conn.execute(
+            "UPDATE reminders SET status = 'fired', fired_at = ?1 WHERE id = ?2",
+            params![now, reminder.id],
+        )?;
+        reminder.status = "fired".to_string();
+        reminder.fired_at = Some(now);
+        fired.push(ReminderFired {
+            missed: reminder.remind_at < started_at,
+            reminder,
+        });
+    }
+    Ok(fired)
+}
+
+/// Emit `reminder-fired` for each reminder and show desktop notifications,
+/// folding a large backlog of missed ones into a single summary.
+pub(crate) fn notify_reminders(app: &tauri::AppHandle, fired: Vec<ReminderFired>) {
+    let missed = fired.iter().filter(|f| f.missed).count();
+    let summarize_missed = missed > MISSED_REMINDER_NOTIFICATIONS;
+    for event in &fired {
+        if !(event.missed && summarize_missed) {
+            let body = if event.missed {
+                format!(
+                    "Missed while the app was closed · {}",
+                    event.reminder.note_title
+                )
+            } else {
+                event.reminder.note_title.clone()
+            };
+            let _ = app
+                .notification()
+                .builder()
+                .title(&event.reminder.title)
+                .body(body)
+                .show();
+        }
+        let _ = app.emit("reminder-fired", event.clone());
+    }
+    if summarize_missed {
+        let _ = app
+            .notification()
+            .builder()
+            .title(format!("{} missed reminders", missed))
+            .body("They came due while the app was closed.")
+            .show();
+    }
+}
+
+// ─── Note Queries ────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct QueryResult {
+    /// `table` or `list`.
+    pub kind: String,
+    /// One heading per requested field, in order.
+    pub columns: Vec<String>,
+    /// `TABLE WITHOUT ID`: the editor should not render the note column.
+    #[serde(rename = "withoutId")]
+    pub without_id: bool,
+    pub rows: Vec<QueryRow>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueryRow {
+    #[serde(rename = "noteId")]
+    pub note_id: String,
+    pub title: String,
+    pub emoji: Option<String>,
+    /// One value per column. Dates are ISO strings and note links are
+    /// `{ "noteId", "title" }` objects.
+    pub values: Vec<serde_json::Value>,
+}
+
+/// Run a Dataview-style query over live notes:
+///
+/// ```text
+/// TABLE [WITHOUT ID] expr [AS "Name"], ... | LIST [expr]
+/// [FROM #tag | "Folder/Path" | [[Note]]  (combined with AND, OR, NOT, -)]
+/// [WHERE expr]
+/// [SORT expr [ASC|DESC], ...]
+/// [LIMIT n]
+/// ```
+///
+/// Fields are note properties by name, or `file.name`, `file.link`,
+/// `file.folder`, `file.path`, `file.ctime`, `file.mtime`, `file.tags`,
+/// `file.words`. `FROM #tag` includes nested tags and `FROM [[Note]]`
+/// selects the notes linking to it.
+#[tauri::command]
+pub fn run_query(db: State<Database>, query: String) -> Result<QueryResult, String> {
+    let parsed = parse_query(&query)?;
+    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+    let timezone = app_timezone(&conn);
+    let now = chrono::Utc::now().with_timezone(&timezone).naive_local();
+    let notes = load_query_notes(&conn, timezone).map_err(|e| e.to_string())?;
+    let source = match &parsed.source {
+        Some(source) => Some(resolve_query_source(&conn, source).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    drop(conn);
+
+    let mut matched: Vec<&QueryNote> = Vec::new();
+    for note in &notes {
+        if source.as_ref().is_some_and(|s| !s.matches(note)) {
+            continue;
+        }
+        if let Some(filter) = &parsed.filter {
+            if !eval_query_expr(filter, note, now)?.truthy() {
+                continue;
+            }
+        }
+        matched.push(note);
+    }
+
+    if !parsed.sort.is_empty() {
+        let mut keyed: Vec<(Vec<QValue>, &QueryNote)> = matched
+            .into_iter()
+            .map(|note| {
+                let keys = parsed
+                    .sort
+                    .iter()
+                    .map(|(expr, _)| eval_query_expr(expr, note, now))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((keys, note))
+            })
+            .collect::<Result<_, String>>()?;
+        keyed.sort_by(|a, b| {
+            for (i, (_, descending)) in parsed.sort.iter().enumerate() {
+                let ordering = a.0[i].sort_cmp(&b.0[i]);
+                let ordering = if *descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                };
+                if ordering != std::cmp::Ordering::Equal {
+                    return ordering;
+                }
+            }
+            std::cmp::Ordering::Equal
+        });
+        matched = keyed.into_iter().map(|(_, note)| note).collect();
+    }
+    if let Some(limit) = parsed.limit {
+        matched.truncate(limit);
+    }
+
+    let mut rows = Vec::with_capacity(matched.len());
+    for note in matched {
+        let values = parsed
+            .fields
+            .iter()
+            .map(|(expr, _)| eval_query_expr(expr, note, now).map(|v| v.to_json()))
+            .collect::<Result<Vec<_>, _>>()?;
+        rows.push(QueryRow {
+            note_id: note.id.clone(),
+            title: note.title.clone(),
+            emoji: note.emoji.clone(),
+            values,
+        });
+    }
+
+    Ok(QueryResult {
+        kind: parsed.kind.to_string(),
+        columns: parsed.fields.into_iter().map(|(_, name)| name).collect(),
+        without_id: parsed.without_id,
+        rows,
+    })
+}
+
+/// A live note with everything a query can read from it.
+struct QueryNote {
+    id: String,
+    title: String,
+    emoji: Option<String>,
+    folder: String,
+    /// Local time in the app timezone.
+    created_at: Option<chrono::NaiveDateTime>,
+    updated_at: Option<chrono::NaiveDateTime>,
+    word_count: i64,
+    is_favorite: bool,
+    is_pinned: bool,
+    tags: Vec<String>,
+    properties: Vec<(String, QValue)>,
+}
+
+fn load_query_notes(
+    conn: &rusqlite::Connection,
+    timezone: chrono_tz::Tz,
+) -> rusqlite::Result<Vec<QueryNote>> {
+    let mut folders: std::collections::HashMap<String, (String, Option<String>)> =
+        std::collections::HashMap::new();
+    {
+        let mut stmt =
+            conn.prepare("SELECT id, title, parent_id FROM notes WHERE is_folder = 1")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?;
+        folders.extend(rows.flatten());
+    }
+    let folder_path = |mut parent: Option<String>| {
+        let mut parts: Vec<&str> = Vec::new();
+        while let Some(id) = parent {
+            match folders.get(&id) {
+                // The depth check guards against a corrupted parent loop.
+                Some((title, next)) if parts.len() < folders.len() => {
+                    parts.push(title);
+                    parent = next.clone();
+                }
+                _ => break,
+            }
+        }
+        parts.reverse();
+        parts.join("/")
+    };
+
+    let mut notes: Vec<QueryNote> = Vec::new();
+    let mut index: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT id, title, emoji, parent_id, COALESCE(created_at, 0), COALESCE(updated_at, 0),
+                    COALESCE(word_count, 0), COALESCE(is_favorite, 0), COALESCE(is_pinned, 0)
+             FROM notes WHERE is_trashed = 0 AND is_folder = 0
+             ORDER BY title COLLATE NOCASE",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                QueryNote {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    emoji: row.get(2)?,
+                    folder: String::new(),
+                    created_at: local_datetime(timezone, row.get(4)?),
+                    updated_at: local_datetime(timezone, row.get(5)?),
+                    word_count: row.get(6)?,
+                    is_favorite: row.get::<_, i64>(7)? != 0,
+                    is_pinned: row.get::<_, i64>(8)? != 0,
+                    tags: vec![],
+                    properties: vec![],
+                },
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?;
+        for (mut note, parent_id) in rows.flatten() {
+            note.folder = folder_path(parent_id);
+            index.insert(note.id.clone(), notes.len());
+            notes.push(note);
+        }
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT nt.note_id, t.name FROM note_tags nt JOIN tags t ON t.id = nt.tag_id ORDER BY t.name",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    for (note_id, name) in rows.flatten() {
+        if let Some(&i) = index.get(&note_id) {
+            notes[i].tags.push(name);
+        }
+    }
+
+    let mut stmt = conn.prepare(
+        "SELECT p.note_id, p.key, s.type, p.value, l.title
+         FROM note_properties p
+         JOIN property_schemas s ON s.key = p.key
+         LEFT JOIN notes l ON s.type = 'note-link' AND l.id = JSON_EXTRACT(p.value, '$')
+         ORDER BY p.position",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, Option<String>>(4)?,
+        ))
+    })?;
+    for (note_id, key, property_type, raw, link_title) in rows.flatten() {
+        let Some(&i) = index.get(&note_id) else {
+            continue;
+        };
+        let value: serde_json::Value =
+            serde_json::from_str(&raw).unwrap_or(serde_json::Value::Null);
+        notes[i].properties.push((
+            key,
+            QValue::from_property(&property_type, value, link_title),
+        ));
+    }
+
+    Ok(notes)
+}
+
+/// A value during query evaluation.
+#[derive(Debug, Clone, PartialEq)]
+enum QValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    Date(chrono::NaiveDateTime),
+    List(Vec<QValue>),
+    Link { id: String, title: String },
+}
+
+impl QValue {
+    fn from_property(
+        property_type: &str,
+        value: serde_json::Value,
+        link_title: Option<String>,
+    ) -> QValue {
+        use serde_json::Value;
+        match (property_type, value) {
+            ("note-link", Value::String(id)) => match link_title {
+                Some(title) => QValue::Link { id, title },
+                None => QValue::Null,
+            },
+            ("date", Value::String(s)) => {
+                parse_query_date(&s).map_or(QValue::Text(s), QValue::Date)
+            }
+            (_, value) => QValue::from_json(value),
+        }
+    }
 
-    Ok(cards)
+    fn from_json(value: serde_json::Value) -> QValue {
+        use serde_json::Value;
+        match value {
+            Value::Null | Value::Object(_) => QValue::Null,
+            Value::Bool(b) => QValue::Bool(b),
+            Value::Number(n) => QValue::Number(n.as_f64().unwrap_or(0.0)),
+            Value::String(s) => QValue::Text(s),
+            Value::Array(items) => QValue::List(items.into_iter().map(QValue::from_json).collect()),
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            QValue::Null => serde_json::Value::Null,
+            QValue::Bool(b) => serde_json::Value::Bool(*b),
+            QValue::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => {
+                serde_json::Value::from(*n as i64)
+            }
+            QValue::Number(n) => serde_json::Value::from(*n),
+            QValue::Text(s) => serde_json::Value::String(s.clone()),
+            QValue::Date(d) if d.time() == chrono::NaiveTime::MIN => {
+                serde_json::Value::String(d.format("%Y-%m-%d").to_string())
+            }
+            QValue::Date(d) => serde_json::Value::String(d.format("%Y-%m-%dT%H:%M:%S").to_string()),
+            QValue::List(items) => {
+                serde_json::Value::Array(items.iter().map(QValue::to_json).collect())
+            }
+            QValue::Link { id, title } => serde_json::json!({ "noteId": id, "title": title }),
+        }
+    }
+
+    fn truthy(&self) -> bool {
+        match self {
+            QValue::Null => false,
+            QValue::Bool(b) => *b,
+            QValue::Number(n) => *n != 0.0,
+            QValue::Text(s) => !s.is_empty(),
+            QValue::List(items) => !items.is_empty(),
+            QValue::Date(_) | QValue::Link { .. } => true,
+        }
+    }
+
+    fn as_text(&self) -> String {
+        match self {
+            QValue::Null => String::new(),
+            QValue::Text(s) => s.clone(),
+            QValue::Link { title, .. } => title.clone(),
+            other => match other.to_json() {
+                serde_json::Value::String(s) => s,
+                json => json.to_string(),
+            },
+        }
+    }
+
+    /// Ordering between comparable values; `None` when either side is null
+    /// or the types do not mix. Text is compared with dates by parsing it.
+    fn compare(&self, other: &QValue) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (QValue::Number(a), QValue::Number(b)) => a.partial_cmp(b),
+            (QValue::Text(a), QValue::Text(b)) => Some(a.cmp(b)),
+            (QValue::Bool(a), QValue::Bool(b)) => Some(a.cmp(b)),
+            (QValue::Date(a), QValue::Date(b)) => Some(a.cmp(b)),
+            (QValue::Date(a), QValue::Text(b)) => parse_query_date(b).map(|b| a.cmp(&b)),
+            (QValue::Text(a), QValue::Date(b)) => parse_query_date(a).map(|a| a.cmp(b)),
+            (QValue::Link { id: a, .. }, QValue::Link { id: b, .. }) if a == b => {
+                Some(std::cmp::Ordering::Equal)
+            }
+            (QValue::Link { title: a, .. }, QValue::Link { title: b, .. }) => Some(a.cmp(b)),
+            (QValue::Link { title, .. }, QValue::Text(s)) => Some(title.as_str().cmp(s)),
+            (QValue::Text(s), QValue::Link { title, .. }) => Some(s.as_str().cmp(title)),
+            (QValue::List(a), QValue::List(b)) if a.len() == b.len() => {
+                for (x, y) in a.iter().zip(b) {
+                    match x.compare(y)? {
+                        std::cmp::Ordering::Equal => continue,
+                        ordering => return Some(ordering),
+                    }
+                }
+                Some(std::cmp::Ordering::Equal)
+            }
+            _ => None,
+        }
+    }
+
+    fn equals(&self, other: &QValue) -> bool {
+        match (self, other) {
+            (QValue::Null, QValue::Null) => true,
+            _ => self.compare(other) == Some(std::cmp::Ordering::Equal),
+        }
+    }
+
+    /// Total order for SORT: values are ranked by kind first (nulls last)
+    /// and only compared within a kind, so mixed columns still sort
+    /// consistently. Links sort with text, by title.
+    fn sort_cmp(&self, other: &QValue) -> std::cmp::Ordering {
+        let rank = |v: &QValue| match v {
+            QValue::Bool(_) => 0,
+            QValue::Number(_) => 1,
+            QValue::Date(_) => 2,
+            QValue::Text(_) | QValue::Link { .. } => 3,
+            QValue::List(_) => 4,
+            QValue::Null => 5,
+        };
+        match (self, other) {
+            (QValue::Bool(a), QValue::Bool(b)) => a.cmp(b),
+            (QValue::Number(a), QValue::Number(b)) => a.total_cmp(b),
+            (QValue::Date(a), QValue::Date(b)) => a.cmp(b),
+            (
+                QValue::Text(a) | QValue::Link { title: a, .. },
+                QValue::Text(b) | QValue::Link { title: b, .. },
+            ) => a.cmp(b),
+            (QValue::List(a), QValue::List(b)) => a
+                .iter()
+                .zip(b)
+                .map(|(x, y)| x.sort_cmp(y))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| a.len().cmp(&b.len())),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
 }
 
-#[tauri::command]
-pub fn move_note_to_tag(
-    db: State<Database>,
-    note_id: String,
-    from_tag: String,
-    to_tag: String,
-) -> Result<(), String> {
-    let conn = db.conn.lock().map_err(|e| e.to_string())?;
+/// `YYYY-MM-DD`, optionally with a time, as used by date properties.
+fn parse_query_date(s: &str) -> Option<chrono::NaiveDateTime> {
+    let s = s.trim();
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Some(date.and_time(chrono::NaiveTime::MIN));
+    }
+    [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| chrono::NaiveDateTime::parse_from_str(s, format).ok())
+}
 
-    // Remove old tag
-    let from_tag_id: Option<String> = conn
-        .query_row("SELECT id FROM tags WHERE name = ?1", params![from_tag], |row| row.get(0))
-        .ok();
-    if let Some(tid) = from_tag_id {
-        conn.execute(
-            "DELETE FROM note_tags WHERE note_id = ?1 AND tag_id = ?2",
-            params![note_id, tid],
-        )
-        .map_err(|e| e.to_string())?;
+#[derive(Debug, Clone, PartialEq)]
+enum QueryToken {
+    Ident(String),
+    Str(String),
+    Number(f64),
+    Tag(String),
+    Link(String),
+    Op(&'static str),
+    Comma,
+    LParen,
+    RParen,
+}
+
+#[derive(Debug)]
+enum QueryExpr {
+    Literal(QValue),
+    Field(String),
+    Call(String, Vec<QueryExpr>),
+    Not(Box<QueryExpr>),
+    Neg(Box<QueryExpr>),
+    Binary(&'static str, Box<QueryExpr>, Box<QueryExpr>),
+}
+
+#[derive(Debug)]
+enum QuerySource {
+    Tag(String),
+    Folder(String),
+    LinksTo(String),
+    Not(Box<QuerySource>),
+    And(Box<QuerySource>, Box<QuerySource>),
+    Or(Box<QuerySource>, Box<QuerySource>),
+}
+
+struct ParsedQuery {
+    kind: &'static str,
+    without_id: bool,
+    fields: Vec<(QueryExpr, String)>,
+    source: Option<QuerySource>,
+    filter: Option<QueryExpr>,
+    sort: Vec<(QueryExpr, bool)>,
+    limit: Option<usize>,
+}
+
+fn tokenize_query(query: &str) -> Result<Vec<(QueryToken, usize, usize)>, String> {
+    let bytes = query.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = query[i..].chars().next().unwrap_or(' ');
+        let start = i;
+        if c.is_whitespace() {
+            i += c.len_utf8();
+            continue;
+        }
+        let token = if c == '"' || c == '\'' {
+            let mut value = String::new();
+            i += 1;
+            loop {
+                let Some(ch) = query[i..].chars().next() else {
+                    return Err(format!("Query error at {}: unterminated string", start + 1));
+                };
+                i += ch.len_utf8();
+                match ch {
+                    '\\' => {
+                        if let Some(escaped) = query[i..].chars().next() {
+                            value.push(escaped);
+                            i += escaped.len_utf8();
+                        }
+                    }
+                    ch if ch == c => break,
+                    ch => value.push(ch),
+                }
+            }
+            QueryToken::Str(value)
+        } else if query[i..].starts_with("[[") {
+            let Some(end) = query[i + 2..].find("]]") else {
+                return Err(format!(
+                    "Query error at {}: unterminated [[link]]",
+                    start + 1
+                ));
+            };
+            i += end + 4;
+            QueryToken::Link(query[start + 2..start + 2 + end].trim().to_string())
+        } else if c == '#' {
+            i += 1;
+            while let Some(ch) = query[i..].chars().next() {
+                if !(ch.is_alphanumeric() || matches!(ch, '_' | '-' | '/')) {
+                    break;
+                }
+                i += ch.len_utf8();
+            }
+            QueryToken::Tag(query[start + 1..i].trim_end_matches('/').to_lowercase())
+        } else if c.is_ascii_digit() {
+            while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                i += 1;
+            }
+            let number = query[start..i]
+                .parse()
+                .map_err(|_| format!("Query error at {}: invalid number", start + 1))?;
+            QueryToken::Number(number)
+        } else if c.is_alphabetic() || c == '_' {
+            while let Some(ch) = query[i..].chars().next() {
+                if !(ch.is_alphanumeric() || matches!(ch, '_' | '.')) {
+                    break;
+                }
+                i += ch.len_utf8();
+            }
+            QueryToken::Ident(query[start..i].to_string())
+        } else {
+            let two = query.get(i..i + 2).unwrap_or("");
+            let (op, len) = match two {
+                "!=" => ("!=", 2),
+                "<=" => ("<=", 2),
+                ">=" => (">=", 2),
+                "==" => ("=", 2),
+                "&&" => ("and", 2),
+                "||" => ("or", 2),
+                _ => match c {
+                    '=' => ("=", 1),
+                    '<' => ("<", 1),
+                    '>' => (">", 1),
+                    '+' => ("+", 1),
+                    '-' => ("-", 1),
+                    '*' => ("*", 1),
+                    '/' => ("/", 1),
+                    '!' => ("!", 1),
+                    ',' | '(' | ')' => ("", 1),
+                    _ => return Err(format!("Query error at {}: unexpected '{}'", start + 1, c)),
+                },
+            };
+            i += len;
+            match c {
+                ',' => QueryToken::Comma,
+                '(' => QueryToken::LParen,
+                ')' => QueryToken::RParen,
+                _ => QueryToken::Op(op),
+            }
+        };
+        tokens.push((token, start, i));
     }
+    Ok(tokens)
+}
 
-    // Add new tag
-    let new_tag_id = Uuid::new_v4().to_string();
-    conn.execute(
-        "INSERT OR IGNORE INTO tags (id, name) VALUES (?1, ?2)",
-        params![new_tag_id, to_tag],
-    )
-    .map_err(|e| e.to_string())?;
+fn parse_query(query: &str) -> Result<ParsedQuery, String> {
+    let mut parser = QueryParser {
+        source: query,
+        tokens: tokenize_query(query)?,
+        pos: 0,
+    };
+    parser.query()
+}
 
-    let actual_id: String = conn
-        .query_row("SELECT id FROM tags WHERE name = ?1", params![to_tag], |row| row.get(0))
-        .map_err(|e| e.to_string())?;
+struct QueryParser<'a> {
+    source: &'a str,
+    tokens: Vec<(QueryToken, usize, usize)>,
+    pos: usize,
+}
 
-    conn.execute(
-        "INSERT OR IGNORE INTO note_tags (note_id, tag_id, source) VALUES (?1, ?2, 'manual')",
-        params![note_id, actual_id],
-    )
-    .map_err(|e| e.to_string())?;
+impl QueryParser<'_> {
+    const CLAUSES: [&'static str; 4] = ["from", "where", "sort", "limit"];
 
-    Ok(())
+    fn peek(&self) -> Option<&QueryToken> {
+        self.tokens.get(self.pos).map(|t| &t.0)
+    }
+
+    fn advance(&mut self) -> Option<QueryToken> {
+        let token = self.tokens.get(self.pos).map(|t| t.0.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn is_keyword(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(QueryToken::Ident(w)) if w.eq_ignore_ascii_case(word))
+    }
+
+    fn eat_keyword(&mut self, word: &str) -> bool {
+        let found = self.is_keyword(word);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn eat(&mut self, token: &QueryToken) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn at_clause(&self) -> bool {
+        self.peek().is_none() || Self::CLAUSES.iter().any(|c| self.is_keyword(c))
+    }
+
+    fn error(&self, expected: &str) -> String {
+        let at = self.tokens.get(self.pos).map_or(self.source.len(), |t| t.1);
+        format!("Query error at {}: expected {}", at + 1, expected)
+    }
+
+    fn query(&mut self) -> Result<ParsedQuery, String> {
+        let mut query = ParsedQuery {
+            kind: "table",
+            without_id: false,
+            fields: vec![],
+            source: None,
+            filter: None,
+            sort: vec![],
+            limit: None,
+        };
+        if self.eat_keyword("table") {
+            if self.is_keyword("without") {
+                self.pos += 1;
+                if !self.eat_keyword("id") {
+                    return Err(self.error("ID after WITHOUT"));
+                }
+                query.without_id = true;
+            }
+            while !self.at_clause() {
+                query.fields.push(self.field()?);
+                if !self.eat(&QueryToken::Comma) {
+                    break;
+                }
+            }
+        } else if self.eat_keyword("list") {
+            query.kind = "list";
+            if !self.at_clause() {
+                query.fields.push(self.field()?);
+            }
+        } else {
+            return Err(self.error("TABLE or LIST"));
+        }
+
+        if self.eat_keyword("from") {
+            query.source = Some(self.source_or()?);
+        }
+        if self.eat_keyword("where") {
+            query.filter = Some(self.expr()?);
+        }
+        if self.eat_keyword("sort") {
+            loop {
+                let expr = self.expr()?;
+                let descending = if self.eat_keyword("desc") {
+                    true
+                } else {
+                    self.eat_keyword("asc");
+                    false
+                };
+                query.sort.push((expr, descending));
+                if !self.eat(&QueryToken::Comma) {
+                    break;
+                }
+            }
+        }
+        if self.eat_keyword("limit") {
+            match self.advance() {
+                Some(QueryToken::Number(n)) if n >= 0.0 && n.fract() == 0.0 => {
+                    query.limit = Some(n as usize)
+                }
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("a whole number after LIMIT"));
+                }
+            }
+        }
+        if self.peek().is_some() {
+            return Err(self.error("end of query"));
+        }
+        Ok(query)
+    }
+
+    fn field(&mut self) -> Result<(QueryExpr, String), String> {
+        let start = self.tokens.get(self.pos).map_or(self.source.len(), |t| t.1);
+        let expr = self.expr()?;
+        let end = self.tokens.get(self.pos - 1).map_or(start, |t| t.2);
+        let name = if self.eat_keyword("as") {
+            match self.advance() {
+                Some(QueryToken::Str(name)) | Some(QueryToken::Ident(name)) => name,
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("a column name after AS"));
+                }
+            }
+        } else {
+            self.source[start..end].to_string()
+        };
+        Ok((expr, name))
+    }
+
+    fn source_or(&mut self) -> Result<QuerySource, String> {
+        let mut source = self.source_and()?;
+        while self.eat_keyword("or") || self.eat(&QueryToken::Op("or")) {
+            source = QuerySource::Or(Box::new(source), Box::new(self.source_and()?));
+        }
+        Ok(source)
+    }
+
+    fn source_and(&mut self) -> Result<QuerySource, String> {
+        let mut source = self.source_atom()?;
+        while self.eat_keyword("and") || self.eat(&QueryToken::Op("and")) {
+            source = QuerySource::And(Box::new(source), Box::new(self.source_atom()?));
+        }
+        Ok(source)
+    }
+
+    fn source_atom(&mut self) -> Result<QuerySource, String> {
+        if self.eat_keyword("not")
+            || self.eat(&QueryToken::Op("-"))
+            || self.eat(&QueryToken::Op("!"))
+        {
+            return Ok(QuerySource::Not(Box::new(self.source_atom()?)));
+        }
+        match self.advance() {
+            Some(QueryToken::Tag(tag)) if !tag.is_empty() => Ok(QuerySource::Tag(tag)),
+            Some(QueryToken::Str(folder)) => {
+                Ok(QuerySource::Folder(folder.trim_matches('/').to_string()))
+            }
+            Some(QueryToken::Link(title)) => Ok(QuerySource::LinksTo(title)),
+            Some(QueryToken::LParen) => {
+                let source = self.source_or()?;
+                if !self.eat(&QueryToken::RParen) {
+                    return Err(self.error("')'"));
+                }
+                Ok(source)
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.error("#tag, \"folder\" or [[note]]"))
+            }
+        }
+    }
+
+    fn expr(&mut self) -> Result<QueryExpr, String> {
+        let mut expr = self.and_expr()?;
+        while self.eat_keyword("or") || self.eat(&QueryToken::Op("or")) {
+            expr = QueryExpr::Binary("or", Box::new(expr), Box::new(self.and_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn and_expr(&mut self) -> Result<QueryExpr, String> {
+        let mut expr = self.not_expr()?;
+        while self.eat_keyword("and") || self.eat(&QueryToken::Op("and")) {
+            expr = QueryExpr::Binary("and", Box::new(expr), Box::new(self.not_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn not_expr(&mut self) -> Result<QueryExpr, String> {
+        if self.eat_keyword("not") || self.eat(&QueryToken::Op("!")) {
+            return Ok(QueryExpr::Not(Box::new(self.not_expr()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<QueryExpr, String> {
+        let left = self.additive()?;
+        for op in ["=", "!=", "<", "<=", ">", ">="] {
+            if self.eat(&QueryToken::Op(op)) {
+                return Ok(QueryExpr::Binary(
+                    op,
+                    Box::new(left),
+                    Box::new(self.additive()?),
+                ));
+            }
+        }
+        Ok(left)
+    }
+
+    fn additive(&mut self) -> Result<QueryExpr, String> {
+        let mut expr = self.multiplicative()?;
+        loop {
+            let op = if self.eat(&QueryToken::Op("+")) {
+                "+"
+            } else if self.eat(&QueryToken::Op("-")) {
+                "-"
+            } else {
+                return Ok(expr);
+            };
+            expr = QueryExpr::Binary(op, Box::new(expr), Box::new(self.multiplicative()?));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<QueryExpr, String> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = if self.eat(&QueryToken::Op("*")) {
+                "*"
+            } else if self.eat(&QueryToken::Op("/")) {
+                "/"
+            } else {
+                return Ok(expr);
+            };
+            expr = QueryExpr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<QueryExpr, String> {
+        if self.eat(&QueryToken::Op("-")) {
+            return Ok(QueryExpr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<QueryExpr, String> {
+        match self.advance() {
+            Some(QueryToken::Number(n)) => Ok(QueryExpr::Literal(QValue::Number(n))),
+            Some(QueryToken::Str(s)) => Ok(QueryExpr::Literal(QValue::Text(s))),
+            Some(QueryToken::Tag(tag)) => Ok(QueryExpr::Literal(QValue::Text(format!("#{}", tag)))),
+            Some(QueryToken::Link(title)) => Ok(QueryExpr::Call(
+                "link".to_string(),
+                vec![QueryExpr::Literal(QValue::Text(title))],
+            )),
+            Some(QueryToken::LParen) => {
+                let expr = self.expr()?;
+                if !self.eat(&QueryToken::RParen) {
+                    return Err(self.error("')'"));
+                }
+                Ok(expr)
+            }
+            Some(QueryToken::Ident(name)) => {
+                if self.eat(&QueryToken::LParen) {
+                    let mut args = Vec::new();
+                    if !self.eat(&QueryToken::RParen) {
+                        loop {
+                            args.push(self.expr()?);
+                            if self.eat(&QueryToken::RParen) {
+                                break;
+                            }
+                            if !self.eat(&QueryToken::Comma) {
+                                return Err(self.error("',' or ')'"));
+                            }
+                        }
+                    }
+                    return Ok(QueryExpr::Call(name.to_lowercase(), args));
+                }
+                Ok(match name.to_ascii_lowercase().as_str() {
+                    "true" => QueryExpr::Literal(QValue::Bool(true)),
+                    "false" => QueryExpr::Literal(QValue::Bool(false)),
+                    "null" => QueryExpr::Literal(QValue::Null),
+                    _ => QueryExpr::Field(name),
+                })
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.error("a value"))
+            }
+        }
+    }
+}
+
+/// A `FROM` clause with its links and folders looked up.
+enum ResolvedSource {
+    Tag(String),
+    Folder(String),
+    Notes(std::collections::HashSet<String>),
+    Not(Box<ResolvedSource>),
+    And(Box<ResolvedSource>, Box<ResolvedSource>),
+    Or(Box<ResolvedSource>, Box<ResolvedSource>),
+}
+
+impl ResolvedSource {
+    fn matches(&self, note: &QueryNote) -> bool {
+        match self {
+            ResolvedSource::Tag(tag) => note
+                .tags
+                .iter()
+                .any(|t| tag_in_subtree(&t.to_lowercase(), tag)),
+            ResolvedSource::Folder(folder) => {
+                let path = note.folder.to_lowercase();
+                tag_in_subtree(&path, folder)
+            }
+            ResolvedSource::Notes(ids) => ids.contains(&note.id),
+            ResolvedSource::Not(inner) => !inner.matches(note),
+            ResolvedSource::And(a, b) => a.matches(note) && b.matches(note),
+            ResolvedSource::Or(a, b) => a.matches(note) || b.matches(note),
+        }
+    }
+}
+
+fn resolve_query_source(
+    conn: &rusqlite::Connection,
+    source: &QuerySource,
+) -> rusqlite::Result<ResolvedSource> {
+    Ok(match source {
+        QuerySource::Tag(tag) => ResolvedSource::Tag(tag.clone()),
+        QuerySource::Folder(folder) => ResolvedSource::Folder(folder.to_lowercase()),
+        QuerySource::LinksTo(title) => {
+            let mut ids = std::collections::HashSet::new();
+            if let Some(target) = resolve_link_target(conn, link_title_part(title), "")? {
+                let mut stmt =
+                    conn.prepare("SELECT source_note_id FROM wikilinks WHERE target_note_id = ?1")?;
+                let rows = stmt.query_map(params![target], |row| row.get(0))?;
+                ids.extend(rows.flatten());
+            }
+            ResolvedSource::Notes(ids)
+        }
+        QuerySource::Not(inner) => {
+            ResolvedSource::Not(Box::new(resolve_query_source(conn, inner)?))
+        }
+        QuerySource::And(a, b) => ResolvedSource::And(
+            Box::new(resolve_query_source(conn, a)?),
+            Box::new(resolve_query_source(conn, b)?),
+        ),
+        QuerySource::Or(a, b) => ResolvedSource::Or(
+            Box::new(resolve_query_source(conn, a)?),
+            Box::new(resolve_query_source(conn, b)?),
+        ),
+    })
+}
+
+/// Look up a field: `file.*` built-ins, then the note's properties
+/// (case-insensitive), then bare aliases of the built-ins such as `title`
+/// or `tags`. Unknown fields are null.
+fn query_field(note: &QueryNote, name: &str) -> QValue {
+    let lower = name.to_lowercase();
+    let builtin = |field: &str| -> Option<QValue> {
+        Some(match field {
+            "name" | "title" => QValue::Text(note.title.clone()),
+            "link" => QValue::Link {
+                id: note.id.clone(),
+                title: note.title.clone(),
+            },
+            "id" => QValue::Text(note.id.clone()),
+            "folder" => QValue::Text(note.folder.clone()),
+            "path" if note.folder.is_empty() => QValue::Text(note.title.clone()),
+            "path" => QValue::Text(format!("{}/{}", note.folder, note.title)),
+            "ctime" | "created" | "cday" => note.created_at.map_or(QValue::Null, QValue::Date),
+            "mtime" | "updated" | "mday" => note.updated_at.map_or(QValue::Null, QValue::Date),
+            "tags" => QValue::List(
+                note.tags
+                    .iter()
+                    .map(|t| QValue::Text(format!("#{}", t)))
+                    .collect(),
+            ),
+            "words" => QValue::Number(note.word_count as f64),
+            "emoji" => note.emoji.clone().map_or(QValue::Null, QValue::Text),
+            "favorite" => QValue::Bool(note.is_favorite),
+            "pinned" => QValue::Bool(note.is_pinned),
+            _ => return None,
+        })
+    };
+
+    if let Some(field) = lower.strip_prefix("file.") {
+        return builtin(field).unwrap_or(QValue::Null);
+    }
+    if let Some((_, value)) = note
+        .properties
+        .iter()
+        .find(|(key, _)| key.to_lowercase() == lower)
+    {
+        return value.clone();
+    }
+    builtin(&lower).unwrap_or(QValue::Null)
+}
+
+/// `now` is the local time in the app timezone, for `date(today)` and co.
+fn eval_query_expr(
+    expr: &QueryExpr,
+    note: &QueryNote,
+    now: chrono::NaiveDateTime,
+) -> Result<QValue, String> {
+    Ok(match expr {
+        QueryExpr::Literal(value) => value.clone(),
+        QueryExpr::Field(name) => query_field(note, name),
+        QueryExpr::Not(inner) => QValue::Bool(!eval_query_expr(inner, note, now)?.truthy()),
+        QueryExpr::Neg(inner) => match eval_query_expr(inner, note, now)? {
+            QValue::Number(n) => QValue::Number(-n),
+            _ => QValue::Null,
+        },
+        QueryExpr::Binary("and", a, b) => QValue::Bool(
+            eval_query_expr(a, note, now)?.truthy() && eval_query_expr(b, note, now)?.truthy(),
+        ),
+        QueryExpr::Binary("or", a, b) => QValue::Bool(
+            eval_query_expr(a, note, now)?.truthy() || eval_query_expr(b, note, now)?.truthy(),
+        ),
+        QueryExpr::Binary(op, a, b) => {
+            let a = eval_query_expr(a, note, now)?;
+            let b = eval_query_expr(b, note, now)?;
+            query_binary(op, a, b)
+        }
+        QueryExpr::Call(name, args) => {
+            let args = args
+                .iter()
+                .map(|arg| match (name.as_str(), arg) {
+                    // `date(today)` reads like a field but names a day.
+                    ("date", QueryExpr::Field(word))
+                        if matches!(
+                            word.to_ascii_lowercase().as_str(),
+                            "today" | "tomorrow" | "yesterday" | "now"
+                        ) =>
+                    {
+                        Ok(QValue::Text(word.clone()))
+                    }
+                    _ => eval_query_expr(arg, note, now),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            query_function(name, args, now)?
+        }
+    })
+}
+
+/// Comparisons involving null are false, except `!=`. Arithmetic on dates
+/// works in days: `date + 7`, `due - date(today)`.
+fn query_binary(op: &str, a: QValue, b: QValue) -> QValue {
+    use std::cmp::Ordering;
+
+    let ordering = a.compare(&b);
+    match op {
+        "=" => QValue::Bool(a.equals(&b)),
+        "!=" => QValue::Bool(!a.equals(&b)),
+        "<" => QValue::Bool(ordering == Some(Ordering::Less)),
+        "<=" => QValue::Bool(matches!(ordering, Some(Ordering::Less | Ordering::Equal))),
+        ">" => QValue::Bool(ordering == Some(Ordering::Greater)),
+        ">=" => QValue::Bool(matches!(
+            ordering,
+            Some(Ordering::Greater | Ordering::Equal)
+        )),
+        _ => match (op, a, b) {
+            ("+", QValue::Number(x), QValue::Number(y)) => QValue::Number(x + y),
+            ("-", QValue::Number(x), QValue::Number(y)) => QValue::Number(x - y),
+            ("*", QValue::Number(x), QValue::Number(y)) => QValue::Number(x * y),
+            ("/", QValue::Number(x), QValue::Number(y)) if y != 0.0 => QValue::Number(x / y),
+            ("+", QValue::Date(d), QValue::Number(days))
+            | ("+", QValue::Number(days), QValue::Date(d)) => query_days_delta(days)
+                .and_then(|delta| d.checked_add_signed(delta))
+                .map_or(QValue::Null, QValue::Date),
+            ("-", QValue::Date(d), QValue::Number(days)) => query_days_delta(days)
+                .and_then(|delta| d.checked_sub_signed(delta))
+                .map_or(QValue::Null, QValue::Date),
+            ("-", QValue::Date(x), QValue::Date(y)) => {
+                QValue::Number((x - y).num_seconds() as f64 / 86_400.0)
+            }
+            ("+", QValue::Text(x), y) => QValue::Text(x + &y.as_text()),
+            ("+", QValue::List(mut x), QValue::List(y)) => {
+                x.extend(y);
+                QValue::List(x)
+            }
+            _ => QValue::Null,
+        },
+    }
+}
+
+/// `days` as a duration, or `None` if it is out of range.
+fn query_days_delta(days: f64) -> Option<chrono::TimeDelta> {
+    let seconds = days * 86_400.0;
+    if !seconds.is_finite() || seconds.abs() >= i64::MAX as f64 {
+        return None;
+    }
+    chrono::TimeDelta::try_seconds(seconds as i64)
+}
+
+fn query_function(
+    name: &str,
+    mut args: Vec<QValue>,
+    now: chrono::NaiveDateTime,
+) -> Result<QValue, String> {
+    let arity = |n: usize, args: &[QValue]| {
+        if args.len() == n {
+            Ok(())
+        } else {
+            Err(format!(
+                "{}() takes {} argument{}",
+                name,
+                n,
+                if n == 1 { "" } else { "s" }
+            ))
+        }
+    };
+    Ok(match name {
+        "contains" => {
+            arity(2, &args)?;
+            let needle = args.pop().unwrap_or(QValue::Null);
+            match args.pop().unwrap_or(QValue::Null) {
+                QValue::List(items) => {
+                    QValue::Bool(items.iter().any(|item| match (item, &needle) {
+                        // `contains(file.tags, "#project")` includes nested tags.
+                        (QValue::Text(t), QValue::Text(n)) if n.starts_with('#') => {
+                            tag_in_subtree(&t.to_lowercase(), &n.to_lowercase())
+                        }
+                        _ => item.equals(&needle),
+                    }))
+                }
+                QValue::Text(text) => QValue::Bool(text.contains(&needle.as_text())),
+                QValue::Link { title, .. } => QValue::Bool(title.contains(&needle.as_text())),
+                _ => QValue::Bool(false),
+            }
+        }
+        "length" => {
+            arity(1, &args)?;
+            match &args[0] {
+                QValue::List(items) => QValue::Number(items.len() as f64),
+                QValue::Null => QValue::Number(0.0),
+                other => QValue::Number(other.as_text().chars().count() as f64),
+            }
+        }
+        "lower" | "upper" => {
+            arity(1, &args)?;
+            match &args[0] {
+                QValue::Null => QValue::Null,
+                value if name == "lower" => QValue::Text(value.as_text().to_lowercase()),
+                value => QValue::Text(value.as_text().to_uppercase()),
+            }
+        }
+        "default" => {
+            arity(2, &args)?;
+            let fallback = args.pop().unwrap_or(QValue::Null);
+            match args.pop().unwrap_or(QValue::Null) {
+                QValue::Null => fallback,
+                value => value,
+            }
+        }
+        "round" => {
+            let digits = match args.len() {
+                1 => 0.0,
+                2 => match args[1] {
+                    QValue::Number(d) => d,
+                    _ => 0.0,
+                },
+                _ => return Err("round() takes 1 or 2 arguments".to_string()),
+            };
+            match args[0] {
+                QValue::Number(n) => {
+                    let scale = 10f64.powi(digits as i32);
+                    QValue::Number((n * scale).round() / scale)
+                }
+                _ => QValue::Null,
+            }
+        }
+        "date" => {
+            arity(1, &args)?;
+            match &args[0] {
+                QValue::Date(d) => QValue::Date(*d),
+                QValue::Text(s) | QValue::Link { title: s, .. } => {
+                    let today = now.date().and_time(chrono::NaiveTime::MIN);
+                    match s.trim().to_ascii_lowercase().as_str() {
+                        "today" => QValue::Date(today),
+                        "tomorrow" => QValue::Date(today + chrono::Duration::days(1)),
+                        "yesterday" => QValue::Date(today - chrono::Duration::days(1)),
+                        "now" => QValue::Date(now),
+                        _ => parse_query_date(s).map_or(QValue::Null, QValue::Date),
+                    }
+                }
+                _ => QValue::Null,
+            }
+        }
+        // `[[Title]]` in an expression; compares equal to note-link values
+        // by title.
+        "link" => {
+            arity(1, &args)?;
+            QValue::Text(args[0].as_text())
+        }
+        _ => return Err(format!("Unknown function {}()", name)),
+    })
 }
 
 // ─── Export Command ──────────────────────────────────────
@@ -1696,7 +9588,210 @@ pub fn export_note_markdown(db: State<Database>, note_id: String) -> Result<Stri
         )
         .map_err(|e| e.to_string())?;
 
-    // Simple markdown export: title as H1 + plain text content
-    let md = format!("# {}\n\n{}", title, plain_text);
+    // Simple markdown export: properties as frontmatter, title as H1 + plain
+    // text content
+    let frontmatter = note_frontmatter(&conn, &note_id)?;
+    let md = format!("{}# {}\n\n{}", frontmatter, title, plain_text);
     Ok(md)
 }
+
+#[cfg(test)]
+mod query_tests {
+    use super::*;
+
+    fn note(title: &str, properties: Vec<(&str, QValue)>) -> QueryNote {
+        QueryNote {
+            id: format!("id-{}", title),
+            title: title.to_string(),
+            emoji: None,
+            folder: "Work/Projects".to_string(),
+            created_at: parse_query_date("2026-01-10T09:30"),
+            updated_at: parse_query_date("2026-02-01"),
+            word_count: 120,
+            is_favorite: true,
+            is_pinned: false,
+            tags: vec!["work/q4".to_string()],
+            properties: properties
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        }
+    }
+
+    fn now() -> chrono::NaiveDateTime {
+        parse_query_date("2026-03-15T12:00").unwrap()
+    }
+
+    fn eval(expr: &str, note: &QueryNote) -> QValue {
+        let query = parse_query(&format!("TABLE {}", expr)).unwrap();
+        eval_query_expr(&query.fields[0].0, note, now()).unwrap()
+    }
+
+    fn date(s: &str) -> QValue {
+        QValue::Date(parse_query_date(s).unwrap())
+    }
+
+    #[test]
+    fn tokenizes_strings_links_tags_and_operators() {
+        let tokens: Vec<QueryToken> =
+            tokenize_query(r#"a.b != "x\"y" [[ Foo ]] #Work/Q4 >= 2.5 && (c)"#)
+                .unwrap()
+                .into_iter()
+                .map(|(token, _, _)| token)
+                .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                QueryToken::Ident("a.b".into()),
+                QueryToken::Op("!="),
+                QueryToken::Str("x\"y".into()),
+                QueryToken::Link("Foo".into()),
+                QueryToken::Tag("work/q4".into()),
+                QueryToken::Op(">="),
+                QueryToken::Number(2.5),
+                QueryToken::Op("and"),
+                QueryToken::LParen,
+                QueryToken::Ident("c".into()),
+                QueryToken::RParen,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenizer_reports_positions() {
+        assert_eq!(
+            tokenize_query("a = 'open").unwrap_err(),
+            "Query error at 5: unterminated string"
+        );
+        assert_eq!(
+            tokenize_query("a ; b").unwrap_err(),
+            "Query error at 3: unexpected ';'"
+        );
+    }
+
+    #[test]
+    fn parses_clauses() {
+        let query = parse_query(
+            "TABLE WITHOUT ID file.name AS Name, due FROM #work AND -\"Archive\" \
+             WHERE due < date(today) SORT due DESC, file.name LIMIT 5",
+        )
+        .unwrap();
+        assert_eq!(query.kind, "table");
+        assert!(query.without_id);
+        let names: Vec<&str> = query.fields.iter().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(names, ["Name", "due"]);
+        assert!(matches!(query.source, Some(QuerySource::And(..))));
+        assert!(query.filter.is_some());
+        assert_eq!(query.sort.len(), 2);
+        assert!(query.sort[0].1 && !query.sort[1].1);
+        assert_eq!(query.limit, Some(5));
+
+        let list = parse_query("LIST").unwrap();
+        assert_eq!(list.kind, "list");
+        assert!(list.fields.is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_queries() {
+        assert!(parse_query("SELECT *").is_err());
+        assert!(parse_query("TABLE a WHERE").is_err());
+        assert!(parse_query("TABLE a LIMIT -1").is_err());
+        assert!(parse_query("TABLE a LIMIT 2 extra").is_err());
+    }
+
+    #[test]
+    fn evaluates_fields_and_operators() {
+        let n = note(
+            "Plan",
+            vec![
+                ("due", date("2026-03-01")),
+                ("status", QValue::Text("open".into())),
+            ],
+        );
+        assert_eq!(eval("file.name", &n), QValue::Text("Plan".into()));
+        assert_eq!(
+            eval("file.folder", &n),
+            QValue::Text("Work/Projects".into())
+        );
+        assert_eq!(eval("status = \"open\"", &n), QValue::Bool(true));
+        assert_eq!(eval("file.words * 2 + 1", &n), QValue::Number(241.0));
+        assert_eq!(eval("due < date(today)", &n), QValue::Bool(true));
+        assert_eq!(eval("due + 7", &n), date("2026-03-08"));
+        assert_eq!(eval("date(today) - due", &n), QValue::Number(14.0));
+        assert_eq!(eval("due = \"2026-03-01\"", &n), QValue::Bool(true));
+        assert_eq!(eval("missing", &n), QValue::Null);
+        assert_eq!(eval("missing != 1", &n), QValue::Bool(true));
+        assert_eq!(
+            eval("!file.pinned && file.favorite", &n),
+            QValue::Bool(true)
+        );
+    }
+
+    #[test]
+    fn evaluates_functions() {
+        let n = note("Plan", vec![]);
+        assert_eq!(eval("lower(\"AbC\")", &n), QValue::Text("abc".into()));
+        assert_eq!(eval("length(file.tags)", &n), QValue::Number(1.0));
+        assert_eq!(
+            eval("contains(file.tags, \"#work/q4\")", &n),
+            QValue::Bool(true)
+        );
+        assert_eq!(
+            eval("default(missing, file.name)", &n),
+            QValue::Text("Plan".into())
+        );
+        assert_eq!(eval("round(2.567, 1)", &n), QValue::Number(2.6));
+        assert_eq!(eval("date(tomorrow)", &n), date("2026-03-16"));
+        assert!(parse_query("TABLE round()")
+            .ok()
+            .and_then(|q| eval_query_expr(&q.fields[0].0, &n, now()).err())
+            .is_some());
+    }
+
+    #[test]
+    fn out_of_range_date_arithmetic_is_null() {
+        let n = note("Plan", vec![]);
+        assert_eq!(eval("file.ctime + 1000000000000", &n), QValue::Null);
+        assert_eq!(eval("file.ctime - 1000000000000", &n), QValue::Null);
+        assert_eq!(
+            eval("file.ctime + 1000000000000 > date(today)", &n),
+            QValue::Bool(false)
+        );
+    }
+
+    #[test]
+    fn sort_order_is_total_across_kinds() {
+        let values = vec![
+            QValue::Null,
+            QValue::Text("2026-01-05".into()),
+            date("2026-01-01"),
+            QValue::Text("zebra".into()),
+            QValue::Number(3.0),
+            date("2026-02-01"),
+            QValue::Text("2025-12-31".into()),
+            QValue::Link {
+                id: "x".into(),
+                title: "Apple".into(),
+            },
+            QValue::Bool(true),
+            QValue::List(vec![QValue::Number(1.0)]),
+            QValue::Number(f64::NAN),
+        ];
+        for a in &values {
+            assert_eq!(a.sort_cmp(a), std::cmp::Ordering::Equal);
+            for b in &values {
+                assert_eq!(a.sort_cmp(b), b.sort_cmp(a).reverse());
+                for c in &values {
+                    if a.sort_cmp(b).is_le() && b.sort_cmp(c).is_le() {
+                        assert!(a.sort_cmp(c).is_le(), "{:?} {:?} {:?}", a, b, c);
+                    }
+                }
+            }
+        }
+
+        let mut sorted = values.clone();
+        sorted.sort_by(|a, b| a.sort_cmp(b));
+        assert_eq!(sorted.first(), Some(&QValue::Bool(true)));
+        assert_eq!(sorted.last(), Some(&QValue::Null));
+    }
+}
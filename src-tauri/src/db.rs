@@ -1,4 +1,5 @@
-use rusqlite::{Connection, Result};
+use crate::indexing::{backfill_note_anchors, backfill_note_tasks, backfill_tag_hierarchy};
+use rusqlite::{params, Connection, Result};
 use std::path::PathBuf;
 use std::sync::Mutex;
 
@@ -27,7 +28,7 @@ impl Database {
     }
 
     fn create_tables(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.conn.lock().unwrap();
         conn.execute_batch(
             "CREATE TABLE IF NOT EXISTS notes (
                 id TEXT PRIMARY KEY,
@@ -44,7 +45,9 @@ impl Database {
                 created_at INTEGER DEFAULT (unixepoch()),
                 updated_at INTEGER DEFAULT (unixepoch()),
                 trashed_at INTEGER,
-                word_count INTEGER DEFAULT 0
+                word_count INTEGER DEFAULT 0,
+                period_type TEXT,
+                period_date TEXT
             );
 
             CREATE INDEX IF NOT EXISTS idx_notes_parent ON notes(parent_id);
@@ -57,6 +60,7 @@ impl Database {
                 id TEXT PRIMARY KEY,
                 name TEXT NOT NULL,
                 color TEXT DEFAULT '#6366f1',
+                parent_id TEXT REFERENCES tags(id) ON DELETE SET NULL,
                 created_at INTEGER DEFAULT (unixepoch())
             );
 
@@ -71,16 +75,18 @@ impl Database {
 
             CREATE INDEX IF NOT EXISTS idx_note_tags_tag ON note_tags(tag_id);
 
-            -- Wikilinks table: tracks [[links]] between notes
+            -- Wikilinks table: tracks [[links]] between notes. The raw link
+            -- text is kept so links to missing notes stay with a null target
             CREATE TABLE IF NOT EXISTS wikilinks (
                 source_note_id TEXT NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
-                target_note_id TEXT NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
-                UNIQUE(source_note_id, target_note_id)
+                target_note_id TEXT REFERENCES notes(id) ON DELETE SET NULL,
+                link_text TEXT NOT NULL,
+                anchor TEXT,
+                link_type TEXT NOT NULL DEFAULT 'link',
+                link_title TEXT NOT NULL DEFAULT '',
+                UNIQUE(source_note_id, link_text, link_type)
             );
 
-            CREATE INDEX IF NOT EXISTS idx_wikilinks_source ON wikilinks(source_note_id);
-            CREATE INDEX IF NOT EXISTS idx_wikilinks_target ON wikilinks(target_note_id);
-
             -- FTS5 virtual table for full-text search
             CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
                 title, plain_text, content='notes', content_rowid='rowid'
@@ -161,9 +167,444 @@ impl Database {
             );",
         )?;
 
+        // Migrate title-resolved wikilinks to the id + link text schema
+        let has_link_text: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('wikilinks') WHERE name = 'link_text'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_link_text {
+            let tx = conn.transaction()?;
+            tx.execute_batch(
+                "CREATE TABLE wikilinks_new (
+                     source_note_id TEXT NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+                     target_note_id TEXT REFERENCES notes(id) ON DELETE SET NULL,
+                     link_text TEXT NOT NULL,
+                     anchor TEXT,
+                     link_type TEXT NOT NULL DEFAULT 'link',
+                     link_title TEXT NOT NULL DEFAULT '',
+                     UNIQUE(source_note_id, link_text, link_type)
+                 );
+                 INSERT OR IGNORE INTO wikilinks_new
+                     (source_note_id, target_note_id, link_text, link_title)
+                     SELECT w.source_note_id, w.target_note_id, n.title, n.title
+                     FROM wikilinks w JOIN notes n ON n.id = w.target_note_id;
+                 DROP TABLE wikilinks;
+                 ALTER TABLE wikilinks_new RENAME TO wikilinks;",
+            )?;
+            tx.commit()?;
+        }
+
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_wikilinks_source ON wikilinks(source_note_id);
+             CREATE INDEX IF NOT EXISTS idx_wikilinks_target ON wikilinks(target_note_id);
+             CREATE INDEX IF NOT EXISTS idx_wikilinks_unresolved
+                 ON wikilinks(LOWER(link_title)) WHERE target_note_id IS NULL;",
+        )?;
+
+        // Alternative names a note answers to in wikilinks and search
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS note_aliases (
+                note_id TEXT NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+                alias TEXT NOT NULL,
+                created_at INTEGER DEFAULT (unixepoch())
+            );
+
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_note_aliases_alias ON note_aliases(LOWER(alias));
+            CREATE INDEX IF NOT EXISTS idx_note_aliases_note ON note_aliases(note_id);",
+        )?;
+
+        // Headings and ^block ids that [[Note#anchor]] links can target;
+        // indexed from every note's content on first run
+        let note_anchors_exists: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'note_anchors'",
+            [],
+            |row| row.get(0),
+        )?;
+        let tx = conn.transaction()?;
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS note_anchors (
+                note_id TEXT NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+                kind TEXT NOT NULL,
+                anchor TEXT NOT NULL,
+                text TEXT NOT NULL DEFAULT '',
+                level INTEGER,
+                position INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_note_anchors_note ON note_anchors(note_id);",
+        )?;
+        if !note_anchors_exists {
+            backfill_note_anchors(&tx)?;
+        }
+        tx.commit()?;
+
+        // Nested tags: `a/b` points at its parent `a`
+        let tags_have_parent: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('tags') WHERE name = 'parent_id'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !tags_have_parent {
+            let tx = conn.transaction()?;
+            tx.execute_batch(
+                "ALTER TABLE tags ADD COLUMN parent_id TEXT REFERENCES tags(id) ON DELETE SET NULL;",
+            )?;
+            backfill_tag_hierarchy(&tx)?;
+            tx.commit()?;
+        }
+        conn.execute_batch("CREATE INDEX IF NOT EXISTS idx_tags_parent ON tags(parent_id);")?;
+
+        // Typed note properties and the registry fixing each key's type
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS property_schemas (
+                key TEXT PRIMARY KEY COLLATE NOCASE,
+                type TEXT NOT NULL,
+                created_at INTEGER DEFAULT (unixepoch())
+            );
+
+            CREATE TABLE IF NOT EXISTS note_properties (
+                note_id TEXT NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+                key TEXT NOT NULL COLLATE NOCASE,
+                value TEXT NOT NULL,
+                position INTEGER NOT NULL DEFAULT 0,
+                updated_at INTEGER DEFAULT (unixepoch()),
+                PRIMARY KEY (note_id, key)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_note_properties_key ON note_properties(key);",
+        )?;
+
+        // To-do blocks extracted from note content; populated on first run
+        let tasks_exists: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'tasks'",
+            [],
+            |row| row.get(0),
+        )?;
+        let tx = conn.transaction()?;
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS tasks (
+                id TEXT PRIMARY KEY,
+                note_id TEXT NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+                block_id TEXT,
+                position INTEGER NOT NULL DEFAULT 0,
+                text TEXT NOT NULL DEFAULT '',
+                checked INTEGER NOT NULL DEFAULT 0,
+                due_date TEXT,
+                priority TEXT
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_tasks_note ON tasks(note_id);
+            CREATE INDEX IF NOT EXISTS idx_tasks_due ON tasks(due_date);",
+        )?;
+        if !tasks_exists {
+            backfill_note_tasks(&tx)?;
+        }
+        tx.commit()?;
+
+        // Periodic notes record their period on the note; date-titled
+        // daily notes from before this are adopted
+        let notes_have_period: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('notes') WHERE name = 'period_type'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !notes_have_period {
+            let tx = conn.transaction()?;
+            tx.execute_batch(
+                "ALTER TABLE notes ADD COLUMN period_type TEXT;
+                 ALTER TABLE notes ADD COLUMN period_date TEXT;
+                 UPDATE notes SET period_type = 'day', period_date = title
+                 WHERE is_folder = 0 AND emoji = '📅' AND date(title) = title;",
+            )?;
+            tx.commit()?;
+        }
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_notes_period ON notes(period_type, period_date);
+
+            CREATE TABLE IF NOT EXISTS periodic_note_settings (
+                period_type TEXT PRIMARY KEY,
+                title_format TEXT NOT NULL,
+                folder_id TEXT REFERENCES notes(id) ON DELETE SET NULL,
+                template_id TEXT REFERENCES templates(id) ON DELETE SET NULL
+            );",
+        )?;
+        // User-defined note templates with `{{...}}` variables, starting out
+        // with the built-in ones
+        let templates_exist: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'templates'",
+            [],
+            |row| row.get(0),
+        )?;
+        let tx = conn.transaction()?;
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS templates (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                emoji TEXT NOT NULL DEFAULT '📝',
+                description TEXT NOT NULL DEFAULT '',
+                content TEXT NOT NULL DEFAULT '[]',
+                created_at INTEGER DEFAULT (unixepoch()),
+                updated_at INTEGER DEFAULT (unixepoch())
+            );",
+        )?;
+        if !templates_exist {
+            seed_builtin_templates(&tx)?;
+        }
+        tx.commit()?;
+
+        // App-wide settings, e.g. the timezone days are bucketed in
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS app_settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );",
+        )?;
+
+        // One row per flashcard review. Cards are recreated on every sync, so
+        // the log hangs off the note rather than the card.
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS flashcard_reviews (
+                id INTEGER PRIMARY KEY,
+                card_id TEXT NOT NULL,
+                note_id TEXT NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+                rating INTEGER NOT NULL,
+                reviewed_at INTEGER NOT NULL DEFAULT (unixepoch())
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_flashcard_reviews_at ON flashcard_reviews(reviewed_at);",
+        )?;
+
+        // Reminders on notes or tasks, fired by the background scheduler
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS reminders (
+                id TEXT PRIMARY KEY,
+                note_id TEXT NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+                task_id TEXT,
+                title TEXT NOT NULL DEFAULT '',
+                remind_at INTEGER NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                fired_at INTEGER,
+                snooze_count INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER DEFAULT (unixepoch())
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_reminders_due ON reminders(status, remind_at);
+            CREATE INDEX IF NOT EXISTS idx_reminders_note ON reminders(note_id);",
+        )?;
+
+        // FTS5 index over snippets; populated from existing rows on first run
+        let snippets_fts_exists: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'snippets_fts'",
+            [],
+            |row| row.get(0),
+        )?;
+
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS snippets_fts USING fts5(
+                title, content, tags, content='snippets', content_rowid='rowid'
+            );
+
+            CREATE TRIGGER IF NOT EXISTS snippets_ai AFTER INSERT ON snippets BEGIN
+                INSERT INTO snippets_fts(rowid, title, content, tags)
+                VALUES (new.rowid, new.title, new.content, new.tags);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS snippets_ad AFTER DELETE ON snippets BEGIN
+                INSERT INTO snippets_fts(snippets_fts, rowid, title, content, tags)
+                VALUES ('delete', old.rowid, old.title, old.content, old.tags);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS snippets_au AFTER UPDATE ON snippets BEGIN
+                INSERT INTO snippets_fts(snippets_fts, rowid, title, content, tags)
+                VALUES ('delete', old.rowid, old.title, old.content, old.tags);
+                INSERT INTO snippets_fts(rowid, title, content, tags)
+                VALUES (new.rowid, new.title, new.content, new.tags);
+            END;",
+        )?;
+
+        if !snippets_fts_exists {
+            conn.execute_batch("INSERT INTO snippets_fts(snippets_fts) VALUES ('rebuild');")?;
+        }
+
+        // Revision snapshots taken before backend-side rewrites of note content
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS note_revisions (
+                id TEXT PRIMARY KEY,
+                note_id TEXT NOT NULL REFERENCES notes(id) ON DELETE CASCADE,
+                batch_id TEXT,
+                reason TEXT NOT NULL DEFAULT 'edit',
+                title TEXT NOT NULL,
+                content TEXT,
+                plain_text TEXT,
+                -- The note's content right after the batch rewrite that followed
+                applied_content TEXT,
+                created_at INTEGER DEFAULT (unixepoch())
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_note_revisions_note ON note_revisions(note_id);
+            CREATE INDEX IF NOT EXISTS idx_note_revisions_batch ON note_revisions(batch_id);",
+        )?;
+
+        // Search history and the term vocabulary used for query suggestions
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS search_history (
+                id TEXT PRIMARY KEY,
+                query TEXT NOT NULL,
+                result_count INTEGER DEFAULT 0,
+                searched_at INTEGER DEFAULT (unixepoch()),
+                clicked_result_id TEXT,
+                clicked_result_kind TEXT,
+                clicked_at INTEGER
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_search_history_searched ON search_history(searched_at);
+
+            CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts_vocab USING fts5vocab(notes_fts, 'row');",
+        )?;
+
         // Add columns if they don't exist (migration for existing DBs)
         let _ = conn.execute_batch("ALTER TABLE notes ADD COLUMN is_pinned INTEGER DEFAULT 0;");
 
         Ok(())
     }
 }
+
+/// One-off seed when the `templates` table is first created: the
+/// templates that used to be built into the frontend.
+fn seed_builtin_templates(conn: &Connection) -> Result<()> {
+    use serde_json::json;
+    let h1 = |text: &str| json!({ "type": "h1", "children": [{ "text": text }] });
+    let h2 = |text: &str| json!({ "type": "h2", "children": [{ "text": text }] });
+    let p = |text: &str| json!({ "type": "p", "children": [{ "text": text }] });
+    let ul = |items: &[&str]| {
+        let items: Vec<serde_json::Value> = items
+            .iter()
+            .map(|text| {
+                let lic = json!({ "type": "lic", "children": [{ "text": text }] });
+                json!({ "type": "li", "children": [lic] })
+            })
+            .collect();
+        json!({ "type": "ul", "children": items })
+    };
+
+    let builtins = [
+        (
+            "meeting-notes",
+            "Meeting Notes",
+            "📋",
+            "Date, attendees, agenda, action items",
+            json!([
+                h1("Meeting Notes — {{date}}"),
+                h2("Attendees"),
+                ul(&["Name 1", "Name 2"]),
+                h2("Agenda"),
+                ul(&["Topic 1", "Topic 2"]),
+                h2("Discussion"),
+                p(""),
+                h2("Action Items"),
+                ul(&["[ ] Action item 1", "[ ] Action item 2"]),
+            ]),
+        ),
+        (
+            "journal-entry",
+            "Journal Entry",
+            "📔",
+            "Date, mood, highlights, gratitude, reflection",
+            json!([
+                h1("Journal — {{date}}"),
+                h2("Mood"),
+                p("How am I feeling today? "),
+                h2("Highlights"),
+                ul(&["Highlight 1"]),
+                h2("Gratitude"),
+                ul(&["I'm grateful for..."]),
+                h2("Reflection"),
+                p(""),
+            ]),
+        ),
+        (
+            "project-plan",
+            "Project Plan",
+            "🎯",
+            "Overview, goals, milestones, tasks, resources",
+            json!([
+                h1("Project Plan"),
+                h2("Overview"),
+                p("Brief description of the project..."),
+                h2("Goals"),
+                ul(&["Goal 1", "Goal 2"]),
+                h2("Milestones"),
+                ul(&["[ ] Milestone 1 — Target date"]),
+                h2("Tasks"),
+                ul(&["[ ] Task 1"]),
+                h2("Resources"),
+                p(""),
+            ]),
+        ),
+        (
+            "weekly-review",
+            "Weekly Review",
+            "📊",
+            "Accomplishments, challenges, next week goals",
+            json!([
+                h1("Weekly Review — {{date}}"),
+                h2("Accomplishments"),
+                ul(&["What I accomplished this week..."]),
+                h2("Challenges"),
+                ul(&["What was difficult..."]),
+                h2("Lessons Learned"),
+                p(""),
+                h2("Next Week Goals"),
+                ul(&["[ ] Goal 1"]),
+            ]),
+        ),
+        (
+            "reading-notes",
+            "Reading Notes",
+            "📚",
+            "Title, author, key ideas, quotes, takeaways",
+            json!([
+                h1("Reading Notes"),
+                json!({ "type": "p", "children": [
+                    { "text": "", "bold": true },
+                    { "text": "Title: " },
+                    { "text": "" }
+                ] }),
+                json!({ "type": "p", "children": [{ "text": "Author: " }, { "text": "" }] }),
+                h2("Key Ideas"),
+                ul(&["Idea 1"]),
+                h2("Quotes"),
+                json!({ "type": "blockquote", "children": [{ "text": "A memorable quote..." }] }),
+                h2("Takeaways"),
+                ul(&["Takeaway 1"]),
+            ]),
+        ),
+        (
+            "decision-log",
+            "Decision Log",
+            "⚖️",
+            "Context, options, decision, reasoning",
+            json!([
+                h1("Decision Log — {{date}}"),
+                h2("Context"),
+                p("What situation requires a decision?"),
+                h2("Options"),
+                ul(&["Option A — Pros/Cons", "Option B — Pros/Cons"]),
+                h2("Decision"),
+                p("We decided to..."),
+                h2("Reasoning"),
+                p("Because..."),
+            ]),
+        ),
+    ];
+
+    for (id, name, emoji, description, content) in builtins {
+        conn.execute(
+            "INSERT OR IGNORE INTO templates (id, name, emoji, description, content)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, name, emoji, description, content.to_string()],
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,397 @@
+//! Indexing that derives rows from note content: tag hierarchy, heading and
+//! block anchors, and tasks. Shared by the commands that save notes and by
+//! the migrations in `db` that backfill newly added tables.
+
+use rusqlite::params;
+use serde::Serialize;
+use uuid::Uuid;
+
+// ─── Plate Content ───────────────────────────────────────
+
+/// Mirrors `extractPlainText` in `src/lib/extract.ts`: leaf text is
+/// concatenated and every block element except links/mentions ends a line.
+pub(crate) fn plate_plain_text(nodes: &[serde_json::Value]) -> String {
+    fn walk(node: &serde_json::Value, out: &mut String) {
+        if !node.is_object() {
+            return;
+        }
+        if let Some(text) = node.get("text").and_then(|t| t.as_str()) {
+            out.push_str(text);
+            return;
+        }
+        if let Some(children) = node.get("children").and_then(|c| c.as_array()) {
+            for child in children {
+                walk(child, out);
+            }
+        }
+        if let Some(kind) = node.get("type").and_then(|t| t.as_str()) {
+            if kind != "a" && kind != "mention" {
+                out.push('\n');
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for node in nodes {
+        walk(node, &mut out);
+    }
+    out.trim().to_string()
+}
+
+// ─── Tags ────────────────────────────────────────────────
+
+/// Canonical form of a tag path: no leading `#`, no empty or padded segments.
+pub(crate) fn normalize_tag_path(name: &str) -> Option<String> {
+    let path = name
+        .trim()
+        .trim_start_matches('#')
+        .split('/')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+    (!path.is_empty()).then_some(path)
+}
+
+/// Look up a tag by path, creating it and any missing ancestors so that
+/// `a/b/c` always hangs off `a/b` and `a`. Returns the id of the leaf tag.
+pub(crate) fn ensure_tag(conn: &rusqlite::Connection, name: &str) -> rusqlite::Result<String> {
+    let path = normalize_tag_path(name).unwrap_or_else(|| name.to_string());
+    let mut parent_id: Option<String> = None;
+    let mut prefix = String::new();
+    for segment in path.split('/') {
+        if !prefix.is_empty() {
+            prefix.push('/');
+        }
+        prefix.push_str(segment);
+
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (id, name, parent_id) VALUES (?1, ?2, ?3)",
+            params![Uuid::new_v4().to_string(), prefix, parent_id],
+        )?;
+        let tag_id: String = conn.query_row(
+            "SELECT id FROM tags WHERE name = ?1",
+            params![prefix],
+            |row| row.get(0),
+        )?;
+        // Rows created before nesting was tracked have no parent yet.
+        if let Some(parent) = &parent_id {
+            conn.execute(
+                "UPDATE tags SET parent_id = ?1 WHERE id = ?2 AND parent_id IS NULL",
+                params![parent, tag_id],
+            )?;
+        }
+        parent_id = Some(tag_id);
+    }
+    Ok(parent_id.unwrap_or_default())
+}
+
+/// Link every existing nested tag to its ancestors. Run once when the
+/// `parent_id` column is introduced.
+pub(crate) fn backfill_tag_hierarchy(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    let names: Vec<String> = {
+        let mut stmt = conn.prepare("SELECT name FROM tags WHERE name LIKE '%/%'")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.filter_map(|r| r.ok()).collect()
+    };
+    for name in names {
+        ensure_tag(conn, &name)?;
+    }
+    Ok(())
+}
+
+// ─── Anchors ─────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct NoteAnchor {
+    pub kind: String,
+    pub anchor: String,
+    pub text: String,
+    pub level: Option<i64>,
+    pub position: i64,
+}
+
+/// Walk Plate nodes collecting headings (`h1`–`h6`) and blocks ending in a
+/// ` ^blockid` marker. `position` counts elements in document order so the
+/// editor can scroll to the anchor.
+fn collect_plate_anchors(nodes: &[serde_json::Value]) -> Vec<NoteAnchor> {
+    fn walk(node: &serde_json::Value, position: &mut i64, out: &mut Vec<NoteAnchor>) {
+        let Some(children) = node.get("children").and_then(|c| c.as_array()) else {
+            return;
+        };
+        let pos = *position;
+        *position += 1;
+
+        let has_text = children.iter().any(|c| c.get("text").is_some());
+        if has_text {
+            let text = plate_plain_text(children).trim().to_string();
+            let node_type = node.get("type").and_then(|t| t.as_str()).unwrap_or("");
+            let level = match node_type {
+                "h1" => Some(1),
+                "h2" => Some(2),
+                "h3" => Some(3),
+                "h4" => Some(4),
+                "h5" => Some(5),
+                "h6" => Some(6),
+                _ => None,
+            };
+            let (text, block_id) = split_block_id(&text);
+            if level.is_some() && !text.is_empty() {
+                out.push(NoteAnchor {
+                    kind: "heading".to_string(),
+                    anchor: text.to_string(),
+                    text: text.to_string(),
+                    level,
+                    position: pos,
+                });
+            }
+            if let Some(id) = block_id {
+                out.push(NoteAnchor {
+                    kind: "block".to_string(),
+                    anchor: format!("^{}", id),
+                    text: text.to_string(),
+                    level,
+                    position: pos,
+                });
+            }
+        }
+
+        for child in children {
+            walk(child, position, out);
+        }
+    }
+
+    let mut position = 0;
+    let mut out = Vec::new();
+    for node in nodes {
+        walk(node, &mut position, &mut out);
+    }
+    out
+}
+
+/// Split a trailing ` ^blockid` marker off a block's text.
+pub(crate) fn split_block_id(text: &str) -> (&str, Option<&str>) {
+    static BLOCK_ID_RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let re =
+        BLOCK_ID_RE.get_or_init(|| regex::Regex::new(r"(?:^|\s)\^([A-Za-z0-9-]+)\s*$").unwrap());
+
+    match re.captures(text) {
+        Some(cap) => (
+            text[..cap.get(0).map_or(0, |m| m.start())].trim(),
+            cap.get(1).map(|m| m.as_str()),
+        ),
+        None => (text.trim(), None),
+    }
+}
+
+/// Rebuild a note's rows in `note_anchors` from its Plate JSON.
+pub(crate) fn index_note_anchors(
+    conn: &rusqlite::Connection,
+    note_id: &str,
+    content: &str,
+) -> rusqlite::Result<()> {
+    let nodes: Vec<serde_json::Value> = serde_json::from_str(content).unwrap_or_default();
+
+    conn.execute(
+        "DELETE FROM note_anchors WHERE note_id = ?1",
+        params![note_id],
+    )?;
+    for anchor in collect_plate_anchors(&nodes) {
+        conn.execute(
+            "INSERT INTO note_anchors (note_id, kind, anchor, text, level, position)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                note_id,
+                anchor.kind,
+                anchor.anchor,
+                anchor.text,
+                anchor.level,
+                anchor.position
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// One-off backfill when `note_anchors` is first created: index every note.
+pub(crate) fn backfill_note_anchors(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    let notes: Vec<(String, String)> = {
+        let mut stmt =
+            conn.prepare("SELECT id, COALESCE(content, '[]') FROM notes WHERE is_folder = 0")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        rows
+    };
+    for (id, content) in &notes {
+        index_note_anchors(conn, id, content)?;
+    }
+
+    Ok(())
+}
+
+// ─── Tasks ───────────────────────────────────────────────
+
+/// A to-do block found in a note's Plate JSON.
+pub(crate) struct PlateTask {
+    /// Child indices leading to the block.
+    pub(crate) path: Vec<usize>,
+    /// Identifies the task within its note: the block id if it has one,
+    /// otherwise a hash of its text, so adding or removing other tasks
+    /// doesn't move it. Repeats get a `~2`, `~3`, ... suffix.
+    pub(crate) key: String,
+    pub(crate) block_id: Option<String>,
+    pub(crate) position: usize,
+    pub(crate) text: String,
+    pub(crate) checked: bool,
+    pub(crate) due_date: Option<String>,
+    pub(crate) priority: Option<String>,
+}
+
+/// To-do blocks in document order: indent-list items with
+/// `listStyleType: "todo"` and legacy `action_item` elements.
+pub(crate) fn collect_plate_tasks(nodes: &[serde_json::Value]) -> Vec<PlateTask> {
+    fn walk(nodes: &[serde_json::Value], path: &mut Vec<usize>, tasks: &mut Vec<PlateTask>) {
+        for (i, node) in nodes.iter().enumerate() {
+            let Some(children) = node.get("children").and_then(|c| c.as_array()) else {
+                continue;
+            };
+            path.push(i);
+            let is_todo = node.get("listStyleType").and_then(|t| t.as_str()) == Some("todo")
+                || node.get("type").and_then(|t| t.as_str()) == Some("action_item");
+            if is_todo {
+                let raw = plate_plain_text(children);
+                let (raw, marker) = split_block_id(&raw);
+                let (text, due_date, priority) = parse_task_metadata(raw);
+                let block_id = node
+                    .get("id")
+                    .and_then(|id| id.as_str())
+                    .or(marker)
+                    .map(str::to_string);
+                tasks.push(PlateTask {
+                    path: path.clone(),
+                    key: String::new(),
+                    block_id,
+                    position: tasks.len(),
+                    text,
+                    checked: node
+                        .get("checked")
+                        .and_then(|c| c.as_bool())
+                        .unwrap_or(false),
+                    due_date,
+                    priority,
+                });
+            } else {
+                walk(children, path, tasks);
+            }
+            path.pop();
+        }
+    }
+
+    let mut tasks = Vec::new();
+    walk(nodes, &mut Vec::new(), &mut tasks);
+
+    let mut used: std::collections::HashSet<String> = std::collections::HashSet::new();
+    for task in &mut tasks {
+        let base = match &task.block_id {
+            Some(block_id) => format!("b:{}", block_id),
+            None => format!("t:{:016x}", fnv1a_hash(&task.text.to_lowercase())),
+        };
+        let mut key = base.clone();
+        let mut n = 1;
+        while !used.insert(key.clone()) {
+            n += 1;
+            key = format!("{}~{}", base, n);
+        }
+        task.key = key;
+    }
+    tasks
+}
+
+/// 64-bit FNV-1a. Unlike `DefaultHasher` its output is fixed, so it can be
+/// stored in ids.
+fn fnv1a_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Pull `📅 YYYY-MM-DD` and a priority (`!high`/`!medium`/`!low`, or the
+/// ⏫ 🔼 🔽 markers) out of a task's text.
+fn parse_task_metadata(text: &str) -> (String, Option<String>, Option<String>) {
+    static DUE_RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    static PRIORITY_RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let due_re = DUE_RE.get_or_init(|| regex::Regex::new(r"📅\s*(\d{4}-\d{2}-\d{2})").unwrap());
+    let priority_re = PRIORITY_RE
+        .get_or_init(|| regex::Regex::new(r"(?i)(?:^|\s)!(high|medium|low)\b|(⏫|🔼|🔽)").unwrap());
+
+    let due_date = due_re
+        .captures(text)
+        .map(|cap| cap[1].to_string())
+        .filter(|d| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").is_ok());
+    let priority = priority_re.captures(text).map(|cap| match cap.get(1) {
+        Some(word) => word.as_str().to_lowercase(),
+        None => match &cap[2] {
+            "⏫" => "high",
+            "🔼" => "medium",
+            _ => "low",
+        }
+        .to_string(),
+    });
+
+    let stripped = due_re.replace_all(text, |cap: &regex::Captures| {
+        if chrono::NaiveDate::parse_from_str(&cap[1], "%Y-%m-%d").is_ok() {
+            " ".to_string()
+        } else {
+            cap[0].to_string()
+        }
+    });
+    let stripped = priority_re.replace_all(&stripped, " ");
+    let clean = stripped.split_whitespace().collect::<Vec<_>>().join(" ");
+    (clean, due_date, priority)
+}
+
+pub(crate) fn write_note_task_rows(
+    conn: &rusqlite::Connection,
+    note_id: &str,
+    content: &str,
+) -> rusqlite::Result<()> {
+    let nodes: Vec<serde_json::Value> = serde_json::from_str(content).unwrap_or_default();
+
+    conn.execute("DELETE FROM tasks WHERE note_id = ?1", params![note_id])?;
+    for task in collect_plate_tasks(&nodes) {
+        conn.execute(
+            "INSERT INTO tasks (id, note_id, block_id, position, text, checked, due_date, priority)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                format!("{}/{}", note_id, task.key),
+                note_id,
+                task.block_id,
+                task.position as i64,
+                task.text,
+                task.checked,
+                task.due_date,
+                task.priority
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+/// One-off backfill when `tasks` is first created, which predates reminders.
+pub(crate) fn backfill_note_tasks(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    let notes: Vec<(String, String)> = {
+        let mut stmt =
+            conn.prepare("SELECT id, COALESCE(content, '[]') FROM notes WHERE is_folder = 0")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        rows
+    };
+    for (id, content) in &notes {
+        write_note_task_rows(conn, id, content)?;
+    }
+    Ok(())
+}
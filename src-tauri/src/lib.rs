@@ -1,5 +1,6 @@
 mod commands;
 mod db;
+mod indexing;
 
 use db::Database;
 use tauri::Manager;
@@ -8,6 +9,7 @@ use tauri::Manager;
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .setup(|app| {
             let app_dir = app
                 .path()
@@ -15,22 +17,42 @@ pub fn run() {
                 .expect("failed to get app data dir");
             let database = Database::new(app_dir).expect("failed to initialize database");
             app.manage(database);
+
+            let handle = app.handle().clone();
+            std::thread::spawn(move || commands::run_reminder_scheduler(handle));
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::execute_sql,
             commands::create_note,
-            commands::create_note_from_template,
+            commands::get_templates,
+            commands::get_template,
+            commands::create_template,
+            commands::update_template,
+            commands::delete_template,
+            commands::create_note_with_template,
             commands::get_note,
             commands::save_note_content,
             commands::get_notes_tree,
             commands::get_most_recent_note,
             commands::search_notes,
+            commands::global_search,
+            commands::search_notes_regex,
+            commands::record_search,
+            commands::record_search_click,
+            commands::get_search_history,
+            commands::clear_search_history,
+            commands::get_search_suggestions,
+            commands::find_and_replace,
+            commands::undo_find_and_replace,
+            commands::get_note_revisions,
+            commands::restore_note_revision,
             commands::delete_note,
             commands::get_recent_notes,
             commands::create_folder,
             commands::move_note,
             commands::rename_note,
+            commands::rename_note_with_links,
             commands::toggle_favorite,
             commands::toggle_pin,
             commands::get_favorite_notes,
@@ -39,18 +61,64 @@ pub fn run() {
             commands::restore_note,
             commands::get_all_tags,
             commands::get_note_tags,
+            commands::get_notes_by_tag,
             commands::sync_inline_tags,
             commands::add_manual_tag,
             commands::remove_tag,
+            commands::rename_tag,
+            commands::merge_tags,
+            commands::delete_tag,
+            commands::set_tag_color,
+            commands::purge_unused_tags,
             commands::sync_wikilinks,
             commands::get_backlinks,
+            commands::get_unlinked_mentions,
+            commands::link_mention,
+            commands::get_outgoing_links,
+            commands::get_orphan_notes,
+            commands::get_dead_links,
+            commands::get_link_health,
             commands::get_all_note_titles,
             commands::find_note_by_title,
+            commands::get_note_anchors,
+            commands::resolve_embed,
+            commands::get_note_aliases,
+            commands::add_note_alias,
+            commands::remove_note_alias,
+            commands::set_note_aliases,
+            commands::get_alias_conflicts,
             commands::get_graph_data,
+            commands::get_local_graph,
+            commands::find_shortest_path,
+            commands::get_note_centrality,
+            commands::get_graph_clusters,
             commands::get_or_create_daily_note,
+            commands::get_or_create_periodic_note,
+            commands::get_periodic_note,
+            commands::get_adjacent_periodic_note,
+            commands::get_periodic_note_settings,
+            commands::set_periodic_note_settings,
             commands::export_note_markdown,
+            commands::get_note_properties,
+            commands::set_note_property,
+            commands::delete_note_property,
+            commands::get_property_schemas,
+            commands::set_property_schema,
+            commands::delete_property_schema,
+            commands::export_note_frontmatter,
+            commands::import_note_frontmatter,
+            commands::run_query,
+            commands::get_tasks,
+            commands::toggle_task,
+            commands::create_reminder,
+            commands::get_reminders,
+            commands::snooze_reminder,
+            commands::dismiss_reminder,
+            commands::delete_reminder,
             // New feature commands
             commands::find_related_notes,
+            commands::suggest_tags,
+            commands::accept_tag_suggestion,
             commands::sync_flashcards,
             commands::get_due_flashcards,
             commands::review_flashcard,
@@ -64,9 +132,12 @@ pub fn run() {
             commands::get_snippets,
             commands::delete_snippet,
             commands::search_snippets,
+            commands::get_timezone,
+            commands::set_timezone,
             commands::record_writing_stat,
             commands::get_writing_stats,
             commands::get_notes_by_date_range,
+            commands::get_calendar_days,
             commands::get_kanban_data,
             commands::move_note_to_tag,
         ])
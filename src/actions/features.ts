@@ -10,6 +10,7 @@ import type {
   TagInfo,
 } from "@/db/schema";
 import { invoke } from "@/lib/tauri";
+import { flattenTags } from "@/actions/tags";
 
 // ─── Related Notes ───────────────────────────────────────
 
@@ -220,7 +221,7 @@ export async function moveNoteToTag(
 
 export async function getAllTags(): Promise<TagInfo[]> {
   try {
-    return await invoke<TagInfo[]>("get_all_tags");
+    return flattenTags(await invoke<TagInfo[]>("get_all_tags"));
   } catch {
     console.warn("[dev] getAllTags fallback");
     return [];
@@ -4,6 +4,8 @@ import type {
   Note,
   NoteTreeItem,
   RecentNote,
+  TemplateItem,
+  TemplateNote,
 } from "@/db/schema";
 import { invoke } from "@/lib/tauri";
 
@@ -16,20 +18,35 @@ export async function createNote(parentId?: string | null): Promise<string> {
   }
 }
 
-export async function createNoteFromTemplate(
-  title: string,
-  emoji: string,
-  content: string
-): Promise<string> {
+export async function getTemplates(): Promise<TemplateItem[]> {
   try {
-    return await invoke<string>("create_note_from_template", {
-      title,
-      emoji,
-      content,
+    return await invoke<TemplateItem[]>("get_templates");
+  } catch {
+    console.warn("[dev] getTemplates fallback");
+    return [];
+  }
+}
+
+/** Create a note from a stored template; prompts fall back to their defaults. */
+export async function createNoteWithTemplate(
+  templateId: string,
+  title?: string,
+  prompts?: Record<string, string>
+): Promise<TemplateNote> {
+  try {
+    return await invoke<TemplateNote>("create_note_with_template", {
+      templateId,
+      title: title ?? null,
+      parentId: null,
+      prompts: prompts ?? null,
     });
   } catch {
-    console.warn("[dev] createNoteFromTemplate fallback");
-    return `mock-template-${Date.now()}`;
+    console.warn("[dev] createNoteWithTemplate fallback");
+    return {
+      noteId: `mock-template-${Date.now()}`,
+      title: title ?? "Untitled",
+      cursor: null,
+    };
   }
 }
 
@@ -1,9 +1,12 @@
 import type { SearchResult } from "@/db/schema";
 import { invoke } from "@/lib/tauri";
 
-export async function searchNotes(query: string): Promise<SearchResult[]> {
+export async function searchNotes(
+  query: string,
+  includeSnippets?: boolean
+): Promise<SearchResult[]> {
   try {
-    return await invoke<SearchResult[]>("search_notes", { query });
+    return await invoke<SearchResult[]>("search_notes", { query, includeSnippets });
   } catch {
     console.warn("[dev] searchNotes fallback");
     return [];
@@ -1,11 +1,35 @@
-import type { NoteTagInfo, TagInfo } from "@/db/schema";
+import type { KanbanCard, NoteTagInfo, TagInfo } from "@/db/schema";
 import { invoke } from "@/lib/tauri";
 
-export async function getAllTags(): Promise<TagInfo[]> {
+/** Depth-first list of every tag in a tag tree, parents before children. */
+export function flattenTags(tree: TagInfo[]): TagInfo[] {
+  return tree.flatMap((tag) => [tag, ...flattenTags(tag.children)]);
+}
+
+export async function getTagTree(): Promise<TagInfo[]> {
   try {
     return await invoke<TagInfo[]>("get_all_tags");
   } catch {
-    console.warn("[dev] getAllTags fallback");
+    console.warn("[dev] getTagTree fallback");
+    return [];
+  }
+}
+
+export async function getAllTags(): Promise<TagInfo[]> {
+  return flattenTags(await getTagTree());
+}
+
+export async function getNotesByTag(
+  tagName: string,
+  includeDescendants = true
+): Promise<KanbanCard[]> {
+  try {
+    return await invoke<KanbanCard[]>("get_notes_by_tag", {
+      tagName,
+      includeDescendants,
+    });
+  } catch {
+    console.warn("[dev] getNotesByTag fallback");
     return [];
   }
 }
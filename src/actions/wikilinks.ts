@@ -3,10 +3,11 @@ import { invoke } from "@/lib/tauri";
 
 export async function syncWikilinks(
   noteId: string,
-  targetTitles: string[]
+  targetTitles: string[],
+  plainText?: string
 ): Promise<void> {
   try {
-    await invoke("sync_wikilinks", { noteId, targetTitles });
+    await invoke("sync_wikilinks", { noteId, targetTitles, plainText });
   } catch {
     console.warn("[dev] syncWikilinks fallback");
   }
@@ -48,12 +49,13 @@ export async function getGraphData(): Promise<GraphData> {
   }
 }
 
-export async function getOrCreateDailyNote(date: string): Promise<string> {
+/** `date` is `YYYY-MM-DD`; omit it for today in the app timezone. */
+export async function getOrCreateDailyNote(date?: string): Promise<string> {
   try {
     return await invoke<string>("get_or_create_daily_note", { date });
   } catch {
     console.warn("[dev] getOrCreateDailyNote fallback");
-    return `mock-daily-${date}`;
+    return `mock-daily-${date ?? "today"}`;
   }
 }
 
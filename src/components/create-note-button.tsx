@@ -1,7 +1,9 @@
 import { FileText, FolderPlus, LayoutTemplate } from "lucide-react";
+import { useState } from "react";
 
 import { createFolder } from "@/actions/folders";
-import { createNoteFromTemplate } from "@/actions/notes";
+import { createNoteWithTemplate, getTemplates } from "@/actions/notes";
+import type { TemplateItem } from "@/db/schema";
 import {
   DropdownMenu,
   DropdownMenuContent,
@@ -17,7 +19,6 @@ import {
   SidebarMenuButton,
   SidebarMenuItem,
 } from "@/components/ui/sidebar";
-import { templates } from "@/lib/templates";
 import { refreshSidebar } from "@/lib/store";
 import { createNote } from "@/actions/notes";
 
@@ -26,10 +27,16 @@ export function CreateNoteButton({
 }: {
   onNavigate?: (noteId: string) => void;
 }) {
+  const [templates, setTemplates] = useState<TemplateItem[]>([]);
+
   return (
     <SidebarMenu>
       <SidebarMenuItem>
-        <DropdownMenu>
+        <DropdownMenu
+          onOpenChange={(open) => {
+            if (open) getTemplates().then(setTemplates);
+          }}
+        >
           <DropdownMenuTrigger asChild>
             <SidebarMenuButton className="w-fit px-1.5">
               <div className="flex aspect-square size-5 items-center justify-center rounded-md bg-sidebar-primary text-sidebar-primary-foreground">
@@ -74,12 +81,11 @@ export function CreateNoteButton({
                   <DropdownMenuItem
                     key={template.id}
                     onClick={async () => {
-                      const id = await createNoteFromTemplate(
-                        template.name,
-                        template.emoji,
-                        JSON.stringify(template.content())
+                      const note = await createNoteWithTemplate(
+                        template.id,
+                        template.name
                       );
-                      onNavigate?.(id);
+                      onNavigate?.(note.noteId);
                       refreshSidebar();
                     }}
                   >
@@ -1,7 +1,8 @@
 import { useCallback, useEffect, useRef, useState } from "react";
 
 import { getGraphData } from "@/actions/wikilinks";
-import type { GraphData } from "@/db/schema";
+import type { GraphData, GraphDelta } from "@/db/schema";
+import { listen } from "@/lib/tauri";
 
 interface SimNode {
   id: string;
@@ -57,6 +58,55 @@ export function GraphView({
     edgesRef.current = graphData.edges;
   }, [graphData]);
 
+  // Patch the simulation in place so existing nodes keep their positions.
+  // This view shows notes only, so tag nodes and note→tag edges are skipped.
+  useEffect(() => {
+    const unlisten = listen<GraphDelta>("graph-delta", (delta) => {
+      const removed = new Set(delta.removedNodes);
+      const sameEdge = (a: SimEdge, b: SimEdge) =>
+        a.source === b.source && a.target === b.target && a.edgeType === b.edgeType;
+
+      const nodes = nodesRef.current.filter((n) => !removed.has(n.id));
+      const width = canvasRef.current?.width ?? 800;
+      const height = canvasRef.current?.height ?? 600;
+      for (const added of delta.addedNodes) {
+        if (added.kind !== "note") continue;
+        const existing = nodes.find((n) => n.id === added.id);
+        if (existing) {
+          existing.title = added.title;
+          existing.emoji = added.emoji;
+        } else {
+          nodes.push({
+            id: added.id,
+            title: added.title,
+            emoji: added.emoji,
+            x: Math.random() * width,
+            y: Math.random() * height,
+            vx: 0,
+            vy: 0,
+          });
+        }
+      }
+
+      const edges = edgesRef.current.filter(
+        (e) =>
+          !removed.has(e.source) &&
+          !removed.has(e.target) &&
+          !delta.removedEdges.some((r) => sameEdge(e, r))
+      );
+      for (const added of delta.addedEdges) {
+        if (added.edgeType === "tag") continue;
+        if (!edges.some((e) => sameEdge(e, added))) edges.push(added);
+      }
+
+      nodesRef.current = nodes;
+      edgesRef.current = edges;
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   const simulate = useCallback(() => {
     const nodes = nodesRef.current;
     const edges = edgesRef.current;
@@ -57,7 +57,7 @@ export function PlateEditor({
           await saveNoteContent(noteId, value, title, plainText);
           if (noteId !== "scratch") {
             await syncInlineTags(noteId, inlineTags);
-            await syncWikilinks(noteId, wikilinks);
+            await syncWikilinks(noteId, wikilinks, plainText);
           }
           refreshSidebar();
         });
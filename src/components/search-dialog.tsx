@@ -1,6 +1,7 @@
 import {
   BookOpen,
   CalendarDays,
+  Code,
   Eye,
   FileText,
   FolderPlus,
@@ -106,8 +107,7 @@ export function SearchDialog({
       label: "Open Today's Note",
       icon: <CalendarDays className="mr-2 h-4 w-4 shrink-0" />,
       action: async () => {
-        const today = new Date().toISOString().split("T")[0]!;
-        const id = await getOrCreateDailyNote(today);
+        const id = await getOrCreateDailyNote();
         onNavigate?.(id);
         refreshSidebar();
       },
@@ -175,7 +175,7 @@ export function SearchDialog({
       setResults([]);
       return;
     }
-    searchNotes(debouncedQuery).then(setResults);
+    searchNotes(debouncedQuery, true).then(setResults);
   }, [debouncedQuery]);
 
   const handleSelect = useCallback(
@@ -187,6 +187,19 @@ export function SearchDialog({
     [onNavigate]
   );
 
+  const handleResultSelect = useCallback(
+    (result: SearchResult) => {
+      if (result.kind === "snippet" || !result.noteId) {
+        setOpen(false);
+        setQuery("");
+        setView("snippets");
+      } else {
+        handleSelect(result.noteId);
+      }
+    },
+    [handleSelect]
+  );
+
   const handleCommandSelect = useCallback(
     (cmd: CommandEntry) => {
       setOpen(false);
@@ -207,17 +220,23 @@ export function SearchDialog({
       />
       <CommandList>
         <CommandEmpty>
-          {showResults ? "No notes or commands found." : "Start typing to search..."}
+          {showResults
+            ? "No notes, snippets or commands found."
+            : "Start typing to search..."}
         </CommandEmpty>
         {showResults && results.length > 0 && (
-          <CommandGroup heading="Notes">
+          <CommandGroup heading="Notes & Snippets">
             {results.map((result) => (
               <CommandItem
-                key={result.noteId}
-                onSelect={() => handleSelect(result.noteId)}
-                value={`note-${result.noteId}`}
+                key={`${result.kind}-${result.id}`}
+                onSelect={() => handleResultSelect(result)}
+                value={`${result.kind}-${result.id}`}
               >
-                <FileText className="mr-2 h-4 w-4 shrink-0" />
+                {result.kind === "snippet" ? (
+                  <Code className="mr-2 h-4 w-4 shrink-0" />
+                ) : (
+                  <FileText className="mr-2 h-4 w-4 shrink-0" />
+                )}
                 <div className="flex min-w-0 flex-col">
                   <span
                     className="truncate text-sm"
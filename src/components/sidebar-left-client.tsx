@@ -130,8 +130,7 @@ export function SidebarLeftClient({
     } else if (item.url === "#analytics") {
       setView("analytics");
     } else if (item.url === "#today") {
-      const today = new Date().toISOString().split("T")[0];
-      const id = await getOrCreateDailyNote(today!);
+      const id = await getOrCreateDailyNote();
       onNavigate(id);
       refreshSidebar();
     }
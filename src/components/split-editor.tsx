@@ -58,7 +58,7 @@ function SplitEditorPane({ noteId }: { noteId: string }) {
         startTransition(async () => {
           await saveNoteContent(noteId, value, title, plainText);
           await syncInlineTags(noteId, inlineTags);
-          await syncWikilinks(noteId, wikilinks);
+          await syncWikilinks(noteId, wikilinks, plainText);
           refreshSidebar();
         });
       }, DEBOUNCE_MS);
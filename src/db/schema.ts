@@ -31,16 +31,25 @@ export interface NoteTreeItem {
 
 export interface SearchResult {
   id: string;
-  noteId: string;
+  kind: "note" | "snippet";
   title: string;
   snippet: string;
+  /** Null for snippet results. */
+  noteId: string | null;
 }
 
 export interface TagInfo {
   id: string;
+  /** Full path, e.g. `project/alpha`. */
   name: string;
+  /** Last path segment, e.g. `alpha`. */
+  label: string;
+  parentId: string | null;
   color: string | null;
   noteCount: number;
+  /** Distinct notes tagged with this tag or any of its descendants. */
+  totalCount: number;
+  children: TagInfo[];
 }
 
 export interface NoteTagInfo {
@@ -80,6 +89,14 @@ export interface GraphData {
   edges: GraphEdge[];
 }
 
+/** Payload of the `graph-delta` event emitted after graph-changing commands. */
+export interface GraphDelta {
+  addedNodes: (GraphNode & { kind: string })[];
+  removedNodes: string[];
+  addedEdges: GraphEdge[];
+  removedEdges: GraphEdge[];
+}
+
 export interface NoteTitleItem {
   id: string;
   title: string;
@@ -98,6 +115,27 @@ export interface FavoriteNote {
   emoji: string | null;
 }
 
+// ─── Templates ───────────────────────────────────────────
+
+export interface TemplateItem {
+  id: string;
+  name: string;
+  emoji: string;
+  description: string;
+  /** Plate JSON with `{{...}}` variables in its text. */
+  content: string;
+  prompts: { name: string; default: string | null }[];
+  createdAt: number;
+  updatedAt: number;
+}
+
+export interface TemplateNote {
+  noteId: string;
+  title: string;
+  /** Where `{{cursor}}` was: a path to the text leaf and an offset into it. */
+  cursor: { path: number[]; offset: number } | null;
+}
+
 // ─── Related Notes ───────────────────────────────────────
 
 export interface RelatedNoteItem {
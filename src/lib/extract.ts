@@ -77,9 +77,10 @@ export function extractTitle(nodes: unknown[]): string {
 }
 
 /**
- * Extract #tags from plain text content.
+ * Extract #tags from plain text content. Nested tags keep their full path
+ * (`#project/alpha`).
  */
-const TAG_REGEX = /(?:^|\s)#([a-zA-Z][\w-]{0,49})\b/g;
+const TAG_REGEX = /(?:^|\s)#([a-zA-Z][\w-]{0,49}(?:\/[\w-]{1,50})*)\b/g;
 
 export function extractInlineTags(text: string): string[] {
   const found = new Set<string>();
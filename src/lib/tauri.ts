@@ -27,6 +27,19 @@ export async function invoke<T>(cmd: string, args?: Record<string, unknown>): Pr
   return _invoke<T>(cmd, args);
 }
 
+/** Subscribe to a backend event; resolves to a no-op unlisten outside Tauri. */
+export async function listen<T>(
+  event: string,
+  handler: (payload: T) => void
+): Promise<() => void> {
+  try {
+    const mod = await import("@tauri-apps/api/event");
+    return await mod.listen<T>(event, (e) => handler(e.payload));
+  } catch {
+    return () => {};
+  }
+}
+
 export function isTauriAvailable(): boolean {
   return typeof window !== "undefined" && "__TAURI_INTERNALS__" in window;
 }